@@ -6,7 +6,7 @@ use core::{
     cell::RefCell,
     future::Future,
     ops::DerefMut,
-    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
     task::Poll,
 };
 use critical_section::Mutex;
@@ -26,6 +26,8 @@ static AT_PROGRESS_WAKER: AtomicWaker = AtomicWaker::new();
 /// A pointer to where the data should be written to
 static AT_DATA: Mutex<RefCell<(AtomicPtr<u8>, usize)>> =
     Mutex::new(RefCell::new((AtomicPtr::new(core::ptr::null_mut()), 0)));
+/// The full length of the last response, which can be longer than what fitted in [AT_DATA]
+static AT_RESPONSE_LEN: AtomicUsize = AtomicUsize::new(0);
 /// When the [AT_DATA] is updated, this waker is called so a future can be woken up.
 static AT_DATA_WAKER: AtomicWaker = AtomicWaker::new();
 
@@ -49,6 +51,11 @@ unsafe extern "C" fn at_callback(resp: *const core::ffi::c_char) {
             return;
         }
 
+        AT_RESPONSE_LEN.store(
+            core::ffi::CStr::from_ptr(resp as _).to_bytes().len(),
+            Ordering::SeqCst,
+        );
+
         // Copy the contents
         let mut index = 0;
         while index < *size && *resp.add(index) != 0 {
@@ -70,22 +77,192 @@ unsafe extern "C" fn at_callback(resp: *const core::ffi::c_char) {
 ///
 /// If the `CAP` is too small to contain the entire response, then the string is simply tuncated.
 pub async fn send_at<const CAP: usize>(command: &str) -> Result<ArrayString<CAP>, Error> {
-    SendATFuture {
-        state: Default::default(),
-        command: command.as_bytes(),
-        response: [0; CAP],
-    }
-    .await
+    send_at_bytes(command.as_bytes()).await
 }
 
 /// Same as [send_at], but send a byte array (that must contain ascii chars) instead
 pub async fn send_at_bytes<const CAP: usize>(command: &[u8]) -> Result<ArrayString<CAP>, Error> {
+    let mut response = [0; CAP];
+
     SendATFuture {
         state: Default::default(),
         command,
-        response: [0; CAP],
+        response: &mut response,
     }
-    .await
+    .await?;
+
+    let mut return_string = ArrayString::from_byte_string(&response).unwrap();
+    strip_null_bytes(&mut return_string);
+
+    Ok(return_string)
+}
+
+/// Send an AT command to the modem and check its final result code.
+///
+/// The response is written into `response_buf`.
+/// When the modem answers with `OK`, the response without the final result code is returned.
+/// An `ERROR`, `+CME ERROR: <n>` or `+CMS ERROR: <n>` is returned as [Error::AtError], [Error::CmeError] or [Error::CmsError].
+///
+/// If the response doesn't fit in `response_buf`, then [Error::BufferTooSmall] is returned with the size that's needed.
+///
+/// This waits for the final result code for as long as it takes. Use [send_at_command_with_timeout] to limit that.
+pub async fn send_at_command<'r>(
+    command: &str,
+    response_buf: &'r mut [u8],
+) -> Result<&'r str, Error> {
+    let response_len = SendATFuture {
+        state: Default::default(),
+        command: command.as_bytes(),
+        response: response_buf,
+    }
+    .await?;
+
+    // The future makes sure that the last byte is always a null character
+    if response_len >= response_buf.len() {
+        return Err(Error::BufferTooSmall(Some(response_len + 1)));
+    }
+
+    parse_final_result(core::str::from_utf8(&response_buf[..response_len])?)
+}
+
+/// Same as [send_at_command], but returns [Error::Timeout] if the modem hasn't given the final result code within the timeout.
+///
+/// The modem may still be busy with the command after the timeout, so the next command can fail.
+#[cfg(feature = "embassy-time")]
+pub async fn send_at_command_with_timeout<'r>(
+    command: &str,
+    response_buf: &'r mut [u8],
+    timeout: embassy_time::Duration,
+) -> Result<&'r str, Error> {
+    embassy_time::with_timeout(timeout, send_at_command(command, response_buf))
+        .await
+        .unwrap_or(Err(Error::Timeout))
+}
+
+/// The final result code the modem ended an AT response with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AtResult {
+    Ok,
+    Error,
+    /// `+CME ERROR: <n>`
+    CmeError(u16),
+    /// `+CMS ERROR: <n>`
+    CmsError(u16),
+}
+
+/// The response to an AT command sent with [send_at_raw]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtResponse<'r> {
+    /// The final result code
+    pub result: AtResult,
+    /// The response without the final result code
+    pub body: &'r str,
+}
+
+#[cfg(feature = "defmt")]
+impl<'r> defmt::Format for AtResponse<'r> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "AtResponse {{ result: {}, body: {} }}",
+            self.result,
+            self.body
+        )
+    }
+}
+
+impl<'r> AtResponse<'r> {
+    /// The lines of the response without the final result code. Empty lines are skipped.
+    pub fn lines(&self) -> impl Iterator<Item = &'r str> {
+        self.body
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+    }
+}
+
+/// Send an AT command that this crate doesn't have a function for, and get the raw response.
+///
+/// Unlike [send_at_command], an error answer of the modem is not turned into an [Error],
+/// but returned as the [AtResponse::result].
+/// The response is written into `response_buf`. If it doesn't fit, [Error::BufferTooSmall] is returned with the size that's needed.
+///
+/// This waits for the AT commands that are in progress, also the ones this crate sends itself,
+/// so the command and its response never get mixed up with another one.
+/// The unsolicited notifications like `+CEREG` come in separately and don't end up in the response.
+///
+/// Changing state the crate relies on, like the functional mode with `AT+CFUN`, can confuse e.g. an [crate::LteLink].
+pub async fn send_at_raw<'r>(
+    command: &str,
+    response_buf: &'r mut [u8],
+) -> Result<AtResponse<'r>, Error> {
+    let response_len = SendATFuture {
+        state: Default::default(),
+        command: command.as_bytes(),
+        response: response_buf,
+    }
+    .await?;
+
+    // The future makes sure that the last byte is always a null character
+    if response_len >= response_buf.len() {
+        return Err(Error::BufferTooSmall(Some(response_len + 1)));
+    }
+
+    let (body, result) = split_final_result(core::str::from_utf8(&response_buf[..response_len])?)?;
+
+    Ok(AtResponse { result, body })
+}
+
+/// Same as [send_at_raw], but returns [Error::Timeout] if the modem hasn't answered within the timeout.
+///
+/// The modem may still be busy with the command after the timeout, so the next command can fail.
+#[cfg(feature = "embassy-time")]
+pub async fn send_at_raw_with_timeout<'r>(
+    command: &str,
+    response_buf: &'r mut [u8],
+    timeout: embassy_time::Duration,
+) -> Result<AtResponse<'r>, Error> {
+    embassy_time::with_timeout(timeout, send_at_raw(command, response_buf))
+        .await
+        .unwrap_or(Err(Error::Timeout))
+}
+
+/// Check the final result code of an AT response and strip it off
+pub(crate) fn parse_final_result(response: &str) -> Result<&str, Error> {
+    let (body, result) = split_final_result(response)?;
+
+    match result {
+        AtResult::Ok => Ok(body),
+        AtResult::Error => Err(Error::AtError),
+        AtResult::CmeError(code) => Err(Error::CmeError(code)),
+        AtResult::CmsError(code) => Err(Error::CmsError(code)),
+    }
+}
+
+/// Split an AT response into the body and its final result code
+fn split_final_result(response: &str) -> Result<(&str, AtResult), Error> {
+    let response = response.trim_end();
+    let (body, result) = match response.rfind('\n') {
+        Some(index) => (&response[..index + 1], &response[index + 1..]),
+        None => ("", response),
+    };
+
+    let error_code = |code: &str| code.trim().parse().map_err(|_| Error::UnexpectedAtResponse);
+
+    let result = if result == "OK" {
+        AtResult::Ok
+    } else if result == "ERROR" {
+        AtResult::Error
+    } else if let Some(code) = result.strip_prefix("+CME ERROR:") {
+        AtResult::CmeError(error_code(code)?)
+    } else if let Some(code) = result.strip_prefix("+CMS ERROR:") {
+        AtResult::CmsError(error_code(code)?)
+    } else {
+        return Err(Error::UnexpectedAtResponse);
+    };
+
+    Ok((body, result))
 }
 
 /// Sends a blocking AT command. The non-blocking variants should be preferred, but sometimes it's necessary to
@@ -133,14 +310,15 @@ pub fn send_at_blocking<const CAP: usize>(command: &str) -> Result<ArrayString<C
     Ok(string)
 }
 
-struct SendATFuture<'c, const CAP: usize> {
+struct SendATFuture<'c, 'r> {
     state: SendATState,
     command: &'c [u8],
-    response: [u8; CAP],
+    response: &'r mut [u8],
 }
 
-impl<'c, const CAP: usize> Future for SendATFuture<'c, CAP> {
-    type Output = Result<ArrayString<CAP>, Error>;
+impl<'c, 'r> Future for SendATFuture<'c, 'r> {
+    /// The full length of the response
+    type Output = Result<usize, Error>;
 
     fn poll(
         mut self: core::pin::Pin<&mut Self>,
@@ -160,9 +338,11 @@ impl<'c, const CAP: usize> Future for SendATFuture<'c, CAP> {
                 }
             }
             SendATState::AccessGranted => {
-                // Set the data pointer. This can be done because we are pinned
+                // Set the data pointer. This can be done because the buffer is borrowed for the lifetime of the future
                 critical_section::with(|cs| {
-                    *AT_DATA.borrow_ref_mut(cs) = (AtomicPtr::new(self.response.as_mut_ptr()), CAP)
+                    let capacity = self.response.len();
+                    *AT_DATA.borrow_ref_mut(cs) =
+                        (AtomicPtr::new(self.response.as_mut_ptr()), capacity)
                 });
                 AT_DATA_WAKER.register(cx.waker());
 
@@ -201,10 +381,7 @@ impl<'c, const CAP: usize> Future for SendATFuture<'c, CAP> {
                         *last = 0
                     }
 
-                    let mut return_string = ArrayString::from_byte_string(&self.response).unwrap();
-                    strip_null_bytes(&mut return_string);
-
-                    Poll::Ready(Ok(return_string))
+                    Poll::Ready(Ok(AT_RESPONSE_LEN.load(Ordering::SeqCst)))
                 } else {
                     AT_DATA_WAKER.register(cx.waker());
                     Poll::Pending
@@ -214,7 +391,7 @@ impl<'c, const CAP: usize> Future for SendATFuture<'c, CAP> {
     }
 }
 
-impl<'c, const CAP: usize> Drop for SendATFuture<'c, CAP> {
+impl<'c, 'r> Drop for SendATFuture<'c, 'r> {
     fn drop(&mut self) {
         match self.state {
             SendATState::WaitingOnAccess => {}
@@ -251,3 +428,36 @@ fn strip_null_bytes<const CAP: usize>(string: &mut ArrayString<CAP>) {
         string.truncate(index);
     }
 }
+
+/// Split the parameters of an AT response at every comma that is not inside a quoted string.
+/// The surrounding whitespace and quotes are removed from the parameters.
+pub(crate) fn split_parameters(parameters: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(parameters);
+
+    core::iter::from_fn(move || {
+        let parameters = rest?;
+
+        let mut in_quotes = false;
+        let end = parameters.char_indices().find_map(|(index, c)| match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                None
+            }
+            ',' if !in_quotes => Some(index),
+            _ => None,
+        });
+
+        let parameter = match end {
+            Some(end) => {
+                rest = Some(&parameters[end + 1..]);
+                &parameters[..end]
+            }
+            None => {
+                rest = None;
+                parameters
+            }
+        };
+
+        Some(parameter.trim().trim_matches('"'))
+    })
+}
@@ -1,6 +1,7 @@
 use crate::{
     error::{Error, ErrorSource},
     waker_node_list::{WakerNode, WakerNodeList},
+    RegistrationStatus,
 };
 use arrayvec::{ArrayString, ArrayVec};
 use core::{cell::RefCell, marker::PhantomPinned, task::Poll};
@@ -20,6 +21,13 @@ pub(crate) unsafe extern "C" fn at_notification_handler(notif: *const core::ffi:
             .unwrap()
     );
 
+    // Losing the network registration makes the waiting socket operations fail right away
+    if let Ok(notification) = core::ffi::CStr::from_ptr(notif as *const _).to_str() {
+        if let Ok(status) = RegistrationStatus::parse_notification(notification) {
+            crate::socket::update_network_registration(status.state.is_registered());
+        }
+    }
+
     critical_section::with(|cs| {
         WAKER_NODE_LIST
             .borrow_ref_mut(cs)
@@ -35,22 +43,39 @@ pub(crate) fn initialize() -> Result<(), Error> {
     Ok(())
 }
 
-/// An async stream of all AT notifications.
+/// An async stream of AT notifications.
 ///
 /// Implements the [futures::Stream] trait for polling.
+///
+/// There can be any amount of streams at the same time and every stream receives its own copy of each notification.
+/// Up to `COUNT` notifications of `CAP` bytes are buffered while the stream is not being polled.
+/// When the buffer is full, new notifications are dropped.
 pub struct AtNotificationStream<const CAP: usize, const COUNT: usize> {
-    buffer: ArrayVec<ArrayString<CAP>, COUNT>,
+    buffer: NotificationQueue<CAP, COUNT>,
     waker_node: Option<WakerNode<dyn NotificationBuffer>>,
     _phantom: PhantomPinned,
 }
 
 impl<const CAP: usize, const COUNT: usize> AtNotificationStream<CAP, COUNT> {
-    /// Creates a new stream that receives AT notifications.
+    /// Creates a new stream that receives all AT notifications.
     ///
     /// To access all the nice iterator functions, use [futures::StreamExt].
     pub async fn new() -> Self {
+        Self::with_prefix("").await
+    }
+
+    /// Creates a new stream that only receives the AT notifications that start with the given prefix,
+    /// e.g. `+CEREG:` or `%XTIME:`.
+    ///
+    /// The other notifications don't take up space in the buffer.
+    ///
+    /// To access all the nice iterator functions, use [futures::StreamExt].
+    pub async fn with_prefix(prefix: &'static str) -> Self {
         Self {
-            buffer: Default::default(),
+            buffer: NotificationQueue {
+                prefix,
+                notifications: ArrayVec::new(),
+            },
             waker_node: None,
             _phantom: Default::default(),
         }
@@ -104,8 +129,8 @@ impl<const CAP: usize, const COUNT: usize> futures::Stream for AtNotificationStr
         }
 
         critical_section::with(|_| {
-            if !this.buffer.is_empty() {
-                Poll::Ready(Some(this.buffer.remove(0)))
+            if !this.buffer.notifications.is_empty() {
+                Poll::Ready(Some(this.buffer.notifications.remove(0)))
             } else {
                 Poll::Pending
             }
@@ -129,11 +154,23 @@ trait NotificationBuffer {
     fn write(&mut self, notif: *const u8);
 }
 
-impl<const CAP: usize, const COUNT: usize> NotificationBuffer
-    for ArrayVec<ArrayString<CAP>, COUNT>
-{
+/// The buffer of a stream
+struct NotificationQueue<const CAP: usize, const COUNT: usize> {
+    /// Only notifications starting with this prefix are stored
+    prefix: &'static str,
+    notifications: ArrayVec<ArrayString<CAP>, COUNT>,
+}
+
+impl<const CAP: usize, const COUNT: usize> NotificationBuffer for NotificationQueue<CAP, COUNT> {
     fn write(&mut self, mut notif: *const u8) {
-        if self.is_full() {
+        if !unsafe { core::ffi::CStr::from_ptr(notif as *const _) }
+            .to_bytes()
+            .starts_with(self.prefix.as_bytes())
+        {
+            return;
+        }
+
+        if self.notifications.is_full() {
             #[cfg(feature = "defmt")]
             defmt::warn!("Notification buffer is full");
 
@@ -164,6 +201,6 @@ impl<const CAP: usize, const COUNT: usize> NotificationBuffer
             );
         }
 
-        self.push(string);
+        self.notifications.push(string);
     }
 }
@@ -0,0 +1,123 @@
+//! A backoff for retrying operations that fail with a transient error
+
+use crate::{CancellationToken, Error};
+use core::future::Future;
+use embassy_time::Duration;
+
+/// An exponential backoff. The delay starts at the base and doubles after every attempt, up to the maximum.
+///
+/// With jitter, every delay is shortened by a random part, so devices that lost the network at the same time
+/// don't all reconnect at the same time. The randomness is not suitable for anything else.
+///
+/// ```rust,ignore
+/// let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(300)).with_jitter(50, device_id);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    jitter_percent: u8,
+    random_state: u32,
+    attempt: u32,
+}
+
+impl Backoff {
+    /// Create a new backoff without jitter
+    pub const fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            jitter_percent: 0,
+            random_state: 1,
+            attempt: 0,
+        }
+    }
+
+    /// Shorten every delay by up to the given percentage (at most 100).
+    ///
+    /// The seed should be different for every device, e.g. a part of the IMEI or the device ID.
+    pub const fn with_jitter(mut self, percent: u8, seed: u32) -> Self {
+        self.jitter_percent = if percent > 100 { 100 } else { percent };
+        // The xorshift gets stuck at 0
+        self.random_state = seed | 1;
+        self
+    }
+
+    /// The amount of delays that were given since the start or the last [Self::reset]
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Get the delay before the next attempt
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self
+            .base
+            .as_ticks()
+            .saturating_mul(1u64 << self.attempt.min(32))
+            .min(self.max.as_ticks());
+        self.attempt = self.attempt.saturating_add(1);
+
+        let jitter = delay / 100 * self.jitter_percent as u64;
+        let jitter = match jitter {
+            0 => 0,
+            jitter => self.next_random() as u64 % jitter,
+        };
+
+        Duration::from_ticks(delay - jitter)
+    }
+
+    /// Start at the base delay again, e.g. after a connection has worked for a while
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    fn next_random(&mut self) -> u32 {
+        let mut x = self.random_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.random_state = x;
+        x
+    }
+}
+
+/// Call `connect` until it succeeds, waiting for the delay of the backoff after every transient error.
+///
+/// Errors that aren't [Error::is_transient] are returned immediately, and so is [Error::OperationCancelled]
+/// when the token is cancelled. The backoff is reset after a success, so it can be used again for the next reconnect.
+///
+/// This works with everything that can be connected, e.g. a [crate::TcpStream], [crate::UdpSocket] or [crate::DtlsSocket]:
+///
+/// ```rust,ignore
+/// let stream = reconnect_loop(&mut backoff, &token, || {
+///     TcpStream::connect_with_cancellation(addr, &token)
+/// })
+/// .await?;
+/// ```
+pub async fn reconnect_loop<T, F, Fut>(
+    backoff: &mut Backoff,
+    token: &CancellationToken,
+    mut connect: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    loop {
+        token.as_result()?;
+
+        match connect().await {
+            Ok(connection) => {
+                backoff.reset();
+                return Ok(connection);
+            }
+            Err(e) if e.is_transient() => {
+                #[cfg(feature = "defmt")]
+                defmt::debug!("Connect failed with {}, trying again", e);
+
+                token.sleep(backoff.next_delay()).await?;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
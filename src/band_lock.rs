@@ -0,0 +1,153 @@
+//! Implementation of the LTE band lock (`%XBANDLOCK`)
+
+use crate::{at, error::Error};
+use arrayvec::ArrayString;
+use core::fmt::Write;
+
+/// The highest band number the band lock mask of the modem can hold
+pub const MAX_LOCKABLE_BAND: u8 = 88;
+
+/// A set of LTE band numbers, e.g. `[3, 8, 20]`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Bands(u128);
+
+impl Bands {
+    /// Create the set from band numbers.
+    ///
+    /// Returns [Error::InvalidBandLock] if a band is 0 or above [MAX_LOCKABLE_BAND].
+    pub fn new(bands: &[u8]) -> Result<Self, Error> {
+        let mut mask = 0;
+
+        for &band in bands {
+            if !(1..=MAX_LOCKABLE_BAND).contains(&band) {
+                return Err(Error::InvalidBandLock);
+            }
+            mask |= 1 << (band - 1);
+        }
+
+        Ok(Self(mask))
+    }
+
+    pub fn contains(&self, band: u8) -> bool {
+        (1..=MAX_LOCKABLE_BAND).contains(&band) && self.0 & (1 << (band - 1)) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The band numbers in the set, from low to high
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (1..=MAX_LOCKABLE_BAND).filter(|band| self.contains(*band))
+    }
+
+    // The modem takes the mask as a string of bits, with band 1 as the last character
+    fn to_mask(self) -> ArrayString<{ MAX_LOCKABLE_BAND as usize }> {
+        let mut mask = ArrayString::new();
+        for band in (1..=MAX_LOCKABLE_BAND).rev() {
+            mask.push(match self.contains(band) {
+                true => '1',
+                false => '0',
+            });
+        }
+        mask
+    }
+
+    fn from_mask(mask: &str) -> Result<Self, Error> {
+        if mask.len() > MAX_LOCKABLE_BAND as usize {
+            return Err(Error::UnexpectedAtResponse);
+        }
+
+        let mut bands = 0;
+        for (index, bit) in mask.bytes().rev().enumerate() {
+            match bit {
+                b'0' => {}
+                b'1' => bands |= 1 << index,
+                _ => return Err(Error::UnexpectedAtResponse),
+            }
+        }
+
+        Ok(Self(bands))
+    }
+}
+
+/// How long a band lock stays in place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BandLockMode {
+    /// The lock is kept until the modem is turned off
+    Runtime,
+    /// The lock is written to the flash of the modem and kept across restarts.
+    /// A runtime lock takes precedence over it.
+    Permanent,
+}
+
+/// The band locks of the modem, as given by [band_lock]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BandLock {
+    pub permanent: Option<Bands>,
+    pub runtime: Option<Bands>,
+}
+
+/// Only let the modem use the given bands, which speeds up the network search if the bands around are known.
+///
+/// The modem must not be active, e.g. by calling this before the first [crate::LteLink] is created.
+/// Returns [Error::InvalidBandLock] if no band is given.
+/// Bands the modem doesn't support are refused by the modem with an AT error.
+pub async fn lock_bands(bands: &[u8], mode: BandLockMode) -> Result<(), Error> {
+    let bands = Bands::new(bands)?;
+    if bands.is_empty() {
+        return Err(Error::InvalidBandLock);
+    }
+
+    let mut command = ArrayString::<{ MAX_LOCKABLE_BAND as usize + 24 }>::new();
+    write!(
+        command,
+        "AT%XBANDLOCK={},\"{}\"",
+        match mode {
+            BandLockMode::Permanent => 1,
+            BandLockMode::Runtime => 2,
+        },
+        bands.to_mask()
+    )
+    .map_err(|_| Error::BufferTooSmall(None))?;
+
+    at::send_at_command(&command, &mut [0; 32]).await?;
+
+    Ok(())
+}
+
+/// Remove the permanent and runtime band locks, so the modem uses all the bands it supports again
+pub async fn unlock_bands() -> Result<(), Error> {
+    at::send_at_command("AT%XBANDLOCK=0", &mut [0; 32]).await?;
+
+    Ok(())
+}
+
+/// Get the current band locks by querying `AT%XBANDLOCK?`
+pub async fn band_lock() -> Result<BandLock, Error> {
+    let mut buffer = [0; 256];
+    let response = at::send_at_command("AT%XBANDLOCK?", &mut buffer).await?;
+
+    // %XBANDLOCK: "<permanent_mask>","<runtime_mask>"
+    let mut masks = at::split_parameters(
+        response
+            .trim()
+            .strip_prefix("%XBANDLOCK:")
+            .ok_or(Error::UnexpectedAtResponse)?,
+    );
+
+    let mut next_lock = || -> Result<Option<Bands>, Error> {
+        match masks.next() {
+            None | Some("") => Ok(None),
+            Some(mask) => Ok(Some(Bands::from_mask(mask)?).filter(|bands| !bands.is_empty())),
+        }
+    };
+
+    Ok(BandLock {
+        permanent: next_lock()?,
+        runtime: next_lock()?,
+    })
+}
@@ -1,14 +1,22 @@
 use crate::Error;
 use core::{
+    future::Future,
     sync::atomic::{AtomicBool, Ordering},
     task::{Context, Poll},
 };
-use futures::task::AtomicWaker;
+use futures::{future::Either, task::AtomicWaker};
 
 /// A token you can pass to certain async functions that let you cancel them.
 ///
 /// This can be nice for example when you have a task that is 'stuck' receiving data that never arrives,
 /// but you want it to stop doing that so it can continue doing something else.
+///
+/// The token can be shared between tasks or put in a static, and [Self::cancel] can also be called from an interrupt.
+/// The flag is accessed with [Ordering::SeqCst], so everything written before calling [Self::cancel] is visible
+/// to the code that sees [Self::is_cancelled] return true.
+///
+/// A token can only wake one task. Two tokens can't be combined into a new token, and there is no token that
+/// cancels itself after a timeout. Instead, run the operation with [Self::with_parent] or [Self::with_timeout].
 #[derive(Default)]
 pub struct CancellationToken {
     canceled: AtomicBool,
@@ -41,12 +49,15 @@ impl CancellationToken {
     /// Set the token to cancel the operation that uses this token.
     ///
     /// This may not cancel the task immediately because that may not always be possible.
+    /// The operation returns [Error::OperationCancelled] once it has stopped.
     pub fn cancel(&self) {
         self.canceled.store(true, Ordering::SeqCst);
         self.waker.wake();
     }
 
-    /// Returns whether or not the cancel function has been called already
+    /// Returns whether or not the cancel function has been called already.
+    ///
+    /// This can be polled by loops in application code that want to stop at the same time as the operations that use the token.
     pub fn is_cancelled(&self) -> bool {
         self.canceled.load(Ordering::SeqCst)
     }
@@ -58,6 +69,107 @@ impl CancellationToken {
         self.canceled.store(false, Ordering::SeqCst);
     }
 
+    /// Run the future and cancel this token when the `parent` token is cancelled.
+    ///
+    /// This makes it possible to use one token for the operation that can be cancelled by multiple sources:
+    ///
+    /// ```rust,ignore
+    /// let token = CancellationToken::new();
+    /// token
+    ///     .with_parent(&USER_TOKEN, stream.receive_with_cancellation(&mut buf, &token))
+    ///     .await?;
+    /// ```
+    ///
+    /// The parent can only wake one task. So while this runs, the parent must not be given to another operation
+    /// or another [Self::with_parent], otherwise only the last one is woken up when it's cancelled.
+    /// To cancel multiple operations from one source, cancel each of their tokens.
+    pub async fn with_parent<F: Future>(&self, parent: &CancellationToken, future: F) -> F::Output {
+        self.cancel_on(parent.cancelled(), future).await.0
+    }
+
+    /// Run the future and cancel this token when it takes longer than the given timeout.
+    ///
+    /// If the operation is stopped by this timeout, the [Error::OperationCancelled] is turned into [Error::Timeout].
+    /// Can be combined with [Self::with_parent].
+    ///
+    /// ```rust,ignore
+    /// let token = CancellationToken::new();
+    /// token
+    ///     .with_timeout(Duration::from_secs(10), stream.receive_with_cancellation(&mut buf, &token))
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "embassy-time")]
+    pub async fn with_timeout<T, F: Future<Output = Result<T, Error>>>(
+        &self,
+        timeout: embassy_time::Duration,
+        future: F,
+    ) -> Result<T, Error> {
+        match self.run_with_timeout(timeout, future).await {
+            (Err(Error::OperationCancelled), true) => Err(Error::Timeout),
+            (result, _) => result,
+        }
+    }
+
+    /// Run the future and cancel this token when it takes longer than the given timeout.
+    ///
+    /// Returns the output of the future and whether the timeout has cancelled the token.
+    #[cfg(feature = "embassy-time")]
+    pub(crate) async fn run_with_timeout<F: Future>(
+        &self,
+        timeout: embassy_time::Duration,
+        future: F,
+    ) -> (F::Output, bool) {
+        self.cancel_on(embassy_time::Timer::after(timeout), future)
+            .await
+    }
+
+    /// Wait for the given time, unless the token is cancelled before that.
+    ///
+    /// Returns [Error::OperationCancelled] when the token was cancelled.
+    #[cfg(feature = "embassy-time")]
+    pub(crate) async fn sleep(&self, duration: embassy_time::Duration) -> Result<(), Error> {
+        let cancelled = self.cancelled();
+        futures::pin_mut!(cancelled);
+
+        match futures::future::select(embassy_time::Timer::after(duration), cancelled).await {
+            Either::Left(((), _)) => self.as_result(),
+            Either::Right(((), _)) => Err(Error::OperationCancelled),
+        }
+    }
+
+    /// Wait until the token is cancelled
+    pub(crate) async fn cancelled(&self) {
+        core::future::poll_fn(|cx| {
+            self.bind_to_context(cx);
+            match self.is_cancelled() {
+                true => Poll::Ready(()),
+                false => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// Run the future and cancel this token when the trigger finishes first.
+    /// The future is still run to completion so it can clean up after the cancellation.
+    ///
+    /// Returns the output of the future and whether the trigger has cancelled the token.
+    async fn cancel_on<F: Future>(
+        &self,
+        trigger: impl Future<Output = ()>,
+        future: F,
+    ) -> (F::Output, bool) {
+        futures::pin_mut!(trigger);
+        futures::pin_mut!(future);
+
+        match futures::future::select(future, trigger).await {
+            Either::Left((output, _)) => (output, false),
+            Either::Right(((), future)) => {
+                self.cancel();
+                (future.await, true)
+            }
+        }
+    }
+
     /// Creates a result of this type to the `?` operator can be used to return from code.
     ///
     /// It returns an OK if the token hasn't been cancelled yet and an error if it has been cancelled.
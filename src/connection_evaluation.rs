@@ -0,0 +1,159 @@
+//! Implementation of the connection evaluation (`%CONEVAL`)
+
+use crate::{
+    at,
+    error::Error,
+    signal_quality::{convert_rsrp, convert_rsrq, convert_snr},
+};
+
+/// The outcome of a connection evaluation
+///
+/// A failed evaluation is not an error, the modem simply can't give an estimate at that moment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConnectionEvaluation {
+    /// The evaluation was successful
+    Available(ConnectionEstimate),
+    /// There is no cell available
+    NoCellAvailable,
+    /// The UICC (the sim) is not available
+    UiccNotAvailable,
+    /// Only barred cells are available
+    OnlyBarredCells,
+    /// The modem is busy, e.g. with GNSS
+    Busy,
+    /// The evaluation was aborted because of a higher priority operation
+    Aborted,
+    /// The modem is not registered to a network
+    NotRegistered,
+    /// The evaluation failed for an unspecified reason
+    Unspecified,
+}
+
+/// The estimated quality of the connection
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConnectionEstimate {
+    /// True if the modem is in RRC connected state, false if it's idle
+    pub rrc_connected: bool,
+    pub energy_estimate: EnergyEstimate,
+    /// Reference signal received power in dBm. See [crate::SignalQuality] for the conversion.
+    pub rsrp: Option<i16>,
+    /// Reference signal received quality in dB. See [crate::SignalQuality] for the conversion.
+    pub rsrq: Option<f32>,
+    /// Signal to noise ratio in dB. See [crate::SignalQuality] for the conversion.
+    pub snr: Option<i16>,
+    /// The E-UTRAN cell ID
+    pub cell_id: u32,
+    /// Mobile country code
+    pub mcc: u16,
+    /// Mobile network code
+    pub mnc: u16,
+    /// The physical cell ID
+    pub physical_cell_id: u16,
+    /// The E-UTRA absolute radio frequency channel number
+    pub earfcn: u32,
+    /// The LTE band the cell is on
+    pub band: u8,
+    /// True if a tracking area update will be done when the connection is set up.
+    /// `None` if this is not known.
+    pub tau_triggered: Option<bool>,
+}
+
+/// The relative energy that a data transfer is estimated to cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EnergyEstimate {
+    /// Setting up a connection will be difficult and data may need the maximum number of repetitions
+    Bad,
+    /// Poor, the energy consumption is higher than normal
+    Poor,
+    /// Normal energy consumption
+    Normal,
+    /// Good, the energy consumption is lower than normal
+    Good,
+    /// Excellent, the energy consumption is small
+    Excellent,
+}
+
+/// Evaluate the connection to the network with `AT%CONEVAL`.
+///
+/// This can be used before a data transfer to decide whether to send now or to wait for a better moment to save energy.
+pub async fn evaluate_connection() -> Result<ConnectionEvaluation, Error> {
+    let mut buffer = [0; 128];
+    let response = at::send_at_command("AT%CONEVAL", &mut buffer).await?;
+
+    parse(response)
+}
+
+// Parse `%CONEVAL: <result>[,<rrc_state>,<energy_estimate>,<rsrp>,<rsrq>,<snr>,<cell_id>,<plmn>,<phys_cell_id>,<earfcn>,<band>,<tau_triggered>,...]`
+fn parse(response: &str) -> Result<ConnectionEvaluation, Error> {
+    let mut fields = at::split_parameters(
+        response
+            .trim()
+            .strip_prefix("%CONEVAL:")
+            .ok_or(Error::UnexpectedAtResponse)?,
+    );
+
+    let int = |field: Option<&str>, radix: u32| {
+        field
+            .and_then(|field| u32::from_str_radix(field, radix).ok())
+            .ok_or(Error::UnexpectedAtResponse)
+    };
+
+    match int(fields.next(), 10)? {
+        0 => {}
+        1 => return Ok(ConnectionEvaluation::NoCellAvailable),
+        2 => return Ok(ConnectionEvaluation::UiccNotAvailable),
+        3 => return Ok(ConnectionEvaluation::OnlyBarredCells),
+        4 => return Ok(ConnectionEvaluation::Busy),
+        5 => return Ok(ConnectionEvaluation::Aborted),
+        6 => return Ok(ConnectionEvaluation::NotRegistered),
+        _ => return Ok(ConnectionEvaluation::Unspecified),
+    }
+
+    let rrc_connected = int(fields.next(), 10)? == 1;
+    let energy_estimate = match int(fields.next(), 10)? {
+        5 => EnergyEstimate::Bad,
+        6 => EnergyEstimate::Poor,
+        7 => EnergyEstimate::Normal,
+        8 => EnergyEstimate::Good,
+        9 => EnergyEstimate::Excellent,
+        _ => return Err(Error::UnexpectedAtResponse),
+    };
+    let rsrp = convert_rsrp(int(fields.next(), 10)? as u8);
+    let rsrq = convert_rsrq(int(fields.next(), 10)? as u8);
+    let snr = convert_snr(int(fields.next(), 10)? as u8);
+    let cell_id = int(fields.next(), 16)?;
+
+    let plmn = fields
+        .next()
+        .filter(|plmn| plmn.len() >= 5 && plmn.is_ascii())
+        .ok_or(Error::UnexpectedAtResponse)?;
+    let mcc = int(Some(&plmn[..3]), 10)? as u16;
+    let mnc = int(Some(&plmn[3..]), 10)? as u16;
+
+    let physical_cell_id = int(fields.next(), 10)? as u16;
+    let earfcn = int(fields.next(), 10)?;
+    let band = int(fields.next(), 10)? as u8;
+    let tau_triggered = match fields.next() {
+        Some("0") => Some(false),
+        Some("1") => Some(true),
+        _ => None,
+    };
+
+    Ok(ConnectionEvaluation::Available(ConnectionEstimate {
+        rrc_connected,
+        energy_estimate,
+        rsrp,
+        rsrq,
+        snr,
+        cell_id,
+        mcc,
+        mnc,
+        physical_cell_id,
+        earfcn,
+        band,
+        tau_triggered,
+    }))
+}
@@ -0,0 +1,191 @@
+//! Management of the credentials in the key storage of the modem (`%CMNG`).
+//!
+//! The credentials are stored under a security tag, which is what e.g. [crate::DtlsConfig::new] takes.
+//! A security tag can hold one credential of every [CredentialType].
+//!
+//! The modem only allows this while it's offline, so call these functions after [crate::init]
+//! and before the first [crate::LteLink] is created, or after putting the modem offline with
+//! [crate::set_functional_mode]. Otherwise [Error::ModemNotOffline] is returned.
+
+use crate::{at, error::Error, FunctionalMode};
+use arrayvec::ArrayString;
+use core::fmt::Write;
+
+/// The kind of a credential in the key storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CredentialType {
+    /// The root CA certificate that the certificate of the server is checked against
+    RootCaCertificate,
+    /// The certificate the modem offers when the server asks for a client certificate
+    ClientCertificate,
+    /// The private key of the client certificate. It can't be read back.
+    ClientPrivateKey,
+    /// A pre-shared key, written as a hex string
+    Psk,
+    /// The identity that is sent with the pre-shared key
+    PskIdentity,
+}
+
+impl CredentialType {
+    fn as_integer(self) -> u8 {
+        match self {
+            CredentialType::RootCaCertificate => 0,
+            CredentialType::ClientCertificate => 1,
+            CredentialType::ClientPrivateKey => 2,
+            CredentialType::Psk => 3,
+            CredentialType::PskIdentity => 4,
+        }
+    }
+
+    fn from_integer(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(CredentialType::RootCaCertificate),
+            1 => Some(CredentialType::ClientCertificate),
+            2 => Some(CredentialType::ClientPrivateKey),
+            3 => Some(CredentialType::Psk),
+            4 => Some(CredentialType::PskIdentity),
+            _ => None,
+        }
+    }
+}
+
+/// A credential in the key storage, as given by [list]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Credential {
+    pub tag: u32,
+    pub credential_type: CredentialType,
+    /// The SHA-256 of the credential as a hex string, if the modem reports one
+    pub sha256: Option<ArrayString<64>>,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Credential {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Credential {{ tag: {}, credential_type: {}, sha256: {} }}",
+            self.tag,
+            self.credential_type,
+            self.sha256.as_deref()
+        )
+    }
+}
+
+/// Write a credential to the security tag. An existing credential of the same type is replaced.
+///
+/// Certificates and keys are given in PEM format. A [CredentialType::Psk] is given as a hex string.
+///
+/// `CAP` is the size of the internal buffer for the AT command. It must fit the data plus 32 bytes.
+pub async fn write<const CAP: usize>(
+    tag: u32,
+    credential_type: CredentialType,
+    data: &str,
+) -> Result<(), Error> {
+    ensure_offline().await?;
+
+    let mut command = ArrayString::<CAP>::new();
+    write!(
+        command,
+        "AT%CMNG=0,{},{},\"{}\"",
+        tag,
+        credential_type.as_integer(),
+        data
+    )
+    .map_err(|_| Error::BufferTooSmall(None))?;
+
+    at::send_at_command(&command, &mut [0; 32]).await?;
+
+    Ok(())
+}
+
+/// Read a credential of the security tag into the given buffer. The credential is returned without the quotes.
+///
+/// The buffer must fit the credential plus about 100 bytes. A [CredentialType::ClientPrivateKey] can't be read.
+pub async fn read(
+    tag: u32,
+    credential_type: CredentialType,
+    buffer: &mut [u8],
+) -> Result<&str, Error> {
+    ensure_offline().await?;
+
+    let command = command(2, tag, credential_type)?;
+    let response = at::send_at_command(&command, buffer).await?;
+
+    // %CMNG: <sec_tag>,<type>,<sha256>,"<content>"
+    at::split_parameters(
+        response
+            .trim()
+            .strip_prefix("%CMNG:")
+            .ok_or(Error::UnexpectedAtResponse)?,
+    )
+    .nth(3)
+    .ok_or(Error::UnexpectedAtResponse)
+}
+
+/// Delete a credential of the security tag
+pub async fn delete(tag: u32, credential_type: CredentialType) -> Result<(), Error> {
+    ensure_offline().await?;
+
+    let command = command(3, tag, credential_type)?;
+    at::send_at_command(&command, &mut [0; 32]).await?;
+
+    Ok(())
+}
+
+/// List the credentials in the key storage. The response is written into the given buffer.
+///
+/// Every credential takes about 90 bytes of the buffer.
+/// Credentials of a type that isn't a [CredentialType], like the keys of the modem itself, are skipped.
+pub async fn list(buffer: &mut [u8]) -> Result<impl Iterator<Item = Credential> + '_, Error> {
+    ensure_offline().await?;
+
+    let response = at::send_at_command("AT%CMNG=1", buffer).await?;
+
+    Ok(response.lines().filter_map(parse_credential))
+}
+
+// Parse `%CMNG: <sec_tag>,<type>[,<sha256>]`
+fn parse_credential(line: &str) -> Option<Credential> {
+    let mut parameters = at::split_parameters(line.trim().strip_prefix("%CMNG:")?);
+
+    let tag = parameters.next()?.parse().ok()?;
+    let credential_type = CredentialType::from_integer(parameters.next()?.parse().ok()?)?;
+    let sha256 = parameters
+        .next()
+        .filter(|sha256| !sha256.is_empty())
+        .and_then(|sha256| ArrayString::from(sha256).ok());
+
+    Some(Credential {
+        tag,
+        credential_type,
+        sha256,
+    })
+}
+
+fn command(
+    opcode: u8,
+    tag: u32,
+    credential_type: CredentialType,
+) -> Result<ArrayString<32>, Error> {
+    let mut command = ArrayString::new();
+    write!(
+        command,
+        "AT%CMNG={},{},{}",
+        opcode,
+        tag,
+        credential_type.as_integer()
+    )
+    .map_err(|_| Error::BufferTooSmall(None))?;
+
+    Ok(command)
+}
+
+async fn ensure_offline() -> Result<(), Error> {
+    match FunctionalMode::query().await? {
+        FunctionalMode::PowerOff | FunctionalMode::Offline | FunctionalMode::OfflineWithUicc => {
+            Ok(())
+        }
+        _ => Err(Error::ModemNotOffline),
+    }
+}
@@ -0,0 +1,76 @@
+//! Implementation of the connectivity statistics of the modem (`%XCONNSTAT`)
+
+use crate::{at, error::Error};
+
+/// The data that the modem has sent and received since the counting was started with [start_data_usage].
+///
+/// This is measured by the modem, so it includes all IP traffic with its headers and retransmissions,
+/// also of sockets that were opened outside of this crate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DataUsage {
+    pub sms_sent: u32,
+    pub sms_received: u32,
+    pub kilobytes_sent: u32,
+    pub kilobytes_received: u32,
+    /// The size of the biggest packet in bytes
+    pub max_packet_size: u32,
+    /// The average size of the packets in bytes
+    pub average_packet_size: u32,
+}
+
+/// Start counting the data usage of the modem. The counters start at 0.
+///
+/// The counters are kept until the modem is powered off or [stop_data_usage] is called.
+pub async fn start_data_usage() -> Result<(), Error> {
+    at::send_at_command("AT%XCONNSTAT=1", &mut [0; 16]).await?;
+
+    Ok(())
+}
+
+/// Stop counting the data usage of the modem
+pub async fn stop_data_usage() -> Result<(), Error> {
+    at::send_at_command("AT%XCONNSTAT=0", &mut [0; 16]).await?;
+
+    Ok(())
+}
+
+/// Set the data usage counters back to 0, e.g. at the start of a billing period
+pub async fn reset_data_usage() -> Result<(), Error> {
+    stop_data_usage().await?;
+    start_data_usage().await
+}
+
+/// Get the data usage since [start_data_usage] was called, by querying `AT%XCONNSTAT?`
+pub async fn data_usage() -> Result<DataUsage, Error> {
+    let mut buffer = [0; 64];
+    let (
+        sms_sent,
+        sms_received,
+        kilobytes_sent,
+        kilobytes_received,
+        max_packet_size,
+        average_packet_size,
+    ) = at_commands::parser::CommandParser::parse(
+        at::send_at_command("AT%XCONNSTAT?", &mut buffer)
+            .await?
+            .as_bytes(),
+    )
+    .expect_identifier(b"%XCONNSTAT: ")
+    .expect_int_parameter()
+    .expect_int_parameter()
+    .expect_int_parameter()
+    .expect_int_parameter()
+    .expect_int_parameter()
+    .expect_int_parameter()
+    .finish()?;
+
+    Ok(DataUsage {
+        sms_sent: sms_sent as u32,
+        sms_received: sms_received as u32,
+        kilobytes_sent: kilobytes_sent as u32,
+        kilobytes_received: kilobytes_received as u32,
+        max_packet_size: max_packet_size as u32,
+        average_packet_size: average_packet_size as u32,
+    })
+}
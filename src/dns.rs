@@ -1,5 +1,5 @@
 use crate::{ip::NrfSockAddr, lte_link::LteLink, CancellationToken, Error};
-use arrayvec::ArrayString;
+use arrayvec::{ArrayString, ArrayVec};
 use core::str::FromStr;
 use no_std_net::{IpAddr, SocketAddr};
 
@@ -23,6 +23,8 @@ pub async fn get_host_by_name(hostname: &str) -> Result<IpAddr, Error> {
 /// While this function is async, the actual DNS bit is blocking because the modem sadly has no async API for this.
 ///
 /// The modem API is capable of setting the dns server, but that's not yet implemented in this wrapper.
+///
+/// The token can cancel the wait for the LTE link, but not the blocking DNS request itself.
 pub async fn get_host_by_name_with_cancellation(
     hostname: &str,
     token: &CancellationToken,
@@ -40,17 +42,127 @@ pub async fn get_host_by_name_with_cancellation(
         return Err(Error::HostnameNotAscii);
     }
 
+    let found_ips = resolve_ips::<1>(hostname, AddressFamily::Any, token).await?;
+
+    Ok(found_ips[0])
+}
+
+/// Get the IP address that corresponds to the given hostname, or [Error::Timeout] if that takes too long.
+///
+/// The timeout covers the wait for the LTE link, which is where a lookup usually hangs when there is no coverage.
+/// The blocking DNS request itself can't be interrupted, but is limited by the timeout of the modem.
+#[cfg(feature = "embassy-time")]
+pub async fn get_host_by_name_with_timeout(
+    hostname: &str,
+    timeout: embassy_time::Duration,
+) -> Result<IpAddr, Error> {
+    embassy_time::with_timeout(timeout, get_host_by_name(hostname))
+        .await
+        .map_err(|_| Error::Timeout)?
+}
+
+/// The address family that [resolve] asks the DNS server for, or that [crate::TcpConnectConfig::family] connects over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AddressFamily {
+    /// Both IPv4 (A records) and IPv6 (AAAA records) addresses
+    #[default]
+    Any,
+    /// Only IPv4 addresses (A records)
+    Ipv4,
+    /// Only IPv6 addresses (AAAA records)
+    Ipv6,
+}
+
+impl AddressFamily {
+    fn as_nrf_family(self) -> u32 {
+        match self {
+            AddressFamily::Any => nrfxlib_sys::NRF_AF_UNSPEC,
+            AddressFamily::Ipv4 => nrfxlib_sys::NRF_AF_INET,
+            AddressFamily::Ipv6 => nrfxlib_sys::NRF_AF_INET6,
+        }
+    }
+
+    pub(crate) fn matches(self, ip: &IpAddr) -> bool {
+        match self {
+            AddressFamily::Any => true,
+            AddressFamily::Ipv4 => ip.is_ipv4(),
+            AddressFamily::Ipv6 => ip.is_ipv6(),
+        }
+    }
+}
+
+/// The hints that control what [resolve] returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResolveHints {
+    /// The address family to ask for
+    pub family: AddressFamily,
+    /// The port that is set in the returned socket addresses
+    pub port: u16,
+}
+
+/// Get all the socket addresses that correspond to the given hostname, in the order the modem gives them.
+///
+/// At most `MAX_ADDRS` addresses are returned. Others are ignored.
+/// If the hostname is an IP address, only that address is returned (if it matches the family of the hints).
+///
+/// Like [get_host_by_name], the actual DNS bit is blocking.
+pub async fn resolve<const MAX_ADDRS: usize>(
+    hostname: &str,
+    hints: ResolveHints,
+) -> Result<impl Iterator<Item = SocketAddr>, Error> {
+    resolve_with_cancellation::<MAX_ADDRS>(hostname, hints, &Default::default()).await
+}
+
+/// Get all the socket addresses that correspond to the given hostname, in the order the modem gives them.
+///
+/// At most `MAX_ADDRS` addresses are returned. Others are ignored.
+/// If the hostname is an IP address, only that address is returned (if it matches the family of the hints).
+///
+/// Like [get_host_by_name], the actual DNS bit is blocking.
+pub async fn resolve_with_cancellation<const MAX_ADDRS: usize>(
+    hostname: &str,
+    hints: ResolveHints,
+    token: &CancellationToken,
+) -> Result<impl Iterator<Item = SocketAddr>, Error> {
+    #[cfg(feature = "defmt")]
+    defmt::debug!("Resolving dns hostname for \"{}\"", hostname);
+
+    let found_ips = match hostname.parse::<IpAddr>() {
+        Ok(ip) if hints.family.matches(&ip) => core::iter::once(ip).collect(),
+        Ok(_) => return Err(Error::AddressNotFound),
+        Err(_) => {
+            if !hostname.is_ascii() {
+                return Err(Error::HostnameNotAscii);
+            }
+
+            resolve_ips::<MAX_ADDRS>(hostname, hints.family, token).await?
+        }
+    };
+
+    Ok(found_ips
+        .into_iter()
+        .map(move |ip| SocketAddr::new(ip, hints.port)))
+}
+
+/// Do the actual DNS request. Returns at least one address.
+async fn resolve_ips<const MAX_ADDRS: usize>(
+    hostname: &str,
+    family: AddressFamily,
+    token: &CancellationToken,
+) -> Result<ArrayVec<IpAddr, MAX_ADDRS>, Error> {
     token.bind_to_current_task().await;
 
     // Make sure we have a network connection
     let link = LteLink::new().await?;
     link.wait_for_link_with_cancellation(token).await?;
 
-    let mut found_ip = None;
+    let mut found_ips = ArrayVec::new();
 
     unsafe {
         let hints = nrfxlib_sys::nrf_addrinfo {
-            ai_family: nrfxlib_sys::NRF_AF_UNSPEC as _,
+            ai_family: family.as_nrf_family() as _,
             ai_socktype: nrfxlib_sys::NRF_SOCK_STREAM as _,
 
             ai_flags: 0,
@@ -94,7 +206,7 @@ pub async fn get_host_by_name_with_cancellation(
 
         let mut result_iter = result;
 
-        while !result_iter.is_null() && found_ip.is_none() {
+        while !result_iter.is_null() && !found_ips.is_full() {
             let address = (*result_iter).ai_addr;
 
             if (*address).sa_family == nrfxlib_sys::NRF_AF_INET as u16 {
@@ -102,13 +214,13 @@ pub async fn get_host_by_name_with_cancellation(
                     &*(address as *const nrfxlib_sys::nrf_sockaddr_in);
 
                 let socket_addr: SocketAddr = NrfSockAddr::from(*dns_addr).into();
-                found_ip = Some(socket_addr.ip());
+                found_ips.push(socket_addr.ip());
             } else if (*address).sa_family == nrfxlib_sys::NRF_AF_INET6 as u16 {
                 let dns_addr: &nrfxlib_sys::nrf_sockaddr_in6 =
                     &*(address as *const nrfxlib_sys::nrf_sockaddr_in6);
 
                 let socket_addr: SocketAddr = NrfSockAddr::from(*dns_addr).into();
-                found_ip = Some(socket_addr.ip());
+                found_ips.push(socket_addr.ip());
             }
 
             result_iter = (*result_iter).ai_next;
@@ -117,10 +229,10 @@ pub async fn get_host_by_name_with_cancellation(
         // The addrinfo is allocated somewhere so we have to make sure to free it
         nrfxlib_sys::nrf_freeaddrinfo(result);
 
-        if let Some(found_ip) = found_ip {
-            Ok(found_ip)
-        } else {
+        if found_ips.is_empty() {
             Err(Error::AddressNotFound)
+        } else {
+            Ok(found_ips)
         }
     }
 }
@@ -0,0 +1,192 @@
+//! An optional cache for the DNS results
+
+use crate::{dns, AddressFamily, CancellationToken, Error, ResolveHints};
+use arrayvec::{ArrayString, ArrayVec};
+use core::cell::RefCell;
+use critical_section::Mutex;
+use embassy_time::{Duration, Instant};
+use no_std_net::{IpAddr, SocketAddr};
+
+/// The longest hostname that can be cached. Longer hostnames are always resolved.
+const MAX_CACHED_HOSTNAME_LEN: usize = 64;
+
+/// A cache of resolved hostnames, so that repeated connects to the same host don't need a DNS request every time.
+///
+/// The modem doesn't give the TTL of the DNS records, so every entry is kept for the TTL given to [Self::new].
+///
+/// - `ENTRIES`: The amount of hostnames that can be cached. When full, the entry that expires first is replaced.
+/// - `ADDRS`: The amount of addresses that is kept per hostname.
+///
+/// Hostnames longer than 64 characters are not cached.
+///
+/// The cache can be used directly with [Self::resolve] or can be given to [crate::DtlsConfig::dns_cache].
+/// It can be put in a static:
+///
+/// ```rust,ignore
+/// static DNS_CACHE: DnsCache<4, 2> = DnsCache::new(Duration::from_secs(3600));
+/// ```
+pub struct DnsCache<const ENTRIES: usize, const ADDRS: usize> {
+    ttl: Duration,
+    entries: Mutex<RefCell<ArrayVec<DnsCacheEntry<ADDRS>, ENTRIES>>>,
+}
+
+struct DnsCacheEntry<const ADDRS: usize> {
+    hostname: ArrayString<MAX_CACHED_HOSTNAME_LEN>,
+    addresses: ArrayVec<IpAddr, ADDRS>,
+    expires_at: Instant,
+}
+
+impl<const ENTRIES: usize, const ADDRS: usize> DnsCache<ENTRIES, ADDRS> {
+    /// Create a new empty cache that keeps its entries for the given time
+    pub const fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(RefCell::new(ArrayVec::new_const())),
+        }
+    }
+
+    /// Get the socket addresses of the hostname. If the hostname is not in the cache or has expired,
+    /// it's resolved with [crate::resolve] and stored in the cache.
+    pub async fn resolve(
+        &self,
+        hostname: &str,
+        hints: ResolveHints,
+    ) -> Result<impl Iterator<Item = SocketAddr>, Error> {
+        self.resolve_with_cancellation(hostname, hints, &Default::default())
+            .await
+    }
+
+    /// Get the socket addresses of the hostname. If the hostname is not in the cache or has expired,
+    /// it's resolved with [crate::resolve] and stored in the cache.
+    pub async fn resolve_with_cancellation(
+        &self,
+        hostname: &str,
+        hints: ResolveHints,
+        token: &CancellationToken,
+    ) -> Result<impl Iterator<Item = SocketAddr>, Error> {
+        let addresses = match self.get(hostname) {
+            Some(addresses) => addresses,
+            None => {
+                // Always ask for both families, so the entry is complete for any later hints
+                let all_families = ResolveHints {
+                    family: AddressFamily::Any,
+                    ..hints
+                };
+                let addresses: ArrayVec<IpAddr, ADDRS> =
+                    dns::resolve_with_cancellation::<ADDRS>(hostname, all_families, token)
+                        .await?
+                        .map(|addr| addr.ip())
+                        .collect();
+                self.insert(hostname, &addresses);
+                addresses
+            }
+        };
+
+        let mut addresses = addresses
+            .into_iter()
+            .filter(move |ip| hints.family.matches(ip))
+            .map(move |ip| SocketAddr::new(ip, hints.port))
+            .peekable();
+
+        match addresses.peek() {
+            Some(_) => Ok(addresses),
+            None => Err(Error::AddressNotFound),
+        }
+    }
+
+    /// Remove all entries from the cache
+    pub fn flush(&self) {
+        critical_section::with(|cs| self.entries.borrow_ref_mut(cs).clear());
+    }
+
+    /// Remove the entry of the hostname from the cache, e.g. after the connection to its address failed
+    pub fn remove(&self, hostname: &str) {
+        critical_section::with(|cs| {
+            self.entries
+                .borrow_ref_mut(cs)
+                .retain(|entry| !entry.hostname.eq_ignore_ascii_case(hostname))
+        });
+    }
+
+    fn get(&self, hostname: &str) -> Option<ArrayVec<IpAddr, ADDRS>> {
+        let now = Instant::now();
+
+        critical_section::with(|cs| {
+            let mut entries = self.entries.borrow_ref_mut(cs);
+            entries.retain(|entry| entry.expires_at > now);
+
+            entries
+                .iter()
+                .find(|entry| entry.hostname.eq_ignore_ascii_case(hostname))
+                .map(|entry| entry.addresses.clone())
+        })
+    }
+
+    fn insert(&self, hostname: &str, addresses: &[IpAddr]) {
+        let hostname = match ArrayString::from(hostname) {
+            Ok(hostname) => hostname,
+            Err(_) => return,
+        };
+
+        let entry = DnsCacheEntry {
+            hostname,
+            addresses: addresses.iter().copied().take(ADDRS).collect(),
+            expires_at: Instant::now() + self.ttl,
+        };
+
+        critical_section::with(|cs| {
+            let mut entries = self.entries.borrow_ref_mut(cs);
+            entries.retain(|entry| !entry.hostname.eq_ignore_ascii_case(&hostname));
+
+            if entries.is_full() {
+                // Replace the entry that would expire first
+                if let Some(oldest) = entries.iter_mut().min_by_key(|entry| entry.expires_at) {
+                    *oldest = entry;
+                }
+            } else {
+                entries.push(entry);
+            }
+        });
+    }
+}
+
+/// The part of the [DnsCache] that the sockets use, without the const generics
+pub(crate) trait DnsLookup {
+    /// Get the cached address of the hostname
+    fn lookup(&self, hostname: &str) -> Option<IpAddr>;
+    /// Store the resolved address of the hostname, adding it to an entry that hasn't expired yet
+    fn store(&self, hostname: &str, address: IpAddr);
+}
+
+impl<const ENTRIES: usize, const ADDRS: usize> DnsLookup for DnsCache<ENTRIES, ADDRS> {
+    fn lookup(&self, hostname: &str) -> Option<IpAddr> {
+        self.get(hostname)
+            .and_then(|addresses| addresses.first().copied())
+    }
+
+    fn store(&self, hostname: &str, address: IpAddr) {
+        let now = Instant::now();
+
+        let merged = critical_section::with(|cs| {
+            let mut entries = self.entries.borrow_ref_mut(cs);
+            let entry = entries.iter_mut().find(|entry| {
+                entry.expires_at > now && entry.hostname.eq_ignore_ascii_case(hostname)
+            });
+
+            match entry {
+                // Keep the other addresses and the expiry that the entry got when it was resolved
+                Some(entry) => {
+                    if !entry.addresses.contains(&address) {
+                        let _ = entry.addresses.try_push(address);
+                    }
+                    true
+                }
+                None => false,
+            }
+        });
+
+        if !merged {
+            self.insert(hostname, &[address]);
+        }
+    }
+}
@@ -1,18 +1,48 @@
 use crate::{
     dns,
     error::Error,
-    socket::{Socket, SocketFamily, SocketOption, SocketProtocol, SocketType, SplitSocketHandle},
-    CancellationToken,
+    socket::{
+        ReleaseAssistance, Socket, SocketFamily, SocketOption, SocketOptionError, SocketProtocol,
+        SocketStats, SocketType, SplitSocketHandle,
+    },
+    CancellationToken, LteLink, SharedSocket,
 };
 
+#[cfg(feature = "embassy-time")]
+use crate::{dns_cache::DnsLookup, DnsCache};
+use arrayvec::ArrayVec;
+use core::future::Future;
 use no_std_net::SocketAddr;
 
+/// A UDP socket secured with DTLS that is connected to a single peer.
+///
+/// The modem does the DTLS handshake with the peer given to connect, so there is no unconnected mode.
 pub struct DtlsSocket {
     inner: Socket,
 }
 
 macro_rules! impl_receive_from {
     () => {
+        /// Try to fill the given buffer with a received message.
+        /// The part of the buffer that was filled is returned.
+        pub async fn receive<'buf>(&self, buf: &'buf mut [u8]) -> Result<&'buf mut [u8], Error> {
+            self.receive_with_cancellation(buf, &Default::default())
+                .await
+        }
+
+        /// Try to fill the given buffer with a received message.
+        /// The part of the buffer that was filled is returned.
+        pub async fn receive_with_cancellation<'buf>(
+            &self,
+            buf: &'buf mut [u8],
+            token: &CancellationToken,
+        ) -> Result<&'buf mut [u8], Error> {
+            let received_len = self.socket().receive(buf, token).await?;
+            Ok(&mut buf[..received_len])
+        }
+
+        /// Try to fill the given buffer with a received message.
+        /// The part of the buffer that was filled is returned together with the address of the source of the message.
         pub async fn receive_from<'buf>(
             &self,
             buf: &'buf mut [u8],
@@ -21,6 +51,8 @@ macro_rules! impl_receive_from {
                 .await
         }
 
+        /// Try to fill the given buffer with a received message.
+        /// The part of the buffer that was filled is returned together with the address of the source of the message.
         pub async fn receive_from_with_cancellation<'buf>(
             &self,
             buf: &'buf mut [u8],
@@ -29,15 +61,34 @@ macro_rules! impl_receive_from {
             let (received_len, addr) = self.socket().receive_from(buf, token).await?;
             Ok((&mut buf[..received_len], addr))
         }
+
+        /// Wait until there is data to receive, without receiving it.
+        ///
+        /// An error or a closed connection also counts, so the next receive call returns it.
+        pub async fn readable(&self) -> Result<(), Error> {
+            self.readable_with_cancellation(&Default::default()).await
+        }
+
+        /// Wait until there is data to receive, without receiving it.
+        ///
+        /// An error or a closed connection also counts, so the next receive call returns it.
+        pub async fn readable_with_cancellation(
+            &self,
+            token: &CancellationToken,
+        ) -> Result<(), Error> {
+            self.socket().readable(token).await
+        }
     };
 }
 
 macro_rules! impl_send {
     () => {
+        /// Send the given buffer as a single message to the connected peer
         pub async fn send(&self, buf: &[u8]) -> Result<(), Error> {
             self.send_with_cancellation(buf, &Default::default()).await
         }
 
+        /// Send the given buffer as a single message to the connected peer
         pub async fn send_with_cancellation(
             &self,
             buf: &[u8],
@@ -45,10 +96,34 @@ macro_rules! impl_send {
         ) -> Result<(), Error> {
             self.socket().write(buf, token).await.map(|_| ())
         }
+
+        /// Wait until the socket can accept data to send, without sending anything.
+        ///
+        /// An error or a closed connection also counts, so the next send call returns it.
+        pub async fn writable(&self) -> Result<(), Error> {
+            self.writable_with_cancellation(&Default::default()).await
+        }
+
+        /// Wait until the socket can accept data to send, without sending anything.
+        ///
+        /// An error or a closed connection also counts, so the next send call returns it.
+        pub async fn writable_with_cancellation(
+            &self,
+            token: &CancellationToken,
+        ) -> Result<(), Error> {
+            self.socket().writable(token).await
+        }
     };
 }
 
 impl DtlsSocket {
+    /// Connect a DTLS socket to the given hostname and port.
+    ///
+    /// The hostname is resolved with DNS and is also set as the TLS hostname of the socket.
+    /// The modem uses it for the SNI extension and checks the server certificate against it.
+    ///
+    /// If the hostname is an IP address literal, there is no DNS name to check the certificate against,
+    /// so [PeerVerification::Enabled] may fail the handshake.
     pub async fn connect(
         hostname: &str,
         port: u16,
@@ -65,6 +140,13 @@ impl DtlsSocket {
         .await
     }
 
+    /// Connect a DTLS socket to the given hostname and port.
+    ///
+    /// The hostname is resolved with DNS and is also set as the TLS hostname of the socket.
+    /// The modem uses it for the SNI extension and checks the server certificate against it.
+    ///
+    /// If the hostname is an IP address literal, there is no DNS name to check the certificate against,
+    /// so [PeerVerification::Enabled] may fail the handshake.
     pub async fn connect_with_cancellation(
         hostname: &str,
         port: u16,
@@ -72,37 +154,103 @@ impl DtlsSocket {
         security_tags: &[u32],
         token: &CancellationToken,
     ) -> Result<Self, Error> {
-        let inner = Socket::create(
-            SocketFamily::Ipv4,
-            SocketType::Datagram,
-            SocketProtocol::DTls1v2,
-        )
-        .await?;
-        inner.set_option(SocketOption::TlsPeerVerify(peer_verify.as_integer()))?;
-        inner.set_option(SocketOption::TlsSessionCache(0))?;
-        inner.set_option(SocketOption::TlsTagList(security_tags))?;
-        inner.set_option(SocketOption::TlsHostName(hostname))?;
-
-        token.as_result()?;
+        DtlsConfig::new(security_tags)
+            .peer_verify(peer_verify)
+            .cancellation(token)
+            .connect(hostname, port)
+            .await
+    }
 
-        let ip = dns::get_host_by_name_with_cancellation(hostname, token).await?;
-        let addr = SocketAddr::from((ip, port));
+    pub fn as_raw_fd(&self) -> i32 {
+        self.inner.as_raw_fd()
+    }
 
-        unsafe {
-            inner.connect(addr, token).await?;
-        }
+    /// Get the amount of data that has been sent and received on this socket since it was created.
+    ///
+    /// The halves of a split socket add to the same counters.
+    pub fn stats(&self) -> SocketStats {
+        self.inner.stats()
+    }
 
-        Ok(DtlsSocket { inner })
+    /// Tell the network how much more data is expected on this socket, so the modem can go to sleep sooner.
+    ///
+    /// E.g. set [ReleaseAssistance::Last] right before the last send.
+    /// [ReleaseAssistance::NoData] needs a connected socket, otherwise [crate::SocketOptionError::NotConnected] is returned.
+    pub fn set_release_assistance(&self, rai: ReleaseAssistance) -> Result<(), Error> {
+        self.inner.set_release_assistance(rai)
     }
 
-    pub fn as_raw_fd(&self) -> i32 {
-        self.inner.as_raw_fd()
+    /// Delete the cached TLS session of this socket, so the next handshake is a full one.
+    ///
+    /// This is useful after the credentials of the used security tags have been changed.
+    pub fn purge_session_cache(&self) -> Result<(), Error> {
+        self.inner.set_option(SocketOption::TlsSessionCachePurge)?;
+        Ok(())
     }
 
     fn socket(&self) -> &Socket {
         &self.inner
     }
 
+    /// Get in which directions the DTLS connection ID is used on this connection
+    pub fn connection_id_status(&self) -> Result<DtlsConnectionIdStatus, Error> {
+        let status = self.inner.get_int_option(
+            nrfxlib_sys::NRF_SOL_SECURE,
+            nrfxlib_sys::NRF_SO_SEC_DTLS_CID_STATUS,
+        )?;
+
+        match status as u32 {
+            nrfxlib_sys::NRF_SO_SEC_DTLS_CID_STATUS_DISABLED => {
+                Ok(DtlsConnectionIdStatus::Disabled)
+            }
+            nrfxlib_sys::NRF_SO_SEC_DTLS_CID_STATUS_DOWNLINK => {
+                Ok(DtlsConnectionIdStatus::Downlink)
+            }
+            nrfxlib_sys::NRF_SO_SEC_DTLS_CID_STATUS_UPLINK => Ok(DtlsConnectionIdStatus::Uplink),
+            nrfxlib_sys::NRF_SO_SEC_DTLS_CID_STATUS_BIDIRECTIONAL => {
+                Ok(DtlsConnectionIdStatus::Bidirectional)
+            }
+            _ => Err(Error::UnexpectedSocketOptionValue(status)),
+        }
+    }
+
+    /// Get whether the handshake of this connection was a full one or resumed a cached session.
+    ///
+    /// Needs modem firmware 2.0.0 or newer.
+    pub fn handshake_status(&self) -> Result<DtlsHandshakeStatus, Error> {
+        let status = self.inner.get_int_option(
+            nrfxlib_sys::NRF_SOL_SECURE,
+            nrfxlib_sys::NRF_SO_SEC_HANDSHAKE_STATUS,
+        )?;
+
+        match status as u32 {
+            nrfxlib_sys::NRF_SO_SEC_HANDSHAKE_STATUS_FULL => Ok(DtlsHandshakeStatus::Full),
+            nrfxlib_sys::NRF_SO_SEC_HANDSHAKE_STATUS_CACHED => Ok(DtlsHandshakeStatus::Cached),
+            _ => Err(Error::UnexpectedSocketOptionValue(status)),
+        }
+    }
+
+    /// Get the cipher suite that was agreed on in the handshake, as one of the `NRF_TLS_*` values of [nrfxlib_sys].
+    ///
+    /// Needs modem firmware 2.0.0 or newer.
+    pub fn cipher_suite(&self) -> Result<u32, Error> {
+        let cipher_suite = self.inner.get_int_option(
+            nrfxlib_sys::NRF_SOL_SECURE,
+            nrfxlib_sys::NRF_SO_SEC_CIPHERSUITE_USED,
+        )?;
+
+        Ok(cipher_suite as u32)
+    }
+
+    /// Turn the socket into a [SharedSocket] that can be used from multiple tasks
+    pub fn into_shared(self) -> SharedSocket {
+        SharedSocket::new(self.inner, false)
+    }
+
+    /// Split the socket into an owned receive and send half.
+    ///
+    /// The halves are `Send` and `'static`, so they can be moved into separate tasks, e.g. spawned embassy tasks.
+    /// To use both halves in the same task, [Self::split] doesn't need the extra [crate::LteLink] of the second half.
     pub async fn split_owned(self) -> Result<(OwnedDtlsReceiveSocket, OwnedDtlsSendSocket), Error> {
         let (read_split, write_split) = self.inner.split().await?;
 
@@ -124,14 +272,408 @@ impl DtlsSocket {
     impl_receive_from!();
     impl_send!();
 
+    /// Send the request and wait for exactly one response, like a CoAP confirmable message.
+    ///
+    /// When no response comes within the timeout, the request is sent again, up to `retries` times.
+    /// The timeout doubles after every retransmission, like in CoAP. If the last attempt times out too,
+    /// [Error::Timeout] is returned. The part of the response buffer that was filled is returned.
+    ///
+    /// A response that doesn't fit the buffer is truncated, like with [Self::receive].
+    #[cfg(feature = "embassy-time")]
+    pub async fn request_reply<'buf>(
+        &self,
+        request: &[u8],
+        response: &'buf mut [u8],
+        timeout: embassy_time::Duration,
+        retries: u8,
+    ) -> Result<&'buf mut [u8], Error> {
+        self.request_reply_with_cancellation(
+            request,
+            response,
+            timeout,
+            retries,
+            &Default::default(),
+        )
+        .await
+    }
+
+    /// Send the request and wait for exactly one response, like a CoAP confirmable message.
+    ///
+    /// When no response comes within the timeout, the request is sent again, up to `retries` times.
+    /// The timeout doubles after every retransmission, like in CoAP. If the last attempt times out too,
+    /// [Error::Timeout] is returned. The part of the response buffer that was filled is returned.
+    ///
+    /// A response that doesn't fit the buffer is truncated, like with [Self::receive].
+    #[cfg(feature = "embassy-time")]
+    pub async fn request_reply_with_cancellation<'buf>(
+        &self,
+        request: &[u8],
+        response: &'buf mut [u8],
+        timeout: embassy_time::Duration,
+        retries: u8,
+        token: &CancellationToken,
+    ) -> Result<&'buf mut [u8], Error> {
+        let response_len = self
+            .inner
+            .request_reply(request, response, timeout, retries, token)
+            .await?;
+        Ok(&mut response[..response_len])
+    }
+
     /// Deactivates the socket and the LTE link.
     /// A normal drop will do the same thing, but blocking.
+    ///
+    /// The modem library has no `shutdown` function, so closing the socket is the only way to end the connection.
+    /// Any close notification to the server is up to the modem.
     pub async fn deactivate(self) -> Result<(), Error> {
         self.inner.deactivate().await?;
         Ok(())
     }
 }
 
+/// The cipher suites that are offered by [DtlsConfig::psk]
+const PSK_CIPHER_SUITES: [u32; 4] = [
+    nrfxlib_sys::NRF_TLS_PSK_WITH_AES_128_CCM_8,
+    nrfxlib_sys::NRF_TLS_PSK_WITH_AES_128_CBC_SHA256,
+    nrfxlib_sys::NRF_TLS_PSK_WITH_AES_128_CBC_SHA,
+    nrfxlib_sys::NRF_TLS_PSK_WITH_AES_256_CBC_SHA,
+];
+
+/// The configuration of a DTLS connection.
+///
+/// The options that aren't set keep the same value as in [DtlsSocket::connect].
+/// The config can be used for multiple connections.
+///
+/// ```rust,ignore
+/// let socket = DtlsConfig::new(&[SECURITY_TAG])
+///     .peer_verify(PeerVerification::Enabled)
+///     .session_cache(true)
+///     .connect("example.com", 5684)
+///     .await?;
+/// ```
+#[derive(Clone, Copy)]
+pub struct DtlsConfig<'a> {
+    security_tags: &'a [u32],
+    client_tag: Option<u32>,
+    peer_verify: PeerVerification,
+    hostname: Option<&'a str>,
+    session_cache: bool,
+    cipher_suites: &'a [u32],
+    connection_id: DtlsConnectionId,
+    pdn_id: Option<u8>,
+    #[cfg(feature = "embassy-time")]
+    dns_cache: Option<&'a (dyn DnsLookup + Sync)>,
+    #[cfg(feature = "embassy-time")]
+    timeout: Option<embassy_time::Duration>,
+    token: Option<&'a CancellationToken>,
+}
+
+impl<'a> DtlsConfig<'a> {
+    /// Create a new config that uses the credentials of the given security tags
+    pub fn new(security_tags: &'a [u32]) -> Self {
+        Self {
+            security_tags,
+            client_tag: None,
+            peer_verify: PeerVerification::Enabled,
+            hostname: None,
+            session_cache: false,
+            cipher_suites: &[],
+            connection_id: DtlsConnectionId::Disabled,
+            pdn_id: None,
+            #[cfg(feature = "embassy-time")]
+            dns_cache: None,
+            #[cfg(feature = "embassy-time")]
+            timeout: None,
+            token: None,
+        }
+    }
+
+    /// Create a new config that authenticates with the pre-shared key and identity of the given security tag.
+    ///
+    /// Only the PSK cipher suites are offered, so no certificates are used.
+    /// Both sides prove that they know the key in the handshake, which is what authenticates the server.
+    /// That's why the certificate verification is disabled.
+    ///
+    /// The key and identity can be written with [crate::credentials::write] as
+    /// [crate::credentials::CredentialType::Psk] and [crate::credentials::CredentialType::PskIdentity].
+    pub fn psk(tag: u32) -> Self {
+        Self::new(&[])
+            .client_credential(tag)
+            .peer_verify(PeerVerification::Disabled)
+            .cipher_suites(&PSK_CIPHER_SUITES)
+    }
+
+    /// Set how the certificate of the server is verified. The default is [PeerVerification::Enabled].
+    pub fn peer_verify(mut self, peer_verify: PeerVerification) -> Self {
+        self.peer_verify = peer_verify;
+        self
+    }
+
+    /// Set the security tags that hold the credentials of the connection.
+    ///
+    /// The modem goes through the tags in the given order. When the server asks for a client certificate,
+    /// the certificate and private key of the first tag that has them is offered.
+    /// At most 7 tags can be given.
+    pub fn security_tags(mut self, security_tags: &'a [u32]) -> Self {
+        self.security_tags = security_tags;
+        self
+    }
+
+    /// Offer the client certificate and private key of the given tag when the server asks for one.
+    ///
+    /// The tag is put at the front of the [Self::security_tags], so it's picked over the other tags.
+    /// The tag doesn't have to be in the security tags already.
+    pub fn client_credential(mut self, tag: u32) -> Self {
+        self.client_tag = Some(tag);
+        self
+    }
+
+    /// Set the TLS hostname that is used for SNI and for checking the certificate of the server.
+    ///
+    /// By default the hostname given to [Self::connect] is used.
+    /// Setting it is useful when connecting to an IP address.
+    pub fn hostname(mut self, hostname: &'a str) -> Self {
+        self.hostname = Some(hostname);
+        self
+    }
+
+    /// Enable or disable the TLS session cache. The default is disabled.
+    ///
+    /// With the session cache enabled, the modem can resume an earlier session with the same server which
+    /// makes the handshake a lot shorter.
+    pub fn session_cache(mut self, session_cache: bool) -> Self {
+        self.session_cache = session_cache;
+        self
+    }
+
+    /// Only offer the given cipher suites in the handshake.
+    ///
+    /// The cipher suites are the `NRF_TLS_*` values of [nrfxlib_sys]. An empty list (the default) keeps the modem default.
+    pub fn cipher_suites(mut self, cipher_suites: &'a [u32]) -> Self {
+        self.cipher_suites = cipher_suites;
+        self
+    }
+
+    /// Set the use of the DTLS connection ID (RFC 9146). The default is disabled.
+    ///
+    /// With a connection ID the session survives a change of the IP address or port of the device,
+    /// so no new handshake is needed after e.g. a cell change. This requires modem firmware v1.3.5 or newer.
+    /// On older firmware the connect returns [crate::SocketOptionError::UnsupportedOption] before the handshake is started.
+    pub fn connection_id(mut self, connection_id: DtlsConnectionId) -> Self {
+        self.connection_id = connection_id;
+        self
+    }
+
+    /// Route the traffic of the socket over the PDN with the given ID. By default the default PDN is used.
+    ///
+    /// See [crate::PdnContext] for creating an extra PDN.
+    pub fn pdn(mut self, pdn_id: u8) -> Self {
+        self.pdn_id = Some(pdn_id);
+        self
+    }
+
+    /// Look up the hostname in the given cache before doing a DNS request, and store the result there.
+    /// By default every connect does a DNS request.
+    #[cfg(feature = "embassy-time")]
+    pub fn dns_cache<const ENTRIES: usize, const ADDRS: usize>(
+        mut self,
+        dns_cache: &'a DnsCache<ENTRIES, ADDRS>,
+    ) -> Self {
+        self.dns_cache = Some(dns_cache);
+        self
+    }
+
+    /// Set the token that can cancel the connect
+    pub fn cancellation(mut self, token: &'a CancellationToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Give up on the connect when it takes longer than the timeout, and return [Error::Timeout].
+    /// The timeout covers the DNS request and the handshake. By default there is no timeout.
+    #[cfg(feature = "embassy-time")]
+    pub fn timeout(mut self, timeout: embassy_time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Connect a DTLS socket with this config to the given hostname and port.
+    ///
+    /// The hostname is resolved with DNS and, unless another one is configured, is also set as the TLS hostname.
+    /// If that is an IP address literal, there is no DNS name to check the certificate against,
+    /// so [PeerVerification::Enabled] may fail the handshake.
+    pub async fn connect(&self, hostname: &str, port: u16) -> Result<DtlsSocket, Error> {
+        self.run_with_timeout(self.connect_hostname(hostname, port))
+            .await
+    }
+
+    /// Connect a DTLS socket with this config to the given address, without a DNS request.
+    ///
+    /// Only the TLS hostname that is set with [Self::hostname] is used, so without it there is
+    /// no DNS name to check the certificate against and [PeerVerification::Enabled] may fail the handshake.
+    pub async fn connect_addr(&self, addr: SocketAddr) -> Result<DtlsSocket, Error> {
+        self.run_with_timeout(self.connect_inner(addr, self.hostname))
+            .await
+    }
+
+    async fn connect_hostname(&self, hostname: &str, port: u16) -> Result<DtlsSocket, Error> {
+        let default_token = CancellationToken::new();
+        let token = self.token.unwrap_or(&default_token);
+
+        #[cfg(feature = "embassy-time")]
+        let ip = match self.dns_cache.and_then(|cache| cache.lookup(hostname)) {
+            Some(ip) => ip,
+            None => {
+                let ip = dns::get_host_by_name_with_cancellation(hostname, token).await?;
+                if let Some(cache) = self.dns_cache {
+                    cache.store(hostname, ip);
+                }
+                ip
+            }
+        };
+        #[cfg(not(feature = "embassy-time"))]
+        let ip = dns::get_host_by_name_with_cancellation(hostname, token).await?;
+
+        self.connect_inner(
+            SocketAddr::from((ip, port)),
+            Some(self.hostname.unwrap_or(hostname)),
+        )
+        .await
+    }
+
+    async fn connect_inner(
+        &self,
+        addr: SocketAddr,
+        tls_hostname: Option<&str>,
+    ) -> Result<DtlsSocket, Error> {
+        let default_token = CancellationToken::new();
+        let token = self.token.unwrap_or(&default_token);
+
+        let family = match addr {
+            SocketAddr::V4(_) => SocketFamily::Ipv4,
+            SocketAddr::V6(_) => SocketFamily::Ipv6,
+        };
+
+        let tags = self.tag_list()?;
+        let inner = Socket::create(family, SocketType::Datagram, SocketProtocol::DTls1v2).await?;
+        for option in self.socket_options(tls_hostname, &tags) {
+            let connection_id = matches!(option, SocketOption::DtlsConnectionId(_));
+
+            // Older modem firmware doesn't know the connection ID option.
+            // Depending on the version it says so with either errno.
+            inner
+                .set_option(option)
+                .map_err(|e| match e.errno() as u32 {
+                    nrfxlib_sys::NRF_EOPNOTSUPP | nrfxlib_sys::NRF_ENOPROTOOPT if connection_id => {
+                        SocketOptionError::UnsupportedOption
+                    }
+                    _ => e,
+                })?;
+        }
+
+        token.as_result()?;
+
+        // A DTLS connect is the handshake, so the errors the modem gives here are handshake failures,
+        // unless they are network problems that are worth trying again
+        unsafe { inner.connect(addr, token).await }.map_err(|e| match e {
+            Error::NrfError(errno) if !e.is_transient() => Error::TlsHandshakeFailed(errno),
+            e => e,
+        })?;
+
+        Ok(DtlsSocket { inner })
+    }
+
+    #[cfg(feature = "embassy-time")]
+    async fn run_with_timeout(
+        &self,
+        connect: impl Future<Output = Result<DtlsSocket, Error>>,
+    ) -> Result<DtlsSocket, Error> {
+        match self.timeout {
+            // A connect that is stopped halfway drops its socket, which closes it
+            Some(timeout) => embassy_time::with_timeout(timeout, connect)
+                .await
+                .unwrap_or(Err(Error::Timeout)),
+            None => connect.await,
+        }
+    }
+
+    #[cfg(not(feature = "embassy-time"))]
+    async fn run_with_timeout(
+        &self,
+        connect: impl Future<Output = Result<DtlsSocket, Error>>,
+    ) -> Result<DtlsSocket, Error> {
+        connect.await
+    }
+
+    /// The options that are set on the socket before the handshake, in the order they are set
+    fn socket_options<'b>(
+        &'b self,
+        tls_hostname: Option<&'b str>,
+        tags: &'b [u32],
+    ) -> ArrayVec<SocketOption<'b>, 7> {
+        let mut options = ArrayVec::new();
+
+        options.push(SocketOption::TlsPeerVerify(self.peer_verify.as_integer()));
+        options.push(SocketOption::TlsSessionCache(self.session_cache as _));
+        options.push(SocketOption::TlsTagList(tags));
+        if let Some(tls_hostname) = tls_hostname {
+            options.push(SocketOption::TlsHostName(tls_hostname));
+        }
+        if !self.cipher_suites.is_empty() {
+            options.push(SocketOption::TlsCipherSuiteList(self.cipher_suites));
+        }
+        // Only set when used, because older modem firmware doesn't know the option
+        if self.connection_id != DtlsConnectionId::Disabled {
+            options.push(SocketOption::DtlsConnectionId(
+                self.connection_id.as_integer(),
+            ));
+        }
+        if let Some(pdn_id) = self.pdn_id {
+            options.push(SocketOption::BindToPdn(pdn_id as i32));
+        }
+
+        options
+    }
+
+    fn tag_list(
+        &self,
+    ) -> Result<ArrayVec<u32, { nrfxlib_sys::NRF_SOCKET_TLS_MAX_SEC_TAG_LIST_SIZE as usize }>, Error>
+    {
+        let mut tags = ArrayVec::new();
+
+        for &tag in self.client_tag.iter().chain(self.security_tags) {
+            if tags.contains(&tag) {
+                continue;
+            }
+            tags.try_push(tag)
+                .map_err(|_| Error::BufferTooSmall(None))?;
+        }
+
+        Ok(tags)
+    }
+
+    /// Deactivate the given socket and connect a new one with this config to the given hostname and port.
+    ///
+    /// The LTE link is kept up in between, so the modem doesn't have to attach to the network again.
+    /// When the session cache is enabled, the modem can resume the session of the old socket.
+    pub async fn reconnect(
+        &self,
+        socket: DtlsSocket,
+        hostname: &str,
+        port: u16,
+    ) -> Result<DtlsSocket, Error> {
+        let link = LteLink::new().await?;
+
+        let result = match socket.deactivate().await {
+            Ok(()) => self.connect(hostname, port).await,
+            Err(e) => Err(e),
+        };
+
+        link.deactivate().await?;
+        result
+    }
+}
+
 pub struct DtlsReceiveSocket<'a> {
     socket: &'a DtlsSocket,
 }
@@ -156,6 +698,7 @@ impl<'a> DtlsSendSocket<'a> {
     impl_send!();
 }
 
+/// An owned receive half of a DTLS socket
 pub struct OwnedDtlsReceiveSocket {
     socket: SplitSocketHandle,
 }
@@ -168,6 +711,7 @@ impl OwnedDtlsReceiveSocket {
     impl_receive_from!();
 }
 
+/// An owned send half of a DTLS socket
 pub struct OwnedDtlsSendSocket {
     socket: SplitSocketHandle,
 }
@@ -180,22 +724,148 @@ impl OwnedDtlsSendSocket {
     impl_send!();
 }
 
+/// How the certificate of the server is verified.
+///
+/// The modem always verifies the full chain up to a CA certificate of the used security tags.
+/// It has no option to limit the depth of the chain.
 #[derive(Debug, Copy, Clone)]
 pub enum PeerVerification {
+    /// The handshake fails if the certificate can't be verified
     Enabled,
+    /// The certificate is verified, but the handshake continues if it fails
     Optional,
+    /// The certificate is not verified
     Disabled,
 }
 impl PeerVerification {
     fn as_integer(self) -> u32 {
         match self {
-            PeerVerification::Enabled => 2,
-            PeerVerification::Optional => 1,
-            PeerVerification::Disabled => 0,
+            PeerVerification::Enabled => nrfxlib_sys::NRF_SO_SEC_PEER_VERIFY_REQUIRED,
+            PeerVerification::Optional => nrfxlib_sys::NRF_SO_SEC_PEER_VERIFY_OPTIONAL,
+            PeerVerification::Disabled => nrfxlib_sys::NRF_SO_SEC_PEER_VERIFY_NONE,
+        }
+    }
+}
+/// The use of the DTLS connection ID (RFC 9146)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DtlsConnectionId {
+    /// The connection ID is not used
+    Disabled,
+    /// The modem accepts a connection ID from the server, but doesn't request one itself
+    Supported,
+    /// The modem requests the use of a connection ID
+    Enabled,
+}
+impl DtlsConnectionId {
+    fn as_integer(self) -> u32 {
+        match self {
+            DtlsConnectionId::Disabled => nrfxlib_sys::NRF_SO_SEC_DTLS_CID_DISABLED,
+            DtlsConnectionId::Supported => nrfxlib_sys::NRF_SO_SEC_DTLS_CID_SUPPORTED,
+            DtlsConnectionId::Enabled => nrfxlib_sys::NRF_SO_SEC_DTLS_CID_ENABLED,
         }
     }
 }
+
+/// The directions in which the DTLS connection ID is included in the records
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DtlsConnectionIdStatus {
+    /// Not used. This is also the status before the handshake is done
+    Disabled,
+    /// Only used in the records sent to the modem
+    Downlink,
+    /// Only used in the records sent by the modem
+    Uplink,
+    /// Used in both directions
+    Bidirectional,
+}
+
+/// How the handshake of a DTLS connection went
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DtlsHandshakeStatus {
+    /// A full handshake with the certificate or PSK checks was done
+    Full,
+    /// A cached session was resumed, see [DtlsConfig::session_cache]
+    Cached,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Version {
     Dtls1v2,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_credential_goes_first() {
+        let tags = [10, 20, 30];
+        let config = DtlsConfig::new(&tags).client_credential(20);
+
+        assert_eq!(&config.tag_list().unwrap()[..], &[20, 10, 30]);
+    }
+
+    #[test]
+    fn too_many_tags() {
+        let tags = [1, 2, 3, 4, 5, 6, 7];
+        assert_eq!(
+            tags.len(),
+            nrfxlib_sys::NRF_SOCKET_TLS_MAX_SEC_TAG_LIST_SIZE as usize
+        );
+
+        // A client credential that is one of the tags doesn't take up an extra spot
+        let config = DtlsConfig::new(&tags).client_credential(7);
+        assert_eq!(&config.tag_list().unwrap()[..], &[7, 1, 2, 3, 4, 5, 6]);
+
+        let config = DtlsConfig::new(&tags).client_credential(8);
+        assert!(matches!(
+            config.tag_list(),
+            Err(Error::BufferTooSmall(None))
+        ));
+    }
+
+    #[test]
+    fn connection_id_is_only_set_when_used() {
+        let tags = [1];
+
+        let config = DtlsConfig::new(&tags).pdn(1);
+        let options = config.socket_options(Some("example.com"), &tags);
+        assert!(!options
+            .iter()
+            .any(|option| matches!(option, SocketOption::DtlsConnectionId(_))));
+
+        let config = config.connection_id(DtlsConnectionId::Enabled);
+        let options = config.socket_options(Some("example.com"), &tags);
+        assert!(options.iter().any(|option| matches!(
+            option,
+            SocketOption::DtlsConnectionId(value) if *value == nrfxlib_sys::NRF_SO_SEC_DTLS_CID_ENABLED
+        )));
+    }
+
+    #[test]
+    fn all_options_are_set() {
+        let tags = [1, 2];
+        let cipher_suites = [nrfxlib_sys::NRF_TLS_PSK_WITH_AES_128_CCM_8];
+        let config = DtlsConfig::new(&tags)
+            .session_cache(true)
+            .cipher_suites(&cipher_suites)
+            .connection_id(DtlsConnectionId::Supported)
+            .pdn(2);
+
+        let options = config.socket_options(Some("example.com"), &tags);
+        assert!(matches!(
+            &options[..],
+            [
+                SocketOption::TlsPeerVerify(_),
+                SocketOption::TlsSessionCache(1),
+                SocketOption::TlsTagList([1, 2]),
+                SocketOption::TlsHostName("example.com"),
+                SocketOption::TlsCipherSuiteList(_),
+                SocketOption::DtlsConnectionId(_),
+                SocketOption::BindToPdn(2),
+            ]
+        ));
+    }
+}
@@ -0,0 +1,254 @@
+//! Implementation of the extended discontinuous reception (eDRX) configuration (`+CEDRXS`)
+
+use crate::{at, at_notifications::AtNotificationStream, error::Error, AccessTechnology};
+use arrayvec::ArrayString;
+use core::{fmt::Write, task::Poll, time::Duration};
+
+/// The eDRX cycle lengths in milliseconds that LTE-M supports, with their encoded value.
+/// See 3GPP TS 24.008, table 10.5.5.32
+const LTE_M_CYCLES: [(u8, u64); 16] = [
+    (0b0000, 5_120),
+    (0b0001, 10_240),
+    (0b0010, 20_480),
+    (0b0011, 40_960),
+    (0b0100, 61_440),
+    (0b0101, 81_920),
+    (0b0110, 102_400),
+    (0b0111, 122_880),
+    (0b1000, 143_360),
+    (0b1001, 163_840),
+    (0b1010, 327_680),
+    (0b1011, 655_360),
+    (0b1100, 1_310_720),
+    (0b1101, 2_621_440),
+    (0b1110, 5_242_880),
+    (0b1111, 10_485_760),
+];
+
+/// The eDRX cycle lengths in milliseconds that NB-IoT supports, with their encoded value.
+/// See 3GPP TS 24.008, table 10.5.5.32
+const NB_IOT_CYCLES: [(u8, u64); 10] = [
+    (0b0010, 20_480),
+    (0b0011, 40_960),
+    (0b0101, 81_920),
+    (0b1001, 163_840),
+    (0b1010, 327_680),
+    (0b1011, 655_360),
+    (0b1100, 1_310_720),
+    (0b1101, 2_621_440),
+    (0b1110, 5_242_880),
+    (0b1111, 10_485_760),
+];
+
+impl AccessTechnology {
+    /// The `<AcT-type>` of the eDRX commands
+    fn edrx_act_type(self) -> u8 {
+        match self {
+            AccessTechnology::LteM => 4,
+            AccessTechnology::NbIot => 5,
+        }
+    }
+
+    fn edrx_cycles(self) -> &'static [(u8, u64)] {
+        match self {
+            AccessTechnology::LteM => &LTE_M_CYCLES,
+            AccessTechnology::NbIot => &NB_IOT_CYCLES,
+        }
+    }
+
+    /// The length of one step of the paging time window in milliseconds
+    fn paging_time_window_step(self) -> u64 {
+        match self {
+            AccessTechnology::LteM => 1_280,
+            AccessTechnology::NbIot => 2_560,
+        }
+    }
+}
+
+/// The eDRX parameters as reported by `+CEDRXP` and `+CEDRXRDP`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EdrxParameters {
+    /// The access technology these parameters apply to.
+    /// `None` if the serving cell doesn't use eDRX.
+    pub access_technology: Option<AccessTechnology>,
+    /// The eDRX cycle that was requested
+    pub requested_cycle: Option<Duration>,
+    /// The eDRX cycle the network has granted
+    pub cycle: Option<Duration>,
+    /// The paging time window the network has granted
+    pub paging_time_window: Option<Duration>,
+}
+
+impl EdrxParameters {
+    /// Get the eDRX parameters of the serving cell by querying `AT+CEDRXRDP`
+    pub async fn query() -> Result<Self, Error> {
+        let mut buffer = [0; 64];
+        let response = at::send_at_command("AT+CEDRXRDP", &mut buffer).await?;
+
+        Self::parse(response, "+CEDRXRDP:")
+    }
+
+    // Parse `<prefix> <AcT-type>[,<Requested_eDRX_value>[,<NW-provided_eDRX_value>[,<Paging_time_window>]]]`
+    fn parse(response: &str, prefix: &str) -> Result<Self, Error> {
+        let mut fields = response
+            .trim()
+            .strip_prefix(prefix)
+            .ok_or(Error::UnexpectedAtResponse)?
+            .split(',')
+            .map(|field| field.trim().trim_matches('"'));
+
+        let access_technology = match fields.next() {
+            Some("4") => Some(AccessTechnology::LteM),
+            Some("5") => Some(AccessTechnology::NbIot),
+            Some(_) => None,
+            None => return Err(Error::UnexpectedAtResponse),
+        };
+
+        let mut bits = || {
+            fields
+                .next()
+                .filter(|field| !field.is_empty())
+                .map(|field| u8::from_str_radix(field, 2).map_err(|_| Error::UnexpectedAtResponse))
+                .transpose()
+        };
+        let (requested_cycle, cycle, paging_time_window) = (bits()?, bits()?, bits()?);
+
+        let decode_cycle = |value: u8| {
+            access_technology.and_then(|act| {
+                act.edrx_cycles()
+                    .iter()
+                    .find(|(v, _)| *v == value)
+                    .map(|(_, ms)| Duration::from_millis(*ms))
+            })
+        };
+
+        Ok(Self {
+            access_technology,
+            requested_cycle: requested_cycle.and_then(decode_cycle),
+            cycle: cycle.and_then(decode_cycle),
+            paging_time_window: paging_time_window.and_then(|value| {
+                access_technology.map(|act| {
+                    Duration::from_millis(act.paging_time_window_step() * (value as u64 + 1))
+                })
+            }),
+        })
+    }
+}
+
+/// Request the network to use eDRX for the given access technology.
+///
+/// The settings for LTE-M and NB-IoT are separate, so call this for both if the modem may use either.
+///
+/// - `cycle`: How often the modem listens for paging. LTE-M supports 5.12 to 10485.76 seconds,
+///   NB-IoT 20.48 to 10485.76 seconds. Only a fixed set of values can be used, so the cycle is rounded up to the next one.
+/// - `paging_time_window`: How long the modem listens each cycle. This is set with `AT%XPTW` and
+///   is rounded up to a multiple of 1.28 seconds for LTE-M (up to 20.48 s) or 2.56 seconds for NB-IoT (up to 40.96 s).
+///   Use `None` to keep the current setting.
+///
+/// If a duration is too long, [Error::EdrxValueOutOfRange] is returned.
+///
+/// The network decides what is actually used. Use [EdrxParameters::query] or the [EdrxStream] to see the granted values.
+pub async fn request_edrx(
+    access_technology: AccessTechnology,
+    cycle: Duration,
+    paging_time_window: Option<Duration>,
+) -> Result<(), Error> {
+    let cycle_ms = cycle.as_millis() as u64;
+    let cycle_value = access_technology
+        .edrx_cycles()
+        .iter()
+        .find(|(_, ms)| *ms >= cycle_ms)
+        .map(|(value, _)| *value)
+        .ok_or(Error::EdrxValueOutOfRange)?;
+
+    let mut command = ArrayString::<32>::new();
+
+    if let Some(paging_time_window) = paging_time_window {
+        let step = access_technology.paging_time_window_step();
+        let steps = (paging_time_window.as_millis() as u64 + step - 1) / step;
+        if steps > 16 {
+            return Err(Error::EdrxValueOutOfRange);
+        }
+
+        write!(
+            command,
+            "AT%XPTW={},\"{:04b}\"",
+            access_technology.edrx_act_type(),
+            steps.saturating_sub(1)
+        )
+        .map_err(|_| Error::BufferTooSmall(None))?;
+        at::send_at_command(&command, &mut [0; 16]).await?;
+        command.clear();
+    }
+
+    // Mode 2 also enables the +CEDRXP notifications
+    write!(
+        command,
+        "AT+CEDRXS=2,{},\"{:04b}\"",
+        access_technology.edrx_act_type(),
+        cycle_value
+    )
+    .map_err(|_| Error::BufferTooSmall(None))?;
+    at::send_at_command(&command, &mut [0; 16]).await?;
+
+    Ok(())
+}
+
+/// Disable eDRX for all access technologies and reset the requested values to their defaults
+pub async fn disable_edrx() -> Result<(), Error> {
+    at::send_at_command("AT+CEDRXS=3", &mut [0; 16]).await?;
+
+    Ok(())
+}
+
+/// An async stream of the eDRX parameters the network grants, using the unsolicited `+CEDRXP` notifications.
+///
+/// The notifications are enabled by [request_edrx].
+///
+/// Implements the [futures::Stream] trait for polling.
+/// `COUNT` is the amount of notifications that can be buffered while the stream is not polled.
+pub struct EdrxStream<const COUNT: usize> {
+    notifications: AtNotificationStream<64, COUNT>,
+}
+
+impl<const COUNT: usize> EdrxStream<COUNT> {
+    /// Creates a new stream.
+    ///
+    /// To access all the nice iterator functions, use [futures::StreamExt].
+    pub async fn new() -> Self {
+        Self {
+            notifications: AtNotificationStream::with_prefix("+CEDRXP:").await,
+        }
+    }
+
+    /// Futures are lazy and can only register themselves once polled.
+    /// Call this function if you want to register this stream early so that it can already receive notifications.
+    pub async fn register(self: core::pin::Pin<&mut Self>) {
+        let notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+        notifications.register().await;
+    }
+}
+
+impl<const COUNT: usize> futures::Stream for EdrxStream<COUNT> {
+    type Item = EdrxParameters;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+
+        loop {
+            match notifications.as_mut().poll_next(cx) {
+                Poll::Ready(Some(notification)) => {
+                    if let Ok(parameters) = EdrxParameters::parse(&notification, "+CEDRXP:") {
+                        return Poll::Ready(Some(parameters));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -33,12 +33,186 @@ pub enum Error {
     /// The ongoing operation has been cancelled by the user
     OperationCancelled,
     SmsNumberNotAscii,
+    /// The peer has closed the connection gracefully (the end of the stream), or the socket isn't connected.
+    ///
+    /// Data that was received before the close is returned first, so this is only returned once everything has been read.
     Disconnected,
     TooManyLteLinks,
     InternalRuntimeMutexLocked,
     /// The given memory layout falls outside of the acceptable range
     BadMemoryLayout,
     ModemAlreadyInitialized,
+    /// The operation did not complete in time
+    Timeout,
+    /// The two halves that were given to be reunited don't come from the same socket, see [crate::ReuniteError]
+    SocketHalvesMismatch,
+    /// The message needs more than the 255 segments a concatenated SMS can have
+    SmsTooLong,
+    /// A received SMS PDU could not be decoded
+    InvalidSmsPdu,
+    /// The modem answered an AT command with `ERROR`
+    AtError,
+    /// The modem answered an AT command with `+CME ERROR: <n>`
+    CmeError(u16),
+    /// The modem answered an AT command with `+CMS ERROR: <n>`.
+    /// This is also how the network rejecting an SMS is reported.
+    CmsError(u16),
+    /// The requested PSM timer is longer than what can be encoded
+    PsmTimerOutOfRange,
+    /// The requested eDRX cycle or paging time window is longer than what can be encoded
+    EdrxValueOutOfRange,
+    /// The operation can only be done while the modem is turned off, but there are active [crate::LteLink] or [crate::Gnss] instances
+    ModemInUse,
+    /// The given address could not be turned into socket addresses
+    DnsFailure,
+    /// The given address resolved to no socket addresses at all
+    NoAddresses,
+    /// The modem can't have more sockets open at the same time
+    TooManySockets,
+    /// The operation can only be done while the modem is offline (`AT+CFUN=4` or `AT+CFUN=0`)
+    ModemNotOffline,
+    /// The connection was reset by the peer or the network, instead of being closed gracefully
+    ConnectionReset,
+    /// The band lock has no bands or a band that can't be put in the band lock mask
+    InvalidBandLock,
+    /// The modem lost its network registration while the socket operation was waiting.
+    /// This is noticed through the `+CEREG` notifications, which are turned on by e.g. [crate::LteLink::wait_for_link].
+    NetworkDown,
+    /// The TLS or DTLS handshake failed with the given nrf errno.
+    /// Transient errors during the handshake, like a timeout or a lost network, are returned as [Error::NrfError] instead.
+    ///
+    /// The modem doesn't report the TLS alert, so e.g. an expired or untrusted certificate can't be told apart.
+    /// Common causes are a missing or wrong root certificate in the security tag, a server name that doesn't match
+    /// the certificate, and a modem that doesn't know the time yet so it can't check the validity period
+    /// (see [crate::network_time]).
+    TlsHandshakeFailed(isize),
+    /// The socket was closed because the modem library was restarted by [crate::recover]
+    SocketInvalidated,
+    /// Too many socket operations are waiting at the same time to store all their wakers.
+    /// The default of 16 can be raised with the `socket-wakers-32` or `socket-wakers-64` feature.
+    TooManySocketWakers,
+    /// The GNSS systems can't be used like this. GPS can't be turned off, and the modem firmware may not support all systems.
+    UnsupportedGnssSystems,
+    /// The modem gave a socket option a value that isn't known, e.g. because the modem firmware is newer than this crate
+    UnexpectedSocketOptionValue(i32),
+}
+
+impl Error {
+    /// Get the nrf errno value that caused this error, if any.
+    ///
+    /// The values are listed in [nrf_errno.h](https://github.com/nrfconnect/sdk-nrfxlib/blob/main/nrf_modem/include/nrf_errno.h)
+    /// and are available as the `NRF_E*` constants of [nrfxlib_sys]. The sign is always positive.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        match self {
+            Error::NrfError(errno) => Some(errno.unsigned_abs() as i32),
+            Error::SocketOptionError(e) => Some(e.errno()),
+            Error::ConnectionReset => Some(nrfxlib_sys::NRF_ECONNRESET as i32),
+            Error::TlsHandshakeFailed(errno) => Some(errno.unsigned_abs() as i32),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the same operation may succeed when it's tried again later, e.g. after a backoff.
+    ///
+    /// Running out of memory or buffers, timeouts, lost connections and network problems are transient.
+    /// Errors that will happen again no matter how often it's tried, like a refused connection, failing TLS,
+    /// invalid arguments, sim problems and a denied registration, are permanent.
+    /// A cancelled operation is not seen as transient, because it was stopped on purpose.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::OutOfMemory
+            | Error::Timeout
+            | Error::Disconnected
+            | Error::ConnectionReset
+            | Error::NetworkDown
+            | Error::AddressNotFound
+            | Error::InternalRuntimeMutexLocked
+            | Error::TooManySockets
+            | Error::TooManySocketWakers => true,
+            Error::SocketOptionError(e) => is_transient_errno(e.errno() as u32),
+            Error::NrfError(errno) => is_transient_errno(errno.unsigned_abs() as u32),
+            _ => false,
+        }
+    }
+}
+
+fn is_transient_errno(errno: u32) -> bool {
+    matches!(
+        errno,
+        nrfxlib_sys::NRF_EAGAIN
+            | nrfxlib_sys::NRF_ENOMEM
+            | nrfxlib_sys::NRF_ENOBUFS
+            | nrfxlib_sys::NRF_EBUSY
+            | nrfxlib_sys::NRF_EINPROGRESS
+            | nrfxlib_sys::NRF_EALREADY
+            | nrfxlib_sys::NRF_ETIMEDOUT
+            | nrfxlib_sys::NRF_ENETDOWN
+            | nrfxlib_sys::NRF_ENETUNREACH
+            | nrfxlib_sys::NRF_ENETRESET
+            | nrfxlib_sys::NRF_EHOSTDOWN
+            | nrfxlib_sys::NRF_EHOSTUNREACH
+            | nrfxlib_sys::NRF_ECONNRESET
+            | nrfxlib_sys::NRF_ECONNABORTED
+            // Canceled because of APN rate control
+            | nrfxlib_sys::NRF_ECANCELED
+    )
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::ModemNotInitialized => f.write_str("modem not initialized"),
+            Error::GnssAlreadyTaken => f.write_str("gnss already taken"),
+            Error::NrfError(errno) => write!(f, "modem errno {}", errno.unsigned_abs()),
+            Error::BufferTooSmall(Some(needed)) => write!(f, "buffer too small, {} needed", needed),
+            Error::BufferTooSmall(None) => f.write_str("buffer too small"),
+            Error::OutOfMemory => f.write_str("out of memory"),
+            Error::AtParseError(_) => f.write_str("at parse error"),
+            Error::InvalidSystemModeConfig => f.write_str("invalid system mode config"),
+            Error::StringNotNulTerminated => f.write_str("string not nul terminated"),
+            Error::Utf8Error => f.write_str("invalid utf8"),
+            Error::LteRegistrationDenied => f.write_str("lte registration denied"),
+            Error::SimFailure => f.write_str("sim failure"),
+            Error::UnexpectedAtResponse => f.write_str("unexpected at response"),
+            Error::HostnameNotAscii => f.write_str("hostname not ascii"),
+            Error::HostnameTooLong => f.write_str("hostname too long"),
+            Error::AddressNotFound => f.write_str("address not found"),
+            Error::SocketOptionError(e) => write!(f, "socket option errno {}", e.errno()),
+            Error::OperationCancelled => f.write_str("operation cancelled"),
+            Error::SmsNumberNotAscii => f.write_str("sms number not ascii"),
+            Error::Disconnected => f.write_str("disconnected"),
+            Error::TooManyLteLinks => f.write_str("too many lte links"),
+            Error::InternalRuntimeMutexLocked => f.write_str("runtime mutex locked"),
+            Error::BadMemoryLayout => f.write_str("bad memory layout"),
+            Error::ModemAlreadyInitialized => f.write_str("modem already initialized"),
+            Error::Timeout => f.write_str("timeout"),
+            Error::SocketHalvesMismatch => f.write_str("socket halves mismatch"),
+            Error::SmsTooLong => f.write_str("sms too long"),
+            Error::InvalidSmsPdu => f.write_str("invalid sms pdu"),
+            Error::AtError => f.write_str("at error"),
+            Error::CmeError(n) => write!(f, "cme error {}", n),
+            Error::CmsError(n) => write!(f, "cms error {}", n),
+            Error::PsmTimerOutOfRange => f.write_str("psm timer out of range"),
+            Error::EdrxValueOutOfRange => f.write_str("edrx value out of range"),
+            Error::ModemInUse => f.write_str("modem in use"),
+            Error::DnsFailure => f.write_str("dns failure"),
+            Error::NoAddresses => f.write_str("no addresses"),
+            Error::TooManySockets => f.write_str("too many sockets"),
+            Error::ModemNotOffline => f.write_str("modem not offline"),
+            Error::ConnectionReset => f.write_str("connection reset"),
+            Error::InvalidBandLock => f.write_str("invalid band lock"),
+            Error::NetworkDown => f.write_str("network down"),
+            Error::TlsHandshakeFailed(errno) => {
+                write!(f, "tls handshake failed, errno {}", errno.unsigned_abs())
+            }
+            Error::SocketInvalidated => f.write_str("socket invalidated"),
+            Error::TooManySocketWakers => f.write_str("too many socket wakers"),
+            Error::UnsupportedGnssSystems => f.write_str("unsupported gnss systems"),
+            Error::UnexpectedSocketOptionValue(value) => {
+                write!(f, "unexpected socket option value {}", value)
+            }
+        }
+    }
 }
 
 pub trait ErrorSource {
@@ -81,3 +255,20 @@ impl From<SocketOptionError> for Error {
         Self::SocketOptionError(e)
     }
 }
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io_async::Error for Error {
+    fn kind(&self) -> embedded_io_async::ErrorKind {
+        match self {
+            Error::Disconnected => embedded_io_async::ErrorKind::ConnectionReset,
+            Error::ConnectionReset => embedded_io_async::ErrorKind::ConnectionReset,
+            Error::NetworkDown => embedded_io_async::ErrorKind::NotConnected,
+            Error::SocketInvalidated => embedded_io_async::ErrorKind::NotConnected,
+            Error::OperationCancelled => embedded_io_async::ErrorKind::Interrupted,
+            Error::OutOfMemory => embedded_io_async::ErrorKind::OutOfMemory,
+            Error::BufferTooSmall(_) => embedded_io_async::ErrorKind::InvalidInput,
+            Error::Timeout => embedded_io_async::ErrorKind::TimedOut,
+            _ => embedded_io_async::ErrorKind::Other,
+        }
+    }
+}
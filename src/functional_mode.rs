@@ -0,0 +1,89 @@
+//! Implementation of the functional mode of the modem (`+CFUN`)
+
+use crate::{at, error::Error};
+use arrayvec::ArrayString;
+use core::fmt::Write;
+
+/// The functional modes of the modem that can be set with [crate::set_functional_mode]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FunctionalMode {
+    /// Everything is turned off. The settings are stored in the flash of the modem.
+    PowerOff,
+    /// Everything that's enabled in the [crate::SystemMode] is turned on
+    Normal,
+    /// Flight mode, the radio and the UICC (the sim) are turned off
+    Offline,
+    /// Turn off LTE, but keep GNSS as it is
+    DeactivateLte,
+    /// Turn on LTE, but keep GNSS as it is
+    ActivateLte,
+    /// Turn off GNSS, but keep LTE as it is
+    DeactivateGnss,
+    /// Turn on GNSS, but keep LTE as it is
+    ActivateGnss,
+    /// Turn off the UICC
+    DeactivateUicc,
+    /// Turn on only the UICC, e.g. to read the [crate::iccid] without connecting to the network
+    ActivateUicc,
+    /// Flight mode, but the UICC stays on
+    OfflineWithUicc,
+}
+
+impl FunctionalMode {
+    fn as_integer(self) -> u8 {
+        match self {
+            FunctionalMode::PowerOff => 0,
+            FunctionalMode::Normal => 1,
+            FunctionalMode::Offline => 4,
+            FunctionalMode::DeactivateLte => 20,
+            FunctionalMode::ActivateLte => 21,
+            FunctionalMode::DeactivateGnss => 30,
+            FunctionalMode::ActivateGnss => 31,
+            FunctionalMode::DeactivateUicc => 40,
+            FunctionalMode::ActivateUicc => 41,
+            FunctionalMode::OfflineWithUicc => 44,
+        }
+    }
+
+    /// Get the functional mode the modem is in by querying `AT+CFUN?`.
+    ///
+    /// The modem reports its combined state, so this is one of [Self::PowerOff], [Self::Normal], [Self::Offline],
+    /// [Self::DeactivateLte] (only GNSS is on), [Self::DeactivateGnss] (only LTE is on),
+    /// [Self::ActivateUicc] (only the UICC is on) or [Self::OfflineWithUicc].
+    pub async fn query() -> Result<Self, Error> {
+        let mut buffer = [0; 32];
+        let (mode,) = at_commands::parser::CommandParser::parse(
+            at::send_at_command("AT+CFUN?", &mut buffer)
+                .await?
+                .as_bytes(),
+        )
+        .expect_identifier(b"+CFUN: ")
+        .expect_int_parameter()
+        .finish()?;
+
+        match mode {
+            0 => Ok(FunctionalMode::PowerOff),
+            1 => Ok(FunctionalMode::Normal),
+            4 => Ok(FunctionalMode::Offline),
+            20 => Ok(FunctionalMode::DeactivateLte),
+            21 => Ok(FunctionalMode::ActivateLte),
+            30 => Ok(FunctionalMode::DeactivateGnss),
+            31 => Ok(FunctionalMode::ActivateGnss),
+            40 => Ok(FunctionalMode::DeactivateUicc),
+            41 => Ok(FunctionalMode::ActivateUicc),
+            44 => Ok(FunctionalMode::OfflineWithUicc),
+            _ => Err(Error::UnexpectedAtResponse),
+        }
+    }
+
+    pub(crate) async fn apply(self) -> Result<(), Error> {
+        let mut command = ArrayString::<16>::new();
+        write!(command, "AT+CFUN={}", self.as_integer())
+            .map_err(|_| Error::BufferTooSmall(None))?;
+
+        at::send_at_command(&command, &mut [0; 16]).await?;
+
+        Ok(())
+    }
+}
@@ -11,12 +11,15 @@ use critical_section::Mutex;
 use futures::{task::AtomicWaker, Stream};
 use num_enum::{FromPrimitive, IntoPrimitive, TryFromPrimitive};
 
-const MAX_NMEA_BURST_SIZE: usize = 5;
+/// A fix with all sentences enabled gives about 8 NMEA strings at once
+const MAX_NMEA_BURST_SIZE: usize = 8;
 
 static GNSS_WAKER: AtomicWaker = AtomicWaker::new();
 static GNSS_NOTICED_EVENTS: AtomicU32 = AtomicU32::new(0);
-static GNSS_NMEA_STRINGS: Mutex<RefCell<ArrayVec<Result<GnssData, Error>, MAX_NMEA_BURST_SIZE>>> =
-    Mutex::new(RefCell::new(ArrayVec::new_const()));
+#[allow(clippy::type_complexity)]
+static GNSS_NMEA_STRINGS: Mutex<
+    RefCell<ArrayVec<Result<ArrayString<83>, Error>, MAX_NMEA_BURST_SIZE>>,
+> = Mutex::new(RefCell::new(ArrayVec::new_const()));
 
 unsafe extern "C" fn gnss_callback(event: i32) {
     let event_type = GnssEventType::from(event as u32);
@@ -28,7 +31,7 @@ unsafe extern "C" fn gnss_callback(event: i32) {
         critical_section::with(|cs| {
             GNSS_NMEA_STRINGS
                 .borrow_ref_mut(cs)
-                .try_push(GnssData::read_from_modem(GnssDataType::Nmea))
+                .try_push(GnssData::read_nmea_from_modem())
                 .ok()
         });
     }
@@ -118,18 +121,36 @@ impl Gnss {
         Ok(GnssStream::new(false, self))
     }
 
+    /// Do a fix every `period_seconds` (at least 10) and keep the GNSS asleep in between.
+    ///
+    /// Every fix may take up to 60 seconds. Use [Self::start_periodic_fix_with_retry] to change that.
     pub fn start_periodic_fix(
+        self,
+        config: GnssConfig,
+        period_seconds: u16,
+    ) -> Result<GnssStream, Error> {
+        self.start_periodic_fix_with_retry(config, period_seconds, 60)
+    }
+
+    /// Do a fix every `period_seconds` (at least 10) and keep the GNSS asleep in between.
+    ///
+    /// The `retry_seconds` parameter controls how long the GNSS may try to get each fix.
+    /// When it is zero, the GNSS keeps trying until a fix is found.
+    ///
+    /// The GNSS can only run when LTE is idle. Combining the period with LTE PSM or eDRX lets both
+    /// sleep together, because the GNSS gets its time windows while LTE sleeps.
+    pub fn start_periodic_fix_with_retry(
         mut self,
         config: GnssConfig,
         period_seconds: u16,
+        retry_seconds: u16,
     ) -> Result<GnssStream, Error> {
         #[cfg(feature = "defmt")]
-        defmt::trace!("Setting single fix");
+        defmt::trace!("Setting periodic fix");
 
         unsafe {
-            nrfxlib_sys::nrf_modem_gnss_fix_interval_set(period_seconds.max(10))
-                .into_result()
-                .unwrap();
+            nrfxlib_sys::nrf_modem_gnss_fix_interval_set(period_seconds.max(10)).into_result()?;
+            nrfxlib_sys::nrf_modem_gnss_fix_retry_set(retry_seconds).into_result()?;
         }
 
         #[cfg(feature = "defmt")]
@@ -153,6 +174,17 @@ impl Gnss {
                 .into_result()?;
             nrfxlib_sys::nrf_modem_gnss_use_case_set(config.use_case.into()).into_result()?;
             nrfxlib_sys::nrf_modem_gnss_nmea_mask_set(config.nmea_mask.into()).into_result()?;
+            // The GNSS isn't running yet, so an invalid argument means the firmware doesn't support the signals
+            nrfxlib_sys::nrf_modem_gnss_signal_mask_set(config.systems.signal_mask()?)
+                .into_result()
+                .map_err(|e| match e {
+                    Error::NrfError(errno)
+                        if errno.unsigned_abs() == nrfxlib_sys::NRF_EINVAL as usize =>
+                    {
+                        Error::UnsupportedGnssSystems
+                    }
+                    e => e,
+                })?;
             nrfxlib_sys::nrf_modem_gnss_power_mode_set(u32::from(config.power_mode) as _)
                 .into_result()?;
             nrfxlib_sys::nrf_modem_gnss_timing_source_set(u32::from(config.timing_source) as _)
@@ -161,6 +193,14 @@ impl Gnss {
         Ok(())
     }
 
+    /// Write assistance data of the given type to the GNSS.
+    ///
+    /// The data must have the layout of the `nrf_modem_gnss_agnss_*` struct in [nrfxlib_sys] that belongs to the type.
+    /// See [AgnssDataType].
+    pub fn write_agnss_data(&self, data_type: AgnssDataType, data: &[u8]) -> Result<(), Error> {
+        write_agnss_data(data_type, data)
+    }
+
     pub async fn deactivate(self) -> Result<(), Error> {
         core::mem::forget(self);
         let result = crate::MODEM_RUNTIME_STATE.deactivate_gps().await;
@@ -188,6 +228,8 @@ impl Drop for Gnss {
     }
 }
 
+/// The NMEA sentence types the GNSS outputs
+#[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct NmeaMask {
     /// Enables Global Positioning System Fix Data.
@@ -224,6 +266,54 @@ impl From<NmeaMask> for u16 {
     }
 }
 
+/// The satellite systems of the GNSS.
+///
+/// The modem firmware supports GPS and QZSS. Other systems, like Galileo, can't be received by the modem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GnssSystems {
+    /// GPS L1 C/A. The modem can't turn this off, so it must be enabled.
+    pub gps: bool,
+    /// QZSS L1 C/A, the regional system of Japan that improves the fixes in the Asia-Pacific region
+    pub qzss: bool,
+}
+
+impl Default for GnssSystems {
+    fn default() -> Self {
+        Self {
+            gps: true,
+            qzss: true,
+        }
+    }
+}
+
+impl GnssSystems {
+    fn signal_mask(self) -> Result<u8, Error> {
+        if !self.gps {
+            return Err(Error::UnsupportedGnssSystems);
+        }
+
+        Ok(nrfxlib_sys::NRF_MODEM_GNSS_SYSTEM_GPS_L1_CA_MASK as u8
+            | (self.qzss as u8 * nrfxlib_sys::NRF_MODEM_GNSS_SYSTEM_QZSS_L1_CA_MASK as u8))
+    }
+
+    /// The systems of the satellites that were used in the fix
+    fn used_in_fix(satellites: &[nrfxlib_sys::nrf_modem_gnss_sv]) -> Self {
+        let used = |signal: u32| {
+            satellites.iter().any(|sv| {
+                sv.sv != 0
+                    && sv.signal as u32 == signal
+                    && sv.flags as u32 & nrfxlib_sys::NRF_MODEM_GNSS_SV_FLAG_USED_IN_FIX != 0
+            })
+        };
+
+        Self {
+            gps: used(nrfxlib_sys::NRF_MODEM_GNSS_SIGNAL_GPS_L1_CA),
+            qzss: used(nrfxlib_sys::NRF_MODEM_GNSS_SIGNAL_QZSS_L1_CA),
+        }
+    }
+}
+
 #[derive(Copy, Clone, IntoPrimitive, FromPrimitive, Debug, Default)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u32)]
@@ -289,6 +379,8 @@ pub struct GnssConfig {
     pub elevation_threshold_angle: u8,
     pub use_case: GnssUsecase,
     pub nmea_mask: NmeaMask,
+    /// The satellite systems to use. By default all systems the modem supports are used.
+    pub systems: GnssSystems,
     pub timing_source: GnssTimingSource,
     pub power_mode: GnssPowerSaveMode,
 }
@@ -299,6 +391,7 @@ impl Default for GnssConfig {
             elevation_threshold_angle: 5,
             use_case: Default::default(),
             nmea_mask: Default::default(),
+            systems: Default::default(),
             timing_source: Default::default(),
             power_mode: Default::default(),
         }
@@ -404,31 +497,7 @@ impl GnssData {
                     Ok(GnssData::PositionVelocityTime(data.assume_init()))
                 }
             }
-            GnssDataType::Nmea => {
-                let mut data: MaybeUninit<nrfxlib_sys::nrf_modem_gnss_nmea_data_frame> =
-                    MaybeUninit::uninit();
-
-                unsafe {
-                    nrfxlib_sys::nrf_modem_gnss_read(
-                        data.as_mut_ptr() as *mut _,
-                        size_of::<nrfxlib_sys::nrf_modem_gnss_nmea_data_frame>() as i32,
-                        data_type as u32 as _,
-                    )
-                    .into_result()?;
-
-                    let data =
-                        core::mem::transmute::<[i8; 83], [u8; 83]>(data.assume_init().nmea_str); // Make data be u8
-                    let mut string_data = ArrayString::from_byte_string(&data)?;
-                    string_data.truncate(
-                        string_data
-                            .as_bytes()
-                            .iter()
-                            .take_while(|b| **b != 0)
-                            .count(),
-                    );
-                    Ok(GnssData::Nmea(string_data))
-                }
-            }
+            GnssDataType::Nmea => Self::read_nmea_from_modem().map(GnssData::Nmea),
             GnssDataType::Agps => {
                 let mut data = MaybeUninit::uninit();
 
@@ -444,6 +513,344 @@ impl GnssData {
             }
         }
     }
+
+    // The strings are kept on their own, because a PVT frame is a lot bigger than a string
+    fn read_nmea_from_modem() -> Result<ArrayString<83>, Error> {
+        let mut data: MaybeUninit<nrfxlib_sys::nrf_modem_gnss_nmea_data_frame> =
+            MaybeUninit::uninit();
+
+        unsafe {
+            nrfxlib_sys::nrf_modem_gnss_read(
+                data.as_mut_ptr() as *mut _,
+                size_of::<nrfxlib_sys::nrf_modem_gnss_nmea_data_frame>() as i32,
+                GnssDataType::Nmea as u32 as _,
+            )
+            .into_result()?;
+
+            let data = core::mem::transmute::<[i8; 83], [u8; 83]>(data.assume_init().nmea_str); // Make data be u8
+            let mut string_data = ArrayString::from_byte_string(&data)?;
+            string_data.truncate(
+                string_data
+                    .as_bytes()
+                    .iter()
+                    .take_while(|b| **b != 0)
+                    .count(),
+            );
+            Ok(string_data)
+        }
+    }
+}
+
+impl GnssData {
+    /// Get the typed position fix if this is a PVT frame that contains a valid fix
+    pub fn position_fix(&self) -> Option<GnssPositionFix> {
+        match self {
+            GnssData::PositionVelocityTime(pvt)
+                if pvt.flags as u32 & nrfxlib_sys::NRF_MODEM_GNSS_PVT_FLAG_FIX_VALID != 0 =>
+            {
+                Some(GnssPositionFix::from(pvt))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the UTC time of the GNSS if this is a PVT frame that has a time, e.g. to set a clock without network time.
+    ///
+    /// Check [GnssTime::fix_valid] before using it, because the time of a frame without a fix may not be correct.
+    pub fn time(&self) -> Option<GnssTime> {
+        match self {
+            // The date is all zeros until the GNSS knows the time
+            GnssData::PositionVelocityTime(pvt) => GnssTime::new(pvt),
+            _ => None,
+        }
+    }
+
+    /// Get the satellites that were tracked if this is a PVT frame
+    pub fn satellites(&self) -> Option<ArrayVec<GnssSatellite, 12>> {
+        match self {
+            GnssData::PositionVelocityTime(pvt) => Some(
+                pvt.sv
+                    .iter()
+                    // Unused entries have a zero satellite number
+                    .filter(|sv| sv.sv != 0)
+                    .map(GnssSatellite::from)
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Get the typed assistance data request if this is an A-GNSS request frame
+    pub fn agnss_request(&self) -> Option<AgnssRequest> {
+        match self {
+            GnssData::Agps(frame) => Some(AgnssRequest::from(frame)),
+            _ => None,
+        }
+    }
+}
+
+/// A position fix of the GNSS
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GnssPositionFix {
+    /// Latitude in degrees
+    pub latitude: f64,
+    /// Longitude in degrees
+    pub longitude: f64,
+    /// Altitude above the WGS-84 ellipsoid in meters
+    pub altitude: f32,
+    /// Accuracy (2D 1-sigma) in meters
+    pub accuracy: f32,
+    /// Accuracy of the altitude (1-sigma) in meters
+    pub altitude_accuracy: f32,
+    /// Position dilution of precision
+    pub pdop: f32,
+    /// Horizontal dilution of precision
+    pub hdop: f32,
+    /// Vertical dilution of precision
+    pub vdop: f32,
+    /// Time dilution of precision
+    pub tdop: f32,
+    /// Horizontal speed in meters per second
+    pub speed: f32,
+    /// Heading of the user movement in degrees
+    pub heading: f32,
+    /// The amount of satellites that were used for the fix
+    pub num_satellites: u8,
+    /// The systems the satellites that were used for the fix belong to
+    pub systems: GnssSystems,
+    /// The UTC date and time of the fix
+    pub timestamp: GnssDateTime,
+}
+
+impl From<&nrfxlib_sys::nrf_modem_gnss_pvt_data_frame> for GnssPositionFix {
+    fn from(pvt: &nrfxlib_sys::nrf_modem_gnss_pvt_data_frame) -> Self {
+        Self {
+            latitude: pvt.latitude,
+            longitude: pvt.longitude,
+            altitude: pvt.altitude,
+            accuracy: pvt.accuracy,
+            altitude_accuracy: pvt.altitude_accuracy,
+            pdop: pvt.pdop,
+            hdop: pvt.hdop,
+            vdop: pvt.vdop,
+            tdop: pvt.tdop,
+            speed: pvt.speed,
+            heading: pvt.heading,
+            num_satellites: pvt
+                .sv
+                .iter()
+                .filter(|sv| {
+                    sv.sv != 0
+                        && sv.flags as u32 & nrfxlib_sys::NRF_MODEM_GNSS_SV_FLAG_USED_IN_FIX != 0
+                })
+                .count() as u8,
+            systems: GnssSystems::used_in_fix(&pvt.sv),
+            timestamp: pvt.datetime.into(),
+        }
+    }
+}
+
+/// A satellite that is tracked by the GNSS
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GnssSatellite {
+    /// The satellite number (PRN)
+    pub prn: u16,
+    /// The signal type, one of the `NRF_MODEM_GNSS_SIGNAL_*` values
+    pub signal: u8,
+    /// The carrier-to-noise density ratio in dB-Hz
+    pub cnr: f32,
+    /// The elevation angle in degrees
+    pub elevation: i16,
+    /// The azimuth angle in degrees
+    pub azimuth: i16,
+    /// Whether the satellite was used in the fix
+    pub used_in_fix: bool,
+    /// Whether the satellite is marked as unhealthy
+    pub unhealthy: bool,
+}
+
+impl From<&nrfxlib_sys::nrf_modem_gnss_sv> for GnssSatellite {
+    fn from(sv: &nrfxlib_sys::nrf_modem_gnss_sv) -> Self {
+        Self {
+            prn: sv.sv,
+            signal: sv.signal,
+            // The modem reports this in 0.1 dB-Hz
+            cnr: sv.cn0 as f32 / 10.0,
+            elevation: sv.elevation,
+            azimuth: sv.azimuth,
+            used_in_fix: sv.flags as u32 & nrfxlib_sys::NRF_MODEM_GNSS_SV_FLAG_USED_IN_FIX != 0,
+            unhealthy: sv.flags as u32 & nrfxlib_sys::NRF_MODEM_GNSS_SV_FLAG_UNHEALTHY != 0,
+        }
+    }
+}
+
+/// A UTC date and time as reported by the GNSS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GnssDateTime {
+    /// 4-digit year
+    pub year: u16,
+    /// 1..=12
+    pub month: u8,
+    /// 1..=31
+    pub day: u8,
+    /// 0..=23
+    pub hour: u8,
+    /// 0..=59
+    pub minute: u8,
+    /// 0..=59
+    pub seconds: u8,
+    /// 0..=999
+    pub ms: u16,
+}
+
+impl GnssDateTime {
+    /// The seconds since 1970-01-01, without the milliseconds.
+    ///
+    /// Returns `None` for a date before 1970 or a date that isn't valid, like the zeroed date the GNSS
+    /// reports before it knows the time.
+    pub fn unix_time(&self) -> Option<u64> {
+        crate::network_time::unix_time(
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.seconds,
+        )
+    }
+}
+
+impl From<nrfxlib_sys::nrf_modem_gnss_datetime> for GnssDateTime {
+    fn from(datetime: nrfxlib_sys::nrf_modem_gnss_datetime) -> Self {
+        Self {
+            year: datetime.year,
+            month: datetime.month,
+            day: datetime.day,
+            hour: datetime.hour,
+            minute: datetime.minute,
+            seconds: datetime.seconds,
+            ms: datetime.ms,
+        }
+    }
+}
+
+/// The UTC time as it was given by the GNSS, see [GnssData::time]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GnssTime {
+    pub datetime: GnssDateTime,
+    /// The UTC time as milliseconds since 1970-01-01
+    pub unix_time_ms: u64,
+    /// The time belongs to a valid fix. Without a fix the time may be off.
+    pub fix_valid: bool,
+    /// The GNSS has decoded the GPS to UTC leap seconds from the satellites.
+    /// Until then the modem uses 18 seconds, which is correct since 2017.
+    pub leap_second_valid: bool,
+}
+
+impl GnssTime {
+    fn new(pvt: &nrfxlib_sys::nrf_modem_gnss_pvt_data_frame) -> Option<Self> {
+        let datetime = GnssDateTime::from(pvt.datetime);
+
+        Some(Self {
+            datetime,
+            unix_time_ms: datetime.unix_time()? * 1000 + datetime.ms as u64,
+            fix_valid: pvt.flags as u32 & nrfxlib_sys::NRF_MODEM_GNSS_PVT_FLAG_FIX_VALID != 0,
+            leap_second_valid: pvt.flags as u32
+                & nrfxlib_sys::NRF_MODEM_GNSS_PVT_FLAG_LEAP_SECOND_VALID
+                != 0,
+        })
+    }
+}
+
+/// Write assistance (A-GNSS) data of the given type to the GNSS, so it can get a fix a lot faster
+fn write_agnss_data(data_type: AgnssDataType, data: &[u8]) -> Result<(), Error> {
+    unsafe {
+        nrfxlib_sys::nrf_modem_gnss_agnss_write(
+            data.as_ptr() as *mut _,
+            data.len() as i32,
+            data_type.into(),
+        )
+        .into_result()
+    }
+}
+
+/// The types of assistance data that can be written to the GNSS.
+///
+/// Assistance data from a cloud service often contains multiple types and then has to be split up and
+/// written one type at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u16)]
+pub enum AgnssDataType {
+    /// `nrf_modem_gnss_agnss_gps_data_utc`
+    GpsUtcParameters = nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_GPS_UTC_PARAMETERS as u16,
+    /// `nrf_modem_gnss_agnss_gps_data_ephemeris`
+    GpsEphemerides = nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_GPS_EPHEMERIDES as u16,
+    /// `nrf_modem_gnss_agnss_gps_data_almanac`
+    GpsAlmanac = nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_GPS_ALMANAC as u16,
+    /// `nrf_modem_gnss_agnss_data_klobuchar`
+    KlobucharIonosphericCorrection =
+        nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_KLOBUCHAR_IONOSPHERIC_CORRECTION as u16,
+    /// `nrf_modem_gnss_agnss_data_nequick`
+    NequickIonosphericCorrection =
+        nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_NEQUICK_IONOSPHERIC_CORRECTION as u16,
+    /// `nrf_modem_gnss_agnss_gps_data_system_time_and_sv_tow`
+    GpsSystemClockAndTows = nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_GPS_SYSTEM_CLOCK_AND_TOWS as u16,
+    /// `nrf_modem_gnss_agnss_data_location`
+    Location = nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_LOCATION as u16,
+    /// `nrf_modem_gnss_agnss_data_integrity`
+    Integrity = nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_INTEGRITY as u16,
+}
+
+/// The assistance data the GNSS asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AgnssRequest {
+    /// GPS UTC parameters
+    pub utc_parameters: bool,
+    /// Klobuchar ionospheric correction parameters
+    pub klobuchar_ionospheric_correction: bool,
+    /// NeQuick ionospheric correction parameters
+    pub nequick_ionospheric_correction: bool,
+    /// GPS system time and satellite time of week
+    pub system_time_and_sv_tow: bool,
+    /// The approximate position of the device
+    pub position: bool,
+    /// Integrity data
+    pub integrity: bool,
+    /// Bitmask of the GPS satellites (bit 0 is PRN 1) whose ephemerides are needed
+    pub gps_ephemerides_mask: u64,
+    /// Bitmask of the GPS satellites (bit 0 is PRN 1) whose almanacs are needed
+    pub gps_almanac_mask: u64,
+}
+
+impl From<&nrfxlib_sys::nrf_modem_gnss_agnss_data_frame> for AgnssRequest {
+    fn from(frame: &nrfxlib_sys::nrf_modem_gnss_agnss_data_frame) -> Self {
+        let flag = |mask: u32| frame.data_flags & mask != 0;
+
+        let gps = frame.system[..(frame.system_count as usize).min(frame.system.len())]
+            .iter()
+            .find(|system| system.system_id as u32 == nrfxlib_sys::NRF_MODEM_GNSS_SYSTEM_GPS);
+
+        Self {
+            utc_parameters: flag(nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_GPS_UTC_REQUEST),
+            klobuchar_ionospheric_correction: flag(
+                nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_KLOBUCHAR_REQUEST,
+            ),
+            nequick_ionospheric_correction: flag(nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_NEQUICK_REQUEST),
+            system_time_and_sv_tow: flag(
+                nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_GPS_SYS_TIME_AND_SV_TOW_REQUEST,
+            ),
+            position: flag(nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_POSITION_REQUEST),
+            integrity: flag(nrfxlib_sys::NRF_MODEM_GNSS_AGNSS_INTEGRITY_REQUEST),
+            gps_ephemerides_mask: gps.map(|system| system.sv_mask_ephe).unwrap_or_default(),
+            gps_almanac_mask: gps.map(|system| system.sv_mask_alm).unwrap_or_default(),
+        }
+    }
 }
 
 /// An async stream of gnss data.
@@ -465,6 +872,30 @@ impl GnssStream {
         }
     }
 
+    /// Write assistance data of the given type to the running GNSS.
+    /// This is typically done as a response to a [GnssData::Agps] request.
+    ///
+    /// The data must have the layout of the `nrf_modem_gnss_agnss_*` struct in [nrfxlib_sys] that belongs to the type.
+    /// See [AgnssDataType].
+    pub fn write_agnss_data(&self, data_type: AgnssDataType, data: &[u8]) -> Result<(), Error> {
+        write_agnss_data(data_type, data)
+    }
+
+    /// Give the GNSS priority over LTE until the returned guard is released or dropped.
+    ///
+    /// LTE and GNSS can't run at the same time, so when LTE idle mode is busy the GNSS may not get enough time for a fix.
+    /// This shows up as the `NRF_MODEM_GNSS_PVT_FLAG_NOT_ENOUGH_WINDOW_TIME` flag in several PVT frames in a row.
+    /// With priority, the GNSS can interrupt LTE idle mode operations. Active LTE connections are not stopped.
+    ///
+    /// The modem drops the priority by itself after the first fix.
+    pub fn request_priority(&self) -> Result<GnssPriorityGuard, Error> {
+        unsafe {
+            nrfxlib_sys::nrf_modem_gnss_prio_mode_enable().into_result()?;
+        }
+
+        Ok(GnssPriorityGuard { _private: () })
+    }
+
     pub async fn deactivate(self) -> Result<(), Error> {
         self.free().deactivate().await
     }
@@ -473,6 +904,76 @@ impl GnssStream {
     pub fn free(mut self) -> Gnss {
         self.gnss.take().unwrap()
     }
+
+    /// Turn this into a stream of only the raw NMEA strings, e.g. to feed them to an existing NMEA parser.
+    ///
+    /// The sentence types are selected with the [GnssConfig::nmea_mask] the GNSS was started with.
+    pub fn into_nmea_stream(self) -> GnssNmeaStream {
+        GnssNmeaStream { inner: self }
+    }
+}
+
+/// An async stream of the raw NMEA strings of the GNSS, made with [GnssStream::into_nmea_stream].
+///
+/// Every item is one complete sentence, starting with the `$` and without the line ending.
+/// The other GNSS data is skipped.
+///
+/// Implements the [futures::Stream] trait for polling.
+pub struct GnssNmeaStream {
+    inner: GnssStream,
+}
+
+impl GnssNmeaStream {
+    pub async fn deactivate(self) -> Result<(), Error> {
+        self.inner.deactivate().await
+    }
+
+    /// Get back the gnss instance
+    pub fn free(self) -> Gnss {
+        self.inner.free()
+    }
+}
+
+impl Stream for GnssNmeaStream {
+    type Item = Result<ArrayString<83>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match Pin::new(&mut self.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(GnssData::Nmea(string)))) => {
+                    return Poll::Ready(Some(Ok(string)))
+                }
+                Poll::Ready(Some(Ok(_))) => continue,
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Keeps the GNSS priority over LTE active. See [GnssStream::request_priority].
+///
+/// Dropping the guard releases the priority.
+pub struct GnssPriorityGuard {
+    _private: (),
+}
+
+impl GnssPriorityGuard {
+    /// Release the GNSS priority so LTE can run again
+    pub fn release(self) -> Result<(), Error> {
+        core::mem::forget(self);
+        unsafe { nrfxlib_sys::nrf_modem_gnss_prio_mode_disable().into_result() }
+    }
+}
+
+impl Drop for GnssPriorityGuard {
+    fn drop(&mut self) {
+        if let Err(_e) = unsafe { nrfxlib_sys::nrf_modem_gnss_prio_mode_disable().into_result() } {
+            #[cfg(feature = "defmt")]
+            defmt::error!("Could not release the gnss priority: {}", _e);
+        }
+    }
 }
 
 impl Stream for GnssStream {
@@ -507,7 +1008,7 @@ impl Stream for GnssStream {
             GnssEventType::Nmea => critical_section::with(|cs| {
                 let mut strings = GNSS_NMEA_STRINGS.borrow_ref_mut(cs);
                 left_over_nmea_strings = strings.len() > 1;
-                strings.pop_at(0)
+                strings.pop_at(0).map(|string| string.map(GnssData::Nmea))
             }),
             GnssEventType::AgpsRequest => Some(GnssData::read_from_modem(GnssDataType::Agps)),
             GnssEventType::RetryTimeoutReached | GnssEventType::SleepAfterFix
@@ -4,7 +4,7 @@
 
 use crate::error::ErrorSource;
 use core::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     ops::Range,
     sync::atomic::{AtomicBool, Ordering},
 };
@@ -14,16 +14,39 @@ use linked_list_allocator::Heap;
 
 mod at;
 mod at_notifications;
+#[cfg(feature = "embassy-time")]
+mod backoff;
+mod band_lock;
 mod cancellation;
+mod connection_evaluation;
+pub mod credentials;
+mod data_usage;
 mod dns;
+#[cfg(feature = "embassy-time")]
+mod dns_cache;
 mod dtls_socket;
+mod edrx;
 mod error;
 pub mod ffi;
+mod functional_mode;
 mod gnss;
 pub(crate) mod ip;
 mod lte_link;
+mod modem_fault;
+mod modem_info;
+mod modem_sleep;
+mod modem_trace;
+mod network_time;
+mod operator;
+mod pdn;
+mod psm;
+mod registration;
+mod serving_cell;
+mod shared_socket;
+mod signal_quality;
 mod sms;
 pub(crate) mod socket;
+mod tcp_listener;
 mod tcp_stream;
 mod udp_socket;
 pub(crate) mod waker_node_list;
@@ -33,13 +56,39 @@ pub use nrfxlib_sys;
 
 pub use at::*;
 pub use at_notifications::AtNotificationStream;
+#[cfg(feature = "embassy-time")]
+pub use backoff::*;
+pub use band_lock::*;
 pub use cancellation::CancellationToken;
+pub use connection_evaluation::*;
+pub use data_usage::*;
 pub use dns::*;
+#[cfg(feature = "embassy-time")]
+pub use dns_cache::*;
 pub use dtls_socket::*;
+pub use edrx::*;
 pub use error::Error;
+pub use functional_mode::*;
 pub use gnss::*;
 pub use lte_link::LteLink;
+pub use modem_fault::*;
+pub use modem_info::*;
+pub use modem_sleep::*;
+pub use modem_trace::*;
+pub use network_time::*;
+pub use operator::*;
+pub use pdn::*;
+pub use psm::*;
+pub use registration::*;
+pub use serving_cell::*;
+pub use shared_socket::*;
+pub use signal_quality::*;
 pub use sms::*;
+pub use socket::{
+    available_sockets, poll_sockets, ReleaseAssistance, ReuniteError, SocketInterest,
+    SocketOptionError, SocketReadiness, SocketStats,
+};
+pub use tcp_listener::*;
 pub use tcp_stream::*;
 pub use udp_socket::*;
 
@@ -74,6 +123,8 @@ static TX_ALLOCATOR: WrappedHeap = Mutex::new(RefCell::new(None));
 
 pub(crate) static MODEM_RUNTIME_STATE: RuntimeState = RuntimeState::new();
 static INITIALIZED: AtomicBool = AtomicBool::new(false);
+/// The configuration [init_with_custom_layout] was called with, so [recover] can start the library the same way
+static INIT_CONFIG: Mutex<Cell<Option<(SystemMode, MemoryLayout)>>> = Mutex::new(Cell::new(None));
 
 /// Start the NRF Modem library
 pub async fn init(mode: SystemMode) -> Result<(), Error> {
@@ -89,6 +140,16 @@ pub async fn init_with_custom_layout(
         return Err(Error::ModemAlreadyInitialized);
     }
 
+    let result = start_library(mode, memory_layout).await;
+    if result.is_ok() {
+        critical_section::with(|cs| INIT_CONFIG.borrow(cs).set(Some((mode, memory_layout))));
+    }
+
+    result
+}
+
+/// Set up and start the modem library, and turn off the modem
+async fn start_library(mode: SystemMode, memory_layout: MemoryLayout) -> Result<(), Error> {
     const SHARED_MEMORY_RANGE: Range<u32> = 0x2000_0000..0x2002_0000;
 
     if !SHARED_MEMORY_RANGE.contains(&memory_layout.base_address) {
@@ -188,17 +249,51 @@ pub async fn init_with_custom_layout(
         at::send_at::<0>("AT+CFUN=0").await?;
     }
 
-    if !mode.is_valid_config() {
-        return Err(Error::InvalidSystemModeConfig);
-    }
+    mode.apply().await
+}
+
+/// Turn off the modem and stop the NRF Modem library, e.g. before going into a deep sleep of the whole chip.
+///
+/// This can only be done while there are no [LteLink] or [Gnss] instances (including the ones held by sockets),
+/// otherwise [Error::ModemInUse] is returned. After this, [init] can be called again.
+pub async fn shutdown() -> Result<(), Error> {
+    MODEM_RUNTIME_STATE.shutdown().await
+}
 
-    let mut buffer = [0; 64];
-    mode.create_at_command(&mut buffer)?;
-    at::send_at_bytes::<0>(&buffer).await?;
+/// Restart the modem library and bring the modem back to the state the existing [LteLink] and [Gnss] instances expect.
+///
+/// This is the way out when the modem has faulted (see [wait_for_modem_fault]) or doesn't respond anymore.
+/// The library is shut down and started again with the configuration of [init], and then LTE and GNSS
+/// are turned on again if there are links or GNSS instances that need them.
+///
+/// All sockets are closed by the restart. Their waiting operations return [Error::SocketInvalidated],
+/// and so does every later operation on them, so they should be dropped.
+/// The same goes for extra [PdnContext]s, which have to be created again. A [Gnss] has to be created again to start a new fix.
+///
+/// This waits for the AT command that is in progress, so cancel those first by dropping their futures.
+pub async fn recover() -> Result<(), Error> {
+    MODEM_RUNTIME_STATE.recover().await
+}
 
-    mode.setup_psm().await?;
+/// Change the system mode after the modem has been initialized with [init].
+///
+/// This selects which of LTE-M, NB-IoT and GNSS can be used and which network type is preferred.
+/// The modem only accepts this while it's turned off, so there must not be any [LteLink] or [Gnss] instance.
+/// Otherwise [Error::ModemInUse] is returned.
+///
+/// If the configuration is not valid, [Error::InvalidSystemModeConfig] is returned.
+/// If the modem firmware doesn't support the combination, the modem rejects it and an AT error is returned.
+pub async fn set_system_mode(mode: SystemMode) -> Result<(), Error> {
+    MODEM_RUNTIME_STATE.set_system_mode(mode).await
+}
 
-    Ok(())
+/// Set the functional mode of the modem with `AT+CFUN`, e.g. to go into flight mode.
+///
+/// The [LteLink] and [Gnss] instances turn the parts of the modem they need on and off by themselves.
+/// So this can only be used while none of them exist, otherwise [Error::ModemInUse] is returned.
+/// When the first link or GNSS instance is created again, it turns its part of the modem back on.
+pub async fn set_functional_mode(mode: FunctionalMode) -> Result<(), Error> {
+    MODEM_RUNTIME_STATE.set_functional_mode(mode).await
 }
 
 /// The memory layout used by the modem library.
@@ -207,6 +302,7 @@ pub async fn init_with_custom_layout(
 /// This also contains the fixed [nrfxlib_sys::NRF_MODEM_SHMEM_CTRL_SIZE].
 ///
 /// Nordic guide: https://developer.nordicsemi.com/nRF_Connect_SDK/doc/2.4.1/nrfxlib/nrf_modem/doc/architecture.html#shared-memory-configuration
+#[derive(Debug, Clone, Copy)]
 pub struct MemoryLayout {
     /// The start of the memory area
     pub base_address: u32,
@@ -214,7 +310,7 @@ pub struct MemoryLayout {
     pub tx_area_size: u32,
     /// The buffer size of the socket receive operations, as well as received AT commands, gnss messages and TLS certs
     pub rx_area_size: u32,
-    /// The buffer size of the trace logs
+    /// The buffer size of the trace logs. This must not be 0 to use [enable_modem_traces].
     pub trace_area_size: u32,
 }
 
@@ -224,19 +320,17 @@ impl Default for MemoryLayout {
             base_address: 0x2001_0000,
             tx_area_size: 0x2000,
             rx_area_size: 0x2000,
-            // Trace is not implemented yet
+            // Only needed for the modem traces, see `enable_modem_traces`
             trace_area_size: 0,
         }
     }
 }
 
-unsafe extern "C" fn modem_fault_handler(_info: *mut nrfxlib_sys::nrf_modem_fault_info) {
-    #[cfg(feature = "defmt")]
-    defmt::error!(
-        "Modem fault - reason: {}, pc: {}",
-        (*_info).reason,
-        (*_info).program_counter
-    );
+unsafe extern "C" fn modem_fault_handler(info: *mut nrfxlib_sys::nrf_modem_fault_info) {
+    modem_fault::report_fault(ModemFault {
+        reason: (*info).reason,
+        program_counter: (*info).program_counter,
+    });
 }
 
 unsafe extern "C" fn modem_dfu_handler(_val: u32) {
@@ -244,7 +338,22 @@ unsafe extern "C" fn modem_dfu_handler(_val: u32) {
     defmt::trace!("Modem DFU handler");
 }
 
+/// The handler of the `IPC` interrupt, which must call this.
+///
 /// IPC code now lives outside `lib_modem`, so call our IPC handler function.
+/// The modem library calls back into this crate from here, so the socket operations, AT commands,
+/// AT notifications and GNSS streams that wait for the modem are woken up directly from the interrupt.
+/// That path doesn't allocate and only takes short critical sections.
+///
+/// The interrupt is unmasked by [init], but the vector must be bound to this function:
+///
+/// ```rust,ignore
+/// #[interrupt]
+/// #[allow(non_snake_case)]
+/// fn IPC() {
+///     nrf_modem::ipc_irq_handler();
+/// }
+/// ```
 pub fn ipc_irq_handler() {
     unsafe {
         crate::ffi::nrf_ipc_irq_handler();
@@ -302,6 +411,55 @@ impl SystemMode {
         }
     }
 
+    /// Get the system mode that is currently configured in the modem
+    pub async fn current() -> Result<Self, Error> {
+        let (lte_support, nbiot_support, gnss_support, preference) =
+            at_commands::parser::CommandParser::parse(
+                at::send_at::<64>("AT%XSYSTEMMODE?").await?.as_bytes(),
+            )
+            .expect_identifier(b"%XSYSTEMMODE: ")
+            .expect_int_parameter()
+            .expect_int_parameter()
+            .expect_int_parameter()
+            .expect_int_parameter()
+            .expect_identifier(b"\r\nOK\r\n")
+            .finish()?;
+
+        let (lte_psm_support,) = at_commands::parser::CommandParser::parse(
+            at::send_at::<64>("AT+CPSMS?").await?.as_bytes(),
+        )
+        .expect_identifier(b"+CPSMS: ")
+        .expect_int_parameter()
+        .finish()?;
+
+        Ok(Self {
+            lte_support: lte_support == 1,
+            lte_psm_support: lte_psm_support == 1,
+            nbiot_support: nbiot_support == 1,
+            gnss_support: gnss_support == 1,
+            preference: match preference {
+                0 => ConnectionPreference::None,
+                1 => ConnectionPreference::Lte,
+                2 => ConnectionPreference::Nbiot,
+                3 => ConnectionPreference::NetworkPreferenceWithLteFallback,
+                4 => ConnectionPreference::NetworkPreferenceWithNbiotFallback,
+                _ => return Err(Error::UnexpectedAtResponse),
+            },
+        })
+    }
+
+    async fn apply(&self) -> Result<(), Error> {
+        if !self.is_valid_config() {
+            return Err(Error::InvalidSystemModeConfig);
+        }
+
+        let mut buffer = [0; 64];
+        let command = self.create_at_command(&mut buffer)?;
+        at::send_at_command(core::str::from_utf8(command)?, &mut [0; 16]).await?;
+
+        self.setup_psm().await
+    }
+
     fn create_at_command<'a>(&self, buffer: &'a mut [u8]) -> Result<&'a [u8], Error> {
         at_commands::builder::CommandBuilder::create_set(buffer, true)
             .named("%XSYSTEMMODE")
@@ -476,6 +634,77 @@ impl RuntimeState {
         Ok(())
     }
 
+    pub(crate) async fn set_system_mode(&self, mode: SystemMode) -> Result<(), Error> {
+        // Keep the lock so nothing can turn on the modem while we're changing the mode
+        let state = self.state.lock().await;
+
+        if state.0 || state.1 > 0 {
+            return Err(Error::ModemInUse);
+        }
+
+        mode.apply().await
+    }
+
+    pub(crate) async fn set_functional_mode(&self, mode: FunctionalMode) -> Result<(), Error> {
+        // Keep the lock so nothing can turn on the modem while we're changing the mode
+        let state = self.state.lock().await;
+
+        if state.0 || state.1 > 0 {
+            return Err(Error::ModemInUse);
+        }
+
+        mode.apply().await
+    }
+
+    pub(crate) async fn shutdown(&self) -> Result<(), Error> {
+        // Keep the lock so nothing can turn on the modem while it's going down
+        let state = self.state.lock().await;
+
+        if state.0 || state.1 > 0 {
+            return Err(Error::ModemInUse);
+        }
+
+        // The library must only be shut down when the modem is turned off
+        at::send_at::<0>("AT+CFUN=0").await?;
+        unsafe { nrfxlib_sys::nrf_modem_shutdown() }.into_result()?;
+
+        INITIALIZED.store(false, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    pub(crate) async fn recover(&self) -> Result<(), Error> {
+        // Keep the lock so nothing can turn on the modem while it's restarted
+        let state = self.state.lock().await;
+
+        let (mode, memory_layout) = critical_section::with(|cs| INIT_CONFIG.borrow(cs).get())
+            .filter(|_| INITIALIZED.load(Ordering::SeqCst))
+            .ok_or(Error::ModemNotInitialized)?;
+
+        #[cfg(feature = "defmt")]
+        defmt::warn!("Recovering the modem");
+
+        // A faulted modem doesn't answer AT commands anymore, and the library can be shut down without it
+        if unsafe { nrfxlib_sys::nrf_modem_is_initialized() } {
+            at::send_at::<0>("AT+CFUN=0").await.ok();
+        }
+        // This fails when the library is already down, which is fine
+        unsafe { nrfxlib_sys::nrf_modem_shutdown() };
+
+        socket::invalidate_sockets();
+
+        start_library(mode, memory_layout).await?;
+
+        if state.1 > 0 {
+            ModemActivation::Lte.act_on_modem().await?;
+        }
+        if state.0 {
+            ModemActivation::Gnss.act_on_modem().await?;
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn set_error_active(&self) {
         self.error.store(true, Ordering::SeqCst);
     }
@@ -1,7 +1,15 @@
 //! Implementation of [LteLink]
 
-use crate::{at, at_notifications::AtNotificationStream, error::Error, CancellationToken};
+use crate::{
+    at,
+    at_notifications::AtNotificationStream,
+    error::Error,
+    registration::{RegistrationState, RegistrationStatus},
+    CancellationToken,
+};
+use arrayvec::ArrayVec;
 use core::{mem, ops::ControlFlow, task::Poll};
+use no_std_net::IpAddr;
 
 /// An object that keeps the modem connected.
 /// As long as there is an instance, the modem will be kept on.
@@ -31,6 +39,29 @@ impl LteLink {
         Ok(LteLink(()))
     }
 
+    /// Create a new instance and wait until the modem is registered to the network,
+    /// but for no longer than the given timeout.
+    ///
+    /// [Self::new] only turns on LTE and returns right away, so it can't hang on a network that is never found.
+    /// This function also waits for the registration, and turns LTE off again if that fails.
+    /// Returns [Error::Timeout] if the modem didn't register in time and [Error::OperationCancelled]
+    /// if the token got cancelled, so e.g. a startup sequence can move on to an offline mode.
+    #[cfg(feature = "embassy-time")]
+    pub async fn new_with_cancellation(
+        timeout: embassy_time::Duration,
+        token: &CancellationToken,
+    ) -> Result<Self, Error> {
+        let link = Self::new().await?;
+
+        match link.wait_for_registration(timeout, token).await {
+            Ok(()) => Ok(link),
+            Err(e) => {
+                link.deactivate().await?;
+                Err(e)
+            }
+        }
+    }
+
     /// While there is an instance of the LteLink, the modem is active.
     /// But that does not mean that there is access to the network.
     ///
@@ -54,20 +85,15 @@ impl LteLink {
 
         // We're gonna be looking for notifications. And to make sure we don't miss one,
         // we already create the stream and register it.
-        let notification_stream = AtNotificationStream::<64, 4>::new().await;
+        let notification_stream = AtNotificationStream::<128, 4>::with_prefix("+CEREG:").await;
         futures::pin_mut!(notification_stream);
         notification_stream.as_mut().register().await;
 
-        // Enable the notifications
-        at::send_at::<0>("AT+CEREG=1").await?;
-
         token.as_result()?;
 
         // We won't get a notification if we're already connected.
-        // So query the current status
-        match Self::get_cereg_stat_control_flow(Self::parse_cereg(
-            at::send_at::<64>("AT+CEREG?").await?.as_str(),
-        )) {
+        // So query the current status. This also enables the notifications.
+        match Self::get_cereg_stat_control_flow(RegistrationStatus::query().await) {
             ControlFlow::Continue(_) => {}
             ControlFlow::Break(result) => return result,
         }
@@ -75,8 +101,11 @@ impl LteLink {
         token.as_result()?;
 
         // We are currently not connected, so lets wait for what the stream turns up
-        let mut stream = notification_stream
-            .map(|notif| Self::get_cereg_stat_control_flow(Self::parse_cereg(notif.as_str())));
+        let mut stream = notification_stream.map(|notif| {
+            Self::get_cereg_stat_control_flow(RegistrationStatus::parse_notification(
+                notif.as_str(),
+            ))
+        });
 
         while let Some(cereg) = core::future::poll_fn(|cx| {
             if token.is_cancelled() {
@@ -100,40 +129,69 @@ impl LteLink {
         unreachable!()
     }
 
-    fn parse_cereg(string: &str) -> Result<i32, Error> {
-        // We can expect two kinds of strings here.
-        // The first is the response to our query that ends with 'OK'.
-        // The second is the notification string.
-
-        let cereg = at_commands::parser::CommandParser::parse(string.as_bytes())
-            .expect_identifier(b"+CEREG:")
-            .expect_int_parameter()
-            .expect_int_parameter()
-            .expect_identifier(b"\r\nOK\r\n")
-            .finish()
-            .map(|(_, stat)| stat);
-
-        cereg
-            .or_else(|_| {
-                at_commands::parser::CommandParser::parse(string.as_bytes())
-                    .expect_identifier(b"+CEREG:")
-                    .expect_int_parameter()
-                    .expect_identifier(b"\r\n")
-                    .finish()
-                    .map(|(stat,)| stat)
-            })
-            .map_err(|e| e.into())
+    /// Wait until the modem is registered to the home network or a roaming network,
+    /// but for no longer than the given timeout.
+    ///
+    /// Returns [Error::Timeout] if the modem didn't register in time,
+    /// [Error::LteRegistrationDenied] if the network denied the registration
+    /// and [Error::OperationCancelled] if the token got cancelled.
+    ///
+    /// The LteLink stays active when this returns an error, so the modem keeps trying to register.
+    #[cfg(feature = "embassy-time")]
+    pub async fn wait_for_registration(
+        &self,
+        timeout: embassy_time::Duration,
+        token: &CancellationToken,
+    ) -> Result<(), Error> {
+        embassy_time::with_timeout(timeout, self.wait_for_link_with_cancellation(token))
+            .await
+            .map_err(|_| Error::Timeout)?
     }
 
-    fn get_cereg_stat_control_flow(stat: Result<i32, Error>) -> ControlFlow<Result<(), Error>, ()> {
-        // Based on the stat number, we know that state of the connection
-        match stat {
+    /// Get the IP addresses of the default PDN context by querying `AT+CGPADDR=0`.
+    ///
+    /// A dual stack PDN can have both an IPv4 and an IPv6 address.
+    /// When the modem hasn't attached to the network yet, no addresses are returned.
+    pub async fn ip_addresses(&self) -> Result<ArrayVec<IpAddr, 2>, Error> {
+        let mut buffer = [0; 128];
+        let response = at::send_at_command("AT+CGPADDR=0", &mut buffer).await?;
+
+        // +CGPADDR: <cid>[,<PDP_addr_1>[,<PDP_addr_2>]]
+        let parameters = at::split_parameters(
+            response
+                .trim()
+                .strip_prefix("+CGPADDR:")
+                .ok_or(Error::UnexpectedAtResponse)?,
+        )
+        .skip(1);
+
+        let mut addresses = ArrayVec::new();
+        for address in parameters.filter(|address| !address.is_empty()) {
+            let address = address.parse().map_err(|_| Error::UnexpectedAtResponse)?;
+            addresses
+                .try_push(address)
+                .map_err(|_| Error::UnexpectedAtResponse)?;
+        }
+
+        Ok(addresses)
+    }
+
+    fn get_cereg_stat_control_flow(
+        status: Result<RegistrationStatus, Error>,
+    ) -> ControlFlow<Result<(), Error>, ()> {
+        // Based on the state, we know that state of the connection
+        match status.map(|status| status.state) {
             Err(_) => ControlFlow::Continue(()),
-            Ok(1) | Ok(5) => ControlFlow::Break(Ok(())),
-            Ok(0) | Ok(2) | Ok(4) => ControlFlow::Continue(()),
-            Ok(3) => ControlFlow::Break(Err(Error::LteRegistrationDenied)),
-            Ok(90) => ControlFlow::Break(Err(Error::SimFailure)),
-            _ => ControlFlow::Break(Err(Error::UnexpectedAtResponse)),
+            Ok(RegistrationState::RegisteredHome | RegistrationState::RegisteredRoaming) => {
+                ControlFlow::Break(Ok(()))
+            }
+            Ok(
+                RegistrationState::NotRegistered
+                | RegistrationState::Searching
+                | RegistrationState::Unknown,
+            ) => ControlFlow::Continue(()),
+            Ok(RegistrationState::Denied) => ControlFlow::Break(Err(Error::LteRegistrationDenied)),
+            Ok(RegistrationState::SimFailure) => ControlFlow::Break(Err(Error::SimFailure)),
         }
     }
 
@@ -0,0 +1,76 @@
+//! Reporting of the faults of the modem core
+
+use core::{cell::Cell, task::Poll};
+use critical_section::Mutex;
+use futures::task::AtomicWaker;
+
+/// A fault of the modem core, as reported by the modem library
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ModemFault {
+    /// The reason of the fault, which is one of the `NRF_MODEM_FAULT_*` values of [nrfxlib_sys]
+    pub reason: u32,
+    /// The program counter of the modem core when the fault happened
+    pub program_counter: u32,
+}
+
+/// The last fault that happened
+static MODEM_FAULT: Mutex<Cell<Option<ModemFault>>> = Mutex::new(Cell::new(None));
+static MODEM_FAULT_WAKER: AtomicWaker = AtomicWaker::new();
+#[allow(clippy::type_complexity)]
+static MODEM_FAULT_HANDLER: Mutex<Cell<Option<fn(ModemFault)>>> = Mutex::new(Cell::new(None));
+
+pub(crate) fn report_fault(fault: ModemFault) {
+    #[cfg(feature = "defmt")]
+    defmt::error!(
+        "Modem fault - reason: {}, pc: {}",
+        fault.reason,
+        fault.program_counter
+    );
+
+    let handler = critical_section::with(|cs| {
+        MODEM_FAULT.borrow(cs).set(Some(fault));
+        MODEM_FAULT_HANDLER.borrow(cs).get()
+    });
+
+    if let Some(handler) = handler {
+        handler(fault);
+    }
+
+    MODEM_FAULT_WAKER.wake();
+}
+
+/// Set a function that is called when the modem faults, e.g. to store the fault for telemetry.
+///
+/// The function is called from the interrupt of the modem library, so it must be short and must not block.
+/// After a fault, the modem doesn't work anymore until the modem library is started again,
+/// see [crate::shutdown] and [crate::init].
+pub fn set_modem_fault_handler(handler: fn(ModemFault)) {
+    critical_section::with(|cs| MODEM_FAULT_HANDLER.borrow(cs).set(Some(handler)));
+}
+
+/// Get the last fault of the modem, if it has faulted since the last call to [take_modem_fault]
+pub fn last_modem_fault() -> Option<ModemFault> {
+    critical_section::with(|cs| MODEM_FAULT.borrow(cs).get())
+}
+
+/// Get and clear the last fault of the modem
+pub fn take_modem_fault() -> Option<ModemFault> {
+    critical_section::with(|cs| MODEM_FAULT.borrow(cs).take())
+}
+
+/// Wait until the modem faults and return the fault, which is then cleared.
+///
+/// Returns right away if there is a fault that wasn't taken yet.
+/// This is meant for a single supervisor task. When multiple tasks wait, only one of them is woken up.
+pub async fn wait_for_modem_fault() -> ModemFault {
+    core::future::poll_fn(|cx| {
+        MODEM_FAULT_WAKER.register(cx.waker());
+
+        match take_modem_fault() {
+            Some(fault) => Poll::Ready(fault),
+            None => Poll::Pending,
+        }
+    })
+    .await
+}
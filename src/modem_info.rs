@@ -0,0 +1,47 @@
+//! Implementation of the modem identification commands (`+CGMR`, `+CGSN` and `%XICCID`)
+
+use crate::{at, error::Error};
+use arrayvec::ArrayString;
+
+/// Get the version of the modem firmware by querying `AT+CGMR`, e.g. `mfw_nrf9160_1.3.5`
+pub async fn firmware_version() -> Result<ArrayString<32>, Error> {
+    let mut buffer = [0; 64];
+    let response = at::send_at_command("AT+CGMR", &mut buffer).await?;
+
+    ArrayString::from(response.trim()).map_err(|_| Error::UnexpectedAtResponse)
+}
+
+/// Get the IMEI of the modem by querying `AT+CGSN=1`
+pub async fn imei() -> Result<ArrayString<15>, Error> {
+    let mut buffer = [0; 64];
+    let response = at::send_at_command("AT+CGSN=1", &mut buffer).await?;
+
+    // +CGSN: "<imei>"
+    let imei = response
+        .trim()
+        .strip_prefix("+CGSN:")
+        .ok_or(Error::UnexpectedAtResponse)?
+        .trim()
+        .trim_matches('"');
+
+    ArrayString::from(imei).map_err(|_| Error::UnexpectedAtResponse)
+}
+
+/// Get the ICCID of the SIM card by querying `AT%XICCID`.
+///
+/// The SIM must be powered, so the modem has to be in a functional mode that activates the UICC.
+pub async fn iccid() -> Result<ArrayString<20>, Error> {
+    let mut buffer = [0; 64];
+    let response = at::send_at_command("AT%XICCID", &mut buffer).await?;
+
+    // %XICCID: <iccid>
+    // An ICCID of 19 digits is padded with an `F`
+    let iccid = response
+        .trim()
+        .strip_prefix("%XICCID:")
+        .ok_or(Error::UnexpectedAtResponse)?
+        .trim()
+        .trim_end_matches(['F', 'f']);
+
+    ArrayString::from(iccid).map_err(|_| Error::UnexpectedAtResponse)
+}
@@ -0,0 +1,140 @@
+//! Implementation of the modem sleep notifications (`%XMODEMSLEEP`)
+
+use crate::{at, at_notifications::AtNotificationStream, error::Error};
+use arrayvec::ArrayString;
+use core::{fmt::Write, task::Poll, time::Duration};
+
+/// The reason the modem goes to sleep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ModemSleepType {
+    /// Power saving mode, see [crate::request_psm]
+    Psm,
+    /// The radio is inactive, e.g. during an eDRX cycle
+    RfInactivity,
+    /// There is no usable network and the modem waits before searching again
+    LimitedService,
+    /// The modem is in flight mode
+    FlightMode,
+    /// The proprietary PSM of the modem
+    ProprietaryPsm,
+}
+
+/// A change in the sleep state of the modem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ModemSleepEvent {
+    /// The modem goes to sleep for the given time
+    Sleeping {
+        sleep_type: ModemSleepType,
+        duration: Duration,
+    },
+    /// The sleep is about to end. This is sent the warning time before the modem wakes up.
+    WakingUp { sleep_type: ModemSleepType },
+}
+
+impl ModemSleepEvent {
+    // Parse `%XMODEMSLEEP: <sleep_type>[,<time>]`
+    fn parse(notification: &str) -> Result<Self, Error> {
+        let mut fields = notification
+            .trim()
+            .strip_prefix("%XMODEMSLEEP:")
+            .ok_or(Error::UnexpectedAtResponse)?
+            .split(',')
+            .map(|field| field.trim());
+
+        let sleep_type = match fields.next() {
+            Some("1") => ModemSleepType::Psm,
+            Some("2") => ModemSleepType::RfInactivity,
+            Some("3") => ModemSleepType::LimitedService,
+            Some("4") => ModemSleepType::FlightMode,
+            Some("5") => ModemSleepType::ProprietaryPsm,
+            _ => return Err(Error::UnexpectedAtResponse),
+        };
+
+        let time: u64 = fields
+            .next()
+            .and_then(|time| time.parse().ok())
+            .unwrap_or(0);
+
+        Ok(match time {
+            0 => ModemSleepEvent::WakingUp { sleep_type },
+            time => ModemSleepEvent::Sleeping {
+                sleep_type,
+                duration: Duration::from_millis(time),
+            },
+        })
+    }
+}
+
+/// An async stream of the sleep state of the modem, using the unsolicited `%XMODEMSLEEP` notifications.
+///
+/// This can be used to only let the application processor sleep while the modem sleeps too.
+///
+/// Implements the [futures::Stream] trait for polling.
+/// `COUNT` is the amount of notifications that can be buffered while the stream is not polled.
+pub struct ModemSleepStream<const COUNT: usize> {
+    notifications: AtNotificationStream<64, COUNT>,
+}
+
+impl<const COUNT: usize> ModemSleepStream<COUNT> {
+    /// Creates a new stream and enables the unsolicited `%XMODEMSLEEP` notifications.
+    ///
+    /// - `warning_time`: How long before the end of a sleep the [ModemSleepEvent::WakingUp] event is sent.
+    ///   The modem accepts 500 ms to 1 hour.
+    /// - `threshold`: Only sleeps that are at least this long are reported. The modem accepts at least 10 seconds.
+    ///
+    /// To access all the nice iterator functions, use [futures::StreamExt].
+    pub async fn new(warning_time: Duration, threshold: Duration) -> Result<Self, Error> {
+        let notifications = AtNotificationStream::with_prefix("%XMODEMSLEEP:").await;
+
+        let mut command = ArrayString::<48>::new();
+        write!(
+            command,
+            "AT%XMODEMSLEEP=1,{},{}",
+            warning_time.as_millis(),
+            threshold.as_millis()
+        )
+        .map_err(|_| Error::BufferTooSmall(None))?;
+        at::send_at_command(&command, &mut [0; 16]).await?;
+
+        Ok(Self { notifications })
+    }
+
+    /// Futures are lazy and can only register themselves once polled.
+    /// Call this function if you want to register this stream early so that it can already receive notifications.
+    pub async fn register(self: core::pin::Pin<&mut Self>) {
+        let notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+        notifications.register().await;
+    }
+}
+
+impl<const COUNT: usize> futures::Stream for ModemSleepStream<COUNT> {
+    type Item = ModemSleepEvent;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+
+        loop {
+            match notifications.as_mut().poll_next(cx) {
+                Poll::Ready(Some(notification)) => {
+                    if let Ok(event) = ModemSleepEvent::parse(&notification) {
+                        return Poll::Ready(Some(event));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Disable the `%XMODEMSLEEP` notifications that were enabled by [ModemSleepStream::new]
+pub async fn disable_modem_sleep_notifications() -> Result<(), Error> {
+    at::send_at_command("AT%XMODEMSLEEP=0", &mut [0; 16]).await?;
+
+    Ok(())
+}
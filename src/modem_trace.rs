@@ -0,0 +1,98 @@
+//! Implementation of the modem traces (`%XMODEMTRACE`)
+
+use crate::{at, error::Error};
+use arrayvec::ArrayString;
+use core::fmt::Write;
+
+/// Which traces the modem outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ModemTraceSet {
+    /// Only the core dump when the modem crashes
+    CoredumpOnly,
+    /// The generic traces and the core dump
+    Generic,
+    /// The LTE and IP traces and the core dump
+    LteAndIp,
+    /// The GNSS traces and the core dump
+    Gnss,
+    /// The LTE, IP and GNSS traces and the core dump
+    LteIpAndGnss,
+}
+
+impl ModemTraceSet {
+    fn as_integer(self) -> u8 {
+        match self {
+            ModemTraceSet::CoredumpOnly => 1,
+            ModemTraceSet::Generic => 2,
+            ModemTraceSet::LteAndIp => 3,
+            ModemTraceSet::Gnss => 4,
+            ModemTraceSet::LteIpAndGnss => 5,
+        }
+    }
+}
+
+/// Let the modem output the given set of traces. The traces can then be read with [read_modem_trace].
+///
+/// The modem library needs a trace area to pass the traces on,
+/// so [crate::init_with_custom_layout] must have been called with a [crate::MemoryLayout::trace_area_size] that is not 0.
+/// The setting is kept by the modem across restarts.
+pub async fn enable_modem_traces(set: ModemTraceSet) -> Result<(), Error> {
+    let mut command = ArrayString::<24>::new();
+    write!(command, "AT%XMODEMTRACE=1,{}", set.as_integer())
+        .map_err(|_| Error::BufferTooSmall(None))?;
+
+    at::send_at_command(&command, &mut [0; 32]).await?;
+
+    Ok(())
+}
+
+/// Stop the modem from outputting traces
+pub async fn disable_modem_traces() -> Result<(), Error> {
+    at::send_at_command("AT%XMODEMTRACE=0", &mut [0; 32]).await?;
+
+    Ok(())
+}
+
+/// Hand the trace data that the modem has output to the given function, without waiting for new data.
+///
+/// The data is opaque and should be sent on as is, e.g. over a UART or RTT channel, to be decoded by the tools of Nordic.
+/// The function can be called multiple times with fragments that should be sent in order.
+/// Returns the amount of bytes that were handed over, which is 0 if there was no trace data.
+///
+/// This should be called regularly while the traces are enabled, because the modem stalls when the trace area is full.
+pub fn read_modem_trace(mut f: impl FnMut(&[u8])) -> Result<usize, Error> {
+    let mut frags: *mut nrfxlib_sys::nrf_modem_trace_data = core::ptr::null_mut();
+    let mut n_frags = 0;
+
+    let result = unsafe {
+        nrfxlib_sys::nrf_modem_trace_get(
+            &mut frags,
+            &mut n_frags,
+            nrfxlib_sys::NRF_MODEM_OS_NO_WAIT as _,
+        )
+    };
+
+    const NRF_EAGAIN: i32 = -(nrfxlib_sys::NRF_EAGAIN as i32);
+    const NRF_ENODATA: i32 = -(nrfxlib_sys::NRF_ENODATA as i32);
+
+    match result {
+        0 => {}
+        NRF_EAGAIN | NRF_ENODATA => return Ok(0),
+        error => return Err(Error::NrfError(error as isize)),
+    }
+
+    let mut total_len = 0;
+    for index in 0..n_frags {
+        let frag = unsafe { &*frags.add(index) };
+        f(unsafe { core::slice::from_raw_parts(frag.data as *const u8, frag.len) });
+        total_len += frag.len;
+    }
+
+    let result = unsafe { nrfxlib_sys::nrf_modem_trace_processed(total_len) };
+    if result != 0 {
+        return Err(Error::NrfError(result as isize));
+    }
+
+    Ok(total_len)
+}
@@ -0,0 +1,241 @@
+//! Implementation of the network time (`+CCLK` and `%XTIME`)
+
+use crate::{at, at_notifications::AtNotificationStream, error::Error};
+use core::task::Poll;
+
+/// The time as it was given by the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NetworkTime {
+    /// The UTC time as seconds since 1970-01-01
+    pub unix_time: u64,
+    /// The offset of the local time to UTC in minutes, including the daylight saving time
+    pub utc_offset_minutes: i16,
+    /// The daylight saving time adjustment in hours that is included in the offset, if the network sent it
+    pub daylight_saving_hours: Option<u8>,
+}
+
+impl NetworkTime {
+    fn new(
+        date_time: [u8; 6],
+        utc_offset_quarters: i16,
+        daylight_saving_hours: Option<u8>,
+    ) -> Result<Self, Error> {
+        let [year, month, day, hour, minute, second] = date_time;
+
+        Ok(Self {
+            unix_time: unix_time(2000 + year as u16, month, day, hour, minute, second)
+                .ok_or(Error::UnexpectedAtResponse)?,
+            utc_offset_minutes: utc_offset_quarters * 15,
+            daylight_saving_hours,
+        })
+    }
+
+    // Parse `+CCLK: "yy/MM/dd,hh:mm:ss±zz"`, which is the local time and the offset in quarters of an hour
+    fn parse_cclk(response: &str) -> Result<Self, Error> {
+        let time = response
+            .trim()
+            .strip_prefix("+CCLK:")
+            .ok_or(Error::UnexpectedAtResponse)?
+            .trim()
+            .trim_matches('"');
+
+        let (local_time, offset) = match time.rfind(['+', '-']) {
+            Some(index) => time.split_at(index),
+            None => return Err(Error::UnexpectedAtResponse),
+        };
+        let offset_quarters: i16 = offset
+            .trim_start_matches('+')
+            .parse()
+            .map_err(|_| Error::UnexpectedAtResponse)?;
+
+        let mut date_time = [0; 6];
+        let mut fields = local_time.split(['/', ',', ':']);
+        for value in date_time.iter_mut() {
+            *value = fields
+                .next()
+                .and_then(|field| field.parse().ok())
+                .ok_or(Error::UnexpectedAtResponse)?;
+        }
+
+        let mut time = Self::new(date_time, offset_quarters, None)?;
+        // The modem gives the local time, so go back to UTC
+        let unix_time = time.unix_time as i64 - time.utc_offset_minutes as i64 * 60;
+        time.unix_time = u64::try_from(unix_time).map_err(|_| Error::UnexpectedAtResponse)?;
+
+        Ok(time)
+    }
+
+    // Parse `%XTIME: "<local_time_zone>","<universal_time>","<daylight_saving_time>"`.
+    // The values are octets in the semi-octet format of 3GPP TS 24.008, and the fields the network didn't send are empty.
+    fn parse_xtime(notification: &str) -> Result<Self, Error> {
+        let mut parameters = at::split_parameters(
+            notification
+                .trim()
+                .strip_prefix("%XTIME:")
+                .ok_or(Error::UnexpectedAtResponse)?,
+        );
+
+        let local_time_zone = parameters.next().ok_or(Error::UnexpectedAtResponse)?;
+        let universal_time = parameters.next().ok_or(Error::UnexpectedAtResponse)?;
+        let daylight_saving = parameters.next().unwrap_or("");
+
+        if universal_time.len() != 14 {
+            return Err(Error::UnexpectedAtResponse);
+        }
+
+        let mut date_time = [0; 6];
+        for (index, value) in date_time.iter_mut().enumerate() {
+            *value = parse_semi_octet(&universal_time[index * 2..][..2])?;
+        }
+
+        // The universal time ends with a time zone too, but the separate field is more up to date
+        let time_zone = match local_time_zone.is_empty() {
+            true => &universal_time[12..],
+            false => local_time_zone,
+        };
+
+        let daylight_saving_hours = match daylight_saving.is_empty() {
+            true => None,
+            false => Some(
+                u8::from_str_radix(daylight_saving, 16).map_err(|_| Error::UnexpectedAtResponse)?,
+            ),
+        };
+
+        Self::new(
+            date_time,
+            parse_time_zone(time_zone)?,
+            daylight_saving_hours,
+        )
+    }
+}
+
+/// Get the time of the network by querying `AT+CCLK?`.
+///
+/// The modem only knows the time once the network has sent it, which most networks do when registering.
+/// Before that the modem answers with an error.
+pub async fn network_time() -> Result<NetworkTime, Error> {
+    let mut buffer = [0; 64];
+    let response = at::send_at_command("AT+CCLK?", &mut buffer).await?;
+
+    NetworkTime::parse_cclk(response)
+}
+
+/// An async stream of the time updates of the network, using the unsolicited `%XTIME` notifications.
+///
+/// The network sends the time when registering and sometimes after that,
+/// so this can be used to keep a software clock in sync.
+///
+/// Implements the [futures::Stream] trait for polling.
+/// `COUNT` is the amount of notifications that can be buffered while the stream is not polled.
+pub struct NetworkTimeStream<const COUNT: usize> {
+    notifications: AtNotificationStream<64, COUNT>,
+}
+
+impl<const COUNT: usize> NetworkTimeStream<COUNT> {
+    /// Creates a new stream and enables the unsolicited `%XTIME` notifications.
+    ///
+    /// To access all the nice iterator functions, use [futures::StreamExt].
+    pub async fn new() -> Result<Self, Error> {
+        let notifications = AtNotificationStream::with_prefix("%XTIME:").await;
+
+        at::send_at_command("AT%XTIME=1", &mut [0; 16]).await?;
+
+        Ok(Self { notifications })
+    }
+
+    /// Futures are lazy and can only register themselves once polled.
+    /// Call this function if you want to register this stream early so that it can already receive notifications.
+    pub async fn register(self: core::pin::Pin<&mut Self>) {
+        let notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+        notifications.register().await;
+    }
+}
+
+impl<const COUNT: usize> futures::Stream for NetworkTimeStream<COUNT> {
+    type Item = NetworkTime;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+
+        loop {
+            match notifications.as_mut().poll_next(cx) {
+                Poll::Ready(Some(notification)) => {
+                    // Updates without the universal time are skipped
+                    if let Ok(time) = NetworkTime::parse_xtime(&notification) {
+                        return Poll::Ready(Some(time));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Disable the `%XTIME` notifications that were enabled by [NetworkTimeStream::new]
+pub async fn disable_network_time_notifications() -> Result<(), Error> {
+    at::send_at_command("AT%XTIME=0", &mut [0; 16]).await?;
+
+    Ok(())
+}
+
+/// Parse an octet in the semi-octet format, where the digits are swapped
+fn parse_semi_octet(octet: &str) -> Result<u8, Error> {
+    let octet = u8::from_str_radix(octet, 16).map_err(|_| Error::UnexpectedAtResponse)?;
+    let (tens, units) = (octet & 0x0F, octet >> 4);
+
+    if tens > 9 || units > 9 {
+        return Err(Error::UnexpectedAtResponse);
+    }
+
+    Ok(tens * 10 + units)
+}
+
+/// Parse a time zone octet, which is a semi-octet in quarters of an hour with the sign in bit 3
+fn parse_time_zone(octet: &str) -> Result<i16, Error> {
+    let octet = u8::from_str_radix(octet, 16).map_err(|_| Error::UnexpectedAtResponse)?;
+    let (tens, units) = (octet & 0x07, octet >> 4);
+
+    if units > 9 {
+        return Err(Error::UnexpectedAtResponse);
+    }
+
+    let quarters = (tens * 10 + units) as i16;
+    Ok(match octet & 0x08 != 0 {
+        true => -quarters,
+        false => quarters,
+    })
+}
+
+/// The seconds since 1970-01-01 of a date and time in UTC.
+///
+/// Returns `None` for a date before 1970, or when the month or the day is 0 or out of range.
+pub(crate) fn unix_time(
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+) -> Option<u64> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year == 0 {
+        return None;
+    }
+
+    // Count the years from March, so the leap day is at the end of the year
+    let (year, month) = match month {
+        1..=2 => (year as u64 - 1, month as u64 + 9),
+        _ => (year as u64, month as u64 - 3),
+    };
+
+    let days_before_year = year * 365 + year / 4 - year / 100 + year / 400;
+    let days_before_month = (153 * month + 2) / 5;
+    // 719468 is the amount of days from 0000-03-01 to 1970-01-01
+    let days = (days_before_year + days_before_month + day as u64 - 1).checked_sub(719468)?;
+
+    Some(days * 86400 + hour as u64 * 3600 + minute as u64 * 60 + second as u64)
+}
@@ -0,0 +1,119 @@
+//! Implementation of the operator selection (`+COPS`)
+
+use crate::{at, error::Error, AccessTechnology};
+use arrayvec::ArrayString;
+use core::fmt::Write;
+
+/// The availability of an operator found by [scan_operators]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OperatorStatus {
+    Unknown,
+    Available,
+    /// The modem is registered to this operator
+    Current,
+    /// The operator may not be used, e.g. because it's on the forbidden list of the SIM
+    Forbidden,
+}
+
+/// An operator found by [scan_operators]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operator {
+    pub status: OperatorStatus,
+    /// The mobile country code and mobile network code as digits, e.g. `"26201"`.
+    /// This can be given to [select_operator].
+    pub plmn: ArrayString<6>,
+    pub access_technology: Option<AccessTechnology>,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Operator {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Operator {{ status: {}, plmn: {}, access_technology: {} }}",
+            self.status,
+            self.plmn.as_str(),
+            self.access_technology
+        )
+    }
+}
+
+impl Operator {
+    // Parse `<stat>,<long_alphanumeric>,<short_alphanumeric>,<numeric>[,<AcT>]`
+    fn parse(fields: &str) -> Option<Self> {
+        let mut fields = at::split_parameters(fields);
+
+        let status = match fields.next()? {
+            "0" => OperatorStatus::Unknown,
+            "1" => OperatorStatus::Available,
+            "2" => OperatorStatus::Current,
+            "3" => OperatorStatus::Forbidden,
+            _ => return None,
+        };
+        let plmn = match fields.nth(2)? {
+            plmn if (5..=6).contains(&plmn.len()) && plmn.bytes().all(|b| b.is_ascii_digit()) => {
+                ArrayString::from(plmn).ok()?
+            }
+            _ => return None,
+        };
+        let access_technology = match fields.next() {
+            Some("7") => Some(AccessTechnology::LteM),
+            Some("9") => Some(AccessTechnology::NbIot),
+            _ => None,
+        };
+
+        Some(Self {
+            status,
+            plmn,
+            access_technology,
+        })
+    }
+}
+
+/// Let the modem select the operator, which is the default
+pub async fn select_operator_automatically() -> Result<(), Error> {
+    at::send_at_command("AT+COPS=0", &mut [0; 32]).await?;
+
+    Ok(())
+}
+
+/// Register to the operator with the given PLMN (the mobile country code and mobile network code, e.g. `"26201"`).
+///
+/// The modem must be active, e.g. by holding an [crate::LteLink].
+/// The modem doesn't fall back to other operators. This returns once the selection has succeeded,
+/// or with [Error::CmeError] if the network rejected the registration or the operator wasn't found.
+pub async fn select_operator(plmn: &str) -> Result<(), Error> {
+    let mut command = ArrayString::<32>::new();
+    write!(command, "AT+COPS=1,2,\"{}\"", plmn).map_err(|_| Error::BufferTooSmall(None))?;
+
+    at::send_at_command(&command, &mut [0; 32]).await?;
+
+    Ok(())
+}
+
+/// Deregister from the network. The modem stays deregistered until an operator is selected again.
+pub async fn deregister_operator() -> Result<(), Error> {
+    at::send_at_command("AT+COPS=2", &mut [0; 32]).await?;
+
+    Ok(())
+}
+
+/// Search for the operators that are around, with `AT+COPS=?`.
+///
+/// The modem must be active, e.g. by holding an [crate::LteLink]. The search can take minutes.
+/// The response is written into the given buffer. Every operator takes about 30 bytes of it.
+pub async fn scan_operators(
+    buffer: &mut [u8],
+) -> Result<impl Iterator<Item = Operator> + '_, Error> {
+    let response = at::send_at_command("AT+COPS=?", buffer).await?;
+
+    // +COPS: (<stat>,"<long>","<short>","<numeric>",<AcT>),(...),,(<modes>),(<formats>)
+    let operators = response
+        .trim()
+        .strip_prefix("+COPS:")
+        .ok_or(Error::UnexpectedAtResponse)?;
+
+    // The lists of the supported modes and formats at the end don't parse as an operator
+    Ok(operators.split(['(', ')']).filter_map(Operator::parse))
+}
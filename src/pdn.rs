@@ -0,0 +1,174 @@
+//! Implementation of the PDN context configuration (`+CGDCONT`)
+
+use crate::{at, error::Error};
+
+/// The IP type of a PDN context
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PdnIpType {
+    Ipv4,
+    Ipv6,
+    /// Dual stack, the network decides which of the two are given
+    Ipv4v6,
+}
+
+impl PdnIpType {
+    fn as_pdp_type(self) -> &'static str {
+        match self {
+            PdnIpType::Ipv4 => "IP",
+            PdnIpType::Ipv6 => "IPV6",
+            PdnIpType::Ipv4v6 => "IPV4V6",
+        }
+    }
+}
+
+/// Configure the APN and IP type of the default PDN context (context 0).
+///
+/// The modem only accepts this while it's offline, so call this after [crate::init] (or [crate::set_system_mode])
+/// and before the first [crate::LteLink] is created. Otherwise the modem answers with an AT error.
+pub async fn configure_default_pdn(apn: &str, ip_type: PdnIpType) -> Result<(), Error> {
+    define_context(0, apn, ip_type).await
+}
+
+async fn define_context(cid: u8, apn: &str, ip_type: PdnIpType) -> Result<(), Error> {
+    let mut buffer = [0; 128];
+    let command = at_commands::builder::CommandBuilder::create_set(&mut buffer, true)
+        .named("+CGDCONT")
+        .with_int_parameter(cid)
+        .with_string_parameter(ip_type.as_pdp_type())
+        .with_string_parameter(apn)
+        .finish()
+        .map_err(|e| Error::BufferTooSmall(Some(e)))?;
+
+    at::send_at_command(core::str::from_utf8(command)?, &mut [0; 16]).await?;
+
+    Ok(())
+}
+
+/// An extra PDN context, e.g. for a private APN next to the default internet APN.
+///
+/// Sockets can be bound to it by passing [Self::pdn_id] to e.g. [crate::TcpConnectConfig::pdn].
+///
+/// The drop function deactivates and removes the context with a blocking AT command.
+/// Use the async [Self::deactivate] to avoid blocking.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PdnContext {
+    cid: u8,
+    pdn_id: u8,
+}
+
+impl PdnContext {
+    /// Create and activate a new PDN context with the given APN.
+    ///
+    /// The modem must be registered to the network to activate the context,
+    /// so keep an [crate::LteLink] alive and wait for it to be connected first.
+    pub async fn create(apn: &str, ip_type: PdnIpType) -> Result<Self, Error> {
+        let mut buffer = [0; 64];
+
+        let (cid,) = at_commands::parser::CommandParser::parse(
+            at::send_at_command("AT%XNEWCID?", &mut buffer)
+                .await?
+                .as_bytes(),
+        )
+        .expect_identifier(b"%XNEWCID: ")
+        .expect_int_parameter()
+        .finish()?;
+        let cid = cid as u8;
+
+        // From here on the drop function cleans up the context
+        let mut context = Self { cid, pdn_id: 0 };
+
+        define_context(cid, apn, ip_type).await?;
+
+        let mut command = [0; 32];
+        let command = at_commands::builder::CommandBuilder::create_set(&mut command, true)
+            .named("+CGACT")
+            .with_int_parameter(1)
+            .with_int_parameter(cid)
+            .finish()
+            .map_err(|e| Error::BufferTooSmall(Some(e)))?;
+        at::send_at_command(core::str::from_utf8(command)?, &mut buffer).await?;
+
+        let mut command = [0; 32];
+        let command = at_commands::builder::CommandBuilder::create_set(&mut command, true)
+            .named("%XGETPDNID")
+            .with_int_parameter(cid)
+            .finish()
+            .map_err(|e| Error::BufferTooSmall(Some(e)))?;
+        let (pdn_id,) = at_commands::parser::CommandParser::parse(
+            at::send_at_command(core::str::from_utf8(command)?, &mut buffer)
+                .await?
+                .as_bytes(),
+        )
+        .expect_identifier(b"%XGETPDNID: ")
+        .expect_int_parameter()
+        .finish()?;
+        context.pdn_id = pdn_id as u8;
+
+        Ok(context)
+    }
+
+    /// The context identifier (the `<cid>` of the AT commands)
+    pub fn cid(&self) -> u8 {
+        self.cid
+    }
+
+    /// The PDN ID that sockets can be bound to
+    pub fn pdn_id(&self) -> u8 {
+        self.pdn_id
+    }
+
+    /// Deactivate and remove the context. This does the same as dropping the instance, but in an async manner.
+    pub async fn deactivate(self) -> Result<(), Error> {
+        let cid = self.cid;
+        core::mem::forget(self);
+
+        let mut buffer = [0; 32];
+        at::send_at_command(Self::deactivate_command(&mut buffer, cid)?, &mut [0; 16]).await?;
+        at::send_at_command(Self::undefine_command(&mut buffer, cid)?, &mut [0; 16]).await?;
+
+        Ok(())
+    }
+
+    fn deactivate_command(buffer: &mut [u8], cid: u8) -> Result<&str, Error> {
+        let command = at_commands::builder::CommandBuilder::create_set(buffer, true)
+            .named("+CGACT")
+            .with_int_parameter(0)
+            .with_int_parameter(cid)
+            .finish()
+            .map_err(|e| Error::BufferTooSmall(Some(e)))?;
+
+        Ok(core::str::from_utf8(command)?)
+    }
+
+    fn undefine_command(buffer: &mut [u8], cid: u8) -> Result<&str, Error> {
+        let command = at_commands::builder::CommandBuilder::create_set(buffer, true)
+            .named("+CGDCONT")
+            .with_int_parameter(cid)
+            .finish()
+            .map_err(|e| Error::BufferTooSmall(Some(e)))?;
+
+        Ok(core::str::from_utf8(command)?)
+    }
+}
+
+impl Drop for PdnContext {
+    fn drop(&mut self) {
+        #[cfg(feature = "defmt")]
+        defmt::warn!(
+            "Removing the PDN context synchronously. Use async function `deactivate` to avoid blocking."
+        );
+
+        let mut buffer = [0; 32];
+        let result = Self::deactivate_command(&mut buffer, self.cid)
+            .and_then(at::send_at_blocking::<0>)
+            .and_then(|_| Self::undefine_command(&mut buffer, self.cid))
+            .and_then(at::send_at_blocking::<0>);
+
+        if let Err(_e) = result {
+            #[cfg(feature = "defmt")]
+            defmt::error!("Could not remove the PDN context: {}", _e);
+        }
+    }
+}
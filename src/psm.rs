@@ -0,0 +1,121 @@
+//! Implementation of the Power Saving Mode (PSM) configuration (`+CPSMS`)
+
+use crate::{at, error::Error, RegistrationStatus};
+use arrayvec::ArrayString;
+use core::{fmt::Write, time::Duration};
+
+/// The units of the GPRS Timer 3 (the periodic TAU), with their length in seconds.
+/// See 3GPP TS 24.008, table 10.5.163a
+const GPRS_TIMER_3_UNITS: [(u8, u64); 7] = [
+    (0b011, 2),
+    (0b100, 30),
+    (0b101, 60),
+    (0b000, 10 * 60),
+    (0b001, 60 * 60),
+    (0b010, 10 * 60 * 60),
+    (0b110, 320 * 60 * 60),
+];
+
+/// The units of the GPRS Timer 2 (the active time), with their length in seconds.
+/// See 3GPP TS 24.008, table 10.5.163
+const GPRS_TIMER_2_UNITS: [(u8, u64); 3] = [(0b000, 2), (0b001, 60), (0b010, 6 * 60)];
+
+/// The unit bits that mark a timer as deactivated
+const GPRS_TIMER_DEACTIVATED: u8 = 0b111;
+
+/// The PSM timers the network has granted
+///
+/// These often differ from the values that were requested with [request_psm].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PsmTimers {
+    /// How long the modem stays reachable after going idle (T3324).
+    /// `None` when PSM is not granted.
+    pub active_time: Option<Duration>,
+    /// The interval at which the modem has to wake up for a tracking area update (T3412 extended).
+    /// `None` when the timer is deactivated.
+    pub periodic_tau: Option<Duration>,
+}
+
+impl PsmTimers {
+    /// Get the PSM timers that are currently granted by the network.
+    ///
+    /// Returns `None` if the network didn't report them, e.g. because the modem is not registered.
+    pub async fn query() -> Result<Option<Self>, Error> {
+        Ok(RegistrationStatus::query().await?.psm_timers)
+    }
+
+    /// Parse the timers from the binary strings of the `+CEREG` notification
+    pub(crate) fn parse(active_time: &str, periodic_tau: &str) -> Result<Self, Error> {
+        Ok(Self {
+            active_time: decode_timer(active_time, &GPRS_TIMER_2_UNITS)?,
+            periodic_tau: decode_timer(periodic_tau, &GPRS_TIMER_3_UNITS)?,
+        })
+    }
+}
+
+/// Request the network to enable PSM with the given timers.
+///
+/// - `periodic_tau`: The interval at which the modem wakes up to tell the network it's still there.
+///   Can be up to 413 days.
+/// - `active_time`: How long the modem stays reachable after going idle before it goes to sleep.
+///   Can be up to 186 minutes.
+///
+/// The timers can only be encoded with a coarse granularity, so they are rounded up to the next value that can be sent.
+/// E.g. 33 minutes is encoded as 4 units of 10 minutes.
+/// If a duration is too long, [Error::PsmTimerOutOfRange] is returned.
+///
+/// The network decides what is actually used. Use [PsmTimers::query] or the [crate::RegistrationStream] to see the granted values.
+pub async fn request_psm(periodic_tau: Duration, active_time: Duration) -> Result<(), Error> {
+    let mut command = ArrayString::<48>::new();
+    write!(
+        command,
+        "AT+CPSMS=1,,,\"{:08b}\",\"{:08b}\"",
+        encode_timer(periodic_tau, &GPRS_TIMER_3_UNITS)?,
+        encode_timer(active_time, &GPRS_TIMER_2_UNITS)?,
+    )
+    .map_err(|_| Error::BufferTooSmall(None))?;
+
+    at::send_at_command(&command, &mut [0; 16]).await?;
+
+    Ok(())
+}
+
+/// Tell the network that PSM is not wanted anymore
+pub async fn disable_psm() -> Result<(), Error> {
+    at::send_at_command("AT+CPSMS=0", &mut [0; 16]).await?;
+
+    Ok(())
+}
+
+// Find the finest unit in which the duration fits and round up to it
+fn encode_timer(duration: Duration, units: &[(u8, u64)]) -> Result<u8, Error> {
+    // Round up any partial second
+    let seconds = duration.as_secs() + (duration.subsec_nanos() > 0) as u64;
+
+    units
+        .iter()
+        .map(|(unit, unit_seconds)| (unit, (seconds + unit_seconds - 1) / unit_seconds))
+        .find(|(_, value)| *value <= 0b11111)
+        .map(|(unit, value)| unit << 5 | value as u8)
+        .ok_or(Error::PsmTimerOutOfRange)
+}
+
+fn decode_timer(timer: &str, units: &[(u8, u64)]) -> Result<Option<Duration>, Error> {
+    let timer = u8::from_str_radix(timer.trim().trim_matches('"'), 2)
+        .map_err(|_| Error::UnexpectedAtResponse)?;
+
+    let (unit, value) = (timer >> 5, timer & 0b11111);
+    if unit == GPRS_TIMER_DEACTIVATED {
+        return Ok(None);
+    }
+
+    // Only the GPRS timer 2 has undefined units, which must be interpreted as minutes
+    let unit_seconds = units
+        .iter()
+        .find(|(u, _)| *u == unit)
+        .map(|(_, seconds)| *seconds)
+        .unwrap_or(60);
+
+    Ok(Some(Duration::from_secs(unit_seconds * value as u64)))
+}
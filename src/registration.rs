@@ -0,0 +1,207 @@
+//! Implementation of the network registration status (`+CEREG`)
+
+use crate::{at, at_notifications::AtNotificationStream, error::Error, PsmTimers};
+use core::task::Poll;
+
+/// The registration state of the modem in the LTE network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RegistrationState {
+    /// Not registered and not searching for an operator to register to
+    NotRegistered,
+    /// Registered to the home network
+    RegisteredHome,
+    /// Not registered, but searching for an operator to register to
+    Searching,
+    /// The registration was denied by the network
+    Denied,
+    /// Not known, e.g. because the modem is out of coverage
+    Unknown,
+    /// Registered to a network other than the home network
+    RegisteredRoaming,
+    /// Not registered because of a failure of the UICC (the sim)
+    SimFailure,
+}
+
+impl RegistrationState {
+    /// Returns true when the modem is registered to either the home or a roaming network
+    pub fn is_registered(&self) -> bool {
+        matches!(
+            self,
+            RegistrationState::RegisteredHome | RegistrationState::RegisteredRoaming
+        )
+    }
+
+    pub(crate) fn from_stat(stat: i32) -> Result<Self, Error> {
+        match stat {
+            0 => Ok(RegistrationState::NotRegistered),
+            1 => Ok(RegistrationState::RegisteredHome),
+            2 => Ok(RegistrationState::Searching),
+            3 => Ok(RegistrationState::Denied),
+            4 => Ok(RegistrationState::Unknown),
+            5 => Ok(RegistrationState::RegisteredRoaming),
+            90 => Ok(RegistrationState::SimFailure),
+            _ => Err(Error::UnexpectedAtResponse),
+        }
+    }
+}
+
+/// The radio access technology of the serving cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AccessTechnology {
+    /// E-UTRAN (WB-S1 mode), better known as LTE-M
+    LteM,
+    /// E-UTRAN (NB-S1 mode), better known as NB-IoT
+    NbIot,
+}
+
+/// The network registration status as reported by `+CEREG`
+///
+/// The location fields are only present when the modem knows about a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegistrationStatus {
+    pub state: RegistrationState,
+    /// The tracking area code of the cell
+    pub tracking_area_code: Option<u16>,
+    /// The E-UTRAN cell ID of the serving cell
+    pub cell_id: Option<u32>,
+    pub access_technology: Option<AccessTechnology>,
+    /// The PSM timers granted by the network, if it reported them
+    pub psm_timers: Option<PsmTimers>,
+}
+
+impl RegistrationStatus {
+    /// Get the current registration status by querying `AT+CEREG?`
+    pub async fn query() -> Result<Self, Error> {
+        // We don't know the current notification mode, so set it to the one with the most info
+        at::send_at::<0>("AT+CEREG=5").await?;
+
+        let mut buffer = [0; 128];
+        let response = at::send_at_command("AT+CEREG?", &mut buffer).await?;
+
+        // The response starts with the notification mode
+        let mut fields = response
+            .trim()
+            .strip_prefix("+CEREG:")
+            .ok_or(Error::UnexpectedAtResponse)?
+            .split(',');
+        fields.next();
+
+        let status = Self::parse_fields(fields)?;
+        // There's no notification for the state the modem is already in
+        crate::socket::update_network_registration(status.state.is_registered());
+
+        Ok(status)
+    }
+
+    /// Parse a `+CEREG` notification line
+    pub(crate) fn parse_notification(notification: &str) -> Result<Self, Error> {
+        let fields = notification
+            .trim()
+            .strip_prefix("+CEREG:")
+            .ok_or(Error::UnexpectedAtResponse)?
+            .split(',');
+
+        Self::parse_fields(fields)
+    }
+
+    // Parse `<stat>[,[<tac>],[<ci>],[<AcT>][,<cause_type>,<reject_cause>[,[<Active-Time>],[<Periodic-TAU>]]]]`
+    fn parse_fields<'a>(mut fields: impl Iterator<Item = &'a str>) -> Result<Self, Error> {
+        let stat = fields
+            .next()
+            .and_then(|stat| stat.trim().parse().ok())
+            .ok_or(Error::UnexpectedAtResponse)?;
+
+        let mut hex_field = |radix_len: usize| {
+            fields
+                .next()
+                .map(|field| field.trim().trim_matches('"'))
+                .filter(|field| !field.is_empty() && field.len() <= radix_len)
+                .map(|field| {
+                    u32::from_str_radix(field, 16).map_err(|_| Error::UnexpectedAtResponse)
+                })
+                .transpose()
+        };
+
+        let tracking_area_code = hex_field(4)?.map(|tac| tac as u16);
+        // The modem reports 0xFFFFFFFF when the cell ID is not valid
+        let cell_id = hex_field(8)?.filter(|ci| *ci != 0xFFFF_FFFF);
+        let access_technology = match fields.next().map(|act| act.trim()) {
+            Some("7") => Some(AccessTechnology::LteM),
+            Some("9") => Some(AccessTechnology::NbIot),
+            _ => None,
+        };
+
+        // Skip the cause type and reject cause
+        let mut fields = fields.skip(2);
+        let psm_timers = match (fields.next(), fields.next()) {
+            (Some(active_time), Some(periodic_tau))
+                if !active_time.trim().is_empty() && !periodic_tau.trim().is_empty() =>
+            {
+                Some(PsmTimers::parse(active_time, periodic_tau)?)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            state: RegistrationState::from_stat(stat)?,
+            tracking_area_code,
+            cell_id,
+            access_technology,
+            psm_timers,
+        })
+    }
+}
+
+/// An async stream of the changes of the network registration status.
+///
+/// Implements the [futures::Stream] trait for polling.
+/// `COUNT` is the amount of notifications that can be buffered while the stream is not polled.
+pub struct RegistrationStream<const COUNT: usize> {
+    notifications: AtNotificationStream<128, COUNT>,
+}
+
+impl<const COUNT: usize> RegistrationStream<COUNT> {
+    /// Creates a new stream and enables the unsolicited `+CEREG` notifications.
+    ///
+    /// To access all the nice iterator functions, use [futures::StreamExt].
+    pub async fn new() -> Result<Self, Error> {
+        let notifications = AtNotificationStream::with_prefix("+CEREG:").await;
+
+        at::send_at::<0>("AT+CEREG=5").await?;
+
+        Ok(Self { notifications })
+    }
+
+    /// Futures are lazy and can only register themselves once polled.
+    /// Call this function if you want to register this stream early so that it can already receive notifications.
+    pub async fn register(self: core::pin::Pin<&mut Self>) {
+        let notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+        notifications.register().await;
+    }
+}
+
+impl<const COUNT: usize> futures::Stream for RegistrationStream<COUNT> {
+    type Item = RegistrationStatus;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+
+        loop {
+            match notifications.as_mut().poll_next(cx) {
+                Poll::Ready(Some(notification)) => {
+                    if let Ok(status) = RegistrationStatus::parse_notification(&notification) {
+                        return Poll::Ready(Some(status));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Implementation of the serving cell information (`%XMONITOR`)
+
+use crate::{
+    at,
+    error::Error,
+    signal_quality::{convert_rsrp, convert_snr},
+    AccessTechnology, RegistrationState,
+};
+
+/// Information about the cell the modem is using, as reported by `%XMONITOR`
+///
+/// This is what a cell-based location service needs when GNSS is not available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ServingCell {
+    pub registration_state: RegistrationState,
+    /// Mobile country code
+    pub mcc: u16,
+    /// Mobile network code
+    pub mnc: u16,
+    /// The tracking area code of the cell
+    pub tracking_area_code: u16,
+    pub access_technology: Option<AccessTechnology>,
+    /// The LTE band the cell is on
+    pub band: u8,
+    /// The E-UTRAN cell ID
+    pub cell_id: u32,
+    /// The physical cell ID
+    pub physical_cell_id: u16,
+    /// The E-UTRA absolute radio frequency channel number
+    pub earfcn: u32,
+    /// Reference signal received power in dBm. See [crate::SignalQuality] for the conversion.
+    pub rsrp: Option<i16>,
+    /// Signal to noise ratio in dB. See [crate::SignalQuality] for the conversion.
+    pub snr: Option<i16>,
+}
+
+impl ServingCell {
+    /// Get the information about the serving cell by querying `AT%XMONITOR`.
+    ///
+    /// Returns `None` if there is no serving cell, e.g. because the modem is not registered.
+    pub async fn query() -> Result<Option<Self>, Error> {
+        let mut buffer = [0; 256];
+        let response = at::send_at_command("AT%XMONITOR", &mut buffer).await?;
+
+        Self::parse(response)
+    }
+
+    // Parse `%XMONITOR: <reg_status>[,<full_name>,<short_name>,<plmn>,<tac>,<AcT>,<band>,<cell_id>,<phys_cell_id>,<EARFCN>,<rsrp>,<snr>,...]`
+    fn parse(response: &str) -> Result<Option<Self>, Error> {
+        let mut fields = at::split_parameters(
+            response
+                .trim()
+                .strip_prefix("%XMONITOR:")
+                .ok_or(Error::UnexpectedAtResponse)?,
+        );
+
+        let registration_state = RegistrationState::from_stat(
+            fields
+                .next()
+                .and_then(|stat| stat.parse().ok())
+                .ok_or(Error::UnexpectedAtResponse)?,
+        )?;
+
+        // Skip the operator names
+        let mut fields = fields.skip(2);
+
+        let plmn = match fields.next() {
+            Some(plmn) if plmn.len() >= 5 && plmn.is_ascii() => plmn,
+            // Only the registration state is reported when there is no serving cell
+            _ => return Ok(None),
+        };
+
+        let int = |field: Option<&str>, radix: u32| {
+            field
+                .and_then(|field| u32::from_str_radix(field, radix).ok())
+                .ok_or(Error::UnexpectedAtResponse)
+        };
+
+        let mcc = int(Some(&plmn[..3]), 10)? as u16;
+        let mnc = int(Some(&plmn[3..]), 10)? as u16;
+        let tracking_area_code = int(fields.next(), 16)? as u16;
+        let access_technology = match fields.next() {
+            Some("7") => Some(AccessTechnology::LteM),
+            Some("9") => Some(AccessTechnology::NbIot),
+            _ => None,
+        };
+        let band = int(fields.next(), 10)? as u8;
+        let cell_id = int(fields.next(), 16)?;
+        let physical_cell_id = int(fields.next(), 10)? as u16;
+        let earfcn = int(fields.next(), 10)?;
+        let rsrp = fields
+            .next()
+            .and_then(|rsrp| rsrp.parse().ok())
+            .and_then(convert_rsrp);
+        let snr = fields
+            .next()
+            .and_then(|snr| snr.parse().ok())
+            .and_then(convert_snr);
+
+        Ok(Some(Self {
+            registration_state,
+            mcc,
+            mnc,
+            tracking_area_code,
+            access_technology,
+            band,
+            cell_id,
+            physical_cell_id,
+            earfcn,
+            rsrp,
+            snr,
+        }))
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    error::Error,
+    socket::{Socket, SplitSocketHandle},
+    CancellationToken,
+};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+
+struct SharedSocketLocks {
+    receive: Mutex<CriticalSectionRawMutex, ()>,
+    write: Mutex<CriticalSectionRawMutex, ()>,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const LOCKS_INIT: SharedSocketLocks = SharedSocketLocks {
+    receive: Mutex::new(()),
+    write: Mutex::new(()),
+};
+/// The locks of the shared sockets, by the spot of their [SplitSocketHandle]
+static SHARED_SOCKET_LOCKS: [SharedSocketLocks; nrfxlib_sys::NRF_MODEM_MAX_SOCKET_COUNT as usize] =
+    [LOCKS_INIT; nrfxlib_sys::NRF_MODEM_MAX_SOCKET_COUNT as usize];
+
+/// A handle to a socket that can be duplicated and used from multiple tasks at the same time.
+///
+/// It's made with e.g. [crate::TcpStream::into_shared]. Every handle can receive and write,
+/// and the socket is closed when the last handle is gone.
+///
+/// The receives of all handles are done one after the other, and so are the writes:
+/// - A chunk of received data goes to exactly one receive call, so concurrent readers each get distinct chunks.
+///   Which of the waiting readers gets the next chunk is not defined.
+/// - A write sends its whole buffer before another write can start, so the data of two writes is never mixed.
+///   The writes are done in the order they get the lock, which is not necessarily the order they were called in.
+///
+/// A receive and a write can run at the same time.
+pub struct SharedSocket {
+    handle: SplitSocketHandle,
+    /// Stream data can be written in multiple calls, datagrams must be written in one
+    stream: bool,
+}
+
+impl SharedSocket {
+    pub(crate) fn new(socket: Socket, stream: bool) -> Self {
+        Self {
+            handle: socket.share(),
+            stream,
+        }
+    }
+
+    /// Create another handle to the socket, e.g. to give to another task
+    pub async fn duplicate(&self) -> Result<SharedSocket, Error> {
+        Ok(Self {
+            handle: self.handle.duplicate().await?,
+            stream: self.stream,
+        })
+    }
+
+    /// Get the raw underlying file descriptor
+    pub fn as_raw_fd(&self) -> i32 {
+        self.handle.as_raw_fd()
+    }
+
+    fn locks(&self) -> &'static SharedSocketLocks {
+        &SHARED_SOCKET_LOCKS[self.handle.index()]
+    }
+
+    /// Receive data into the buffer and return the length that was received.
+    ///
+    /// For a datagram socket this is one whole datagram, which is truncated if it doesn't fit in the buffer.
+    pub async fn receive(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.receive_with_cancellation(buf, &Default::default())
+            .await
+    }
+
+    /// Receive data into the buffer and return the length that was received.
+    ///
+    /// For a datagram socket this is one whole datagram, which is truncated if it doesn't fit in the buffer.
+    pub async fn receive_with_cancellation(
+        &self,
+        buf: &mut [u8],
+        token: &CancellationToken,
+    ) -> Result<usize, Error> {
+        // The socket has one waker per direction, so only one receive can wait at a time
+        let _lock = self.locks().receive.lock().await;
+
+        let max_receive_len = match self.stream {
+            true => self.handle.max_segment_size().min(buf.len()),
+            false => buf.len(),
+        };
+        self.handle
+            .receive(&mut buf[..max_receive_len], token)
+            .await
+    }
+
+    /// Write the entire buffer to the socket. For a datagram socket, the buffer is sent as one datagram.
+    pub async fn write(&self, buf: &[u8]) -> Result<(), Error> {
+        self.write_with_cancellation(buf, &Default::default()).await
+    }
+
+    /// Write the entire buffer to the socket. For a datagram socket, the buffer is sent as one datagram.
+    pub async fn write_with_cancellation(
+        &self,
+        buf: &[u8],
+        token: &CancellationToken,
+    ) -> Result<(), Error> {
+        let _lock = self.locks().write.lock().await;
+
+        if !self.stream {
+            self.handle.write(buf, token).await?;
+            return Ok(());
+        }
+
+        let mut written_bytes = 0;
+
+        while written_bytes < buf.len() {
+            let max_write_len = self
+                .handle
+                .max_segment_size()
+                .min(buf.len() - written_bytes);
+            written_bytes += self
+                .handle
+                .write(&buf[written_bytes..][..max_write_len], token)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Deactivates this handle and its LTE link. The socket is closed if this was the last handle.
+    /// A normal drop will do the same thing, but blocking.
+    pub async fn deactivate(self) -> Result<(), Error> {
+        self.handle.deactivate().await
+    }
+}
@@ -0,0 +1,136 @@
+//! Implementation of the signal quality reporting (`+CESQ`, `%CESQ` and `%XSNRSQ`)
+
+use crate::{at, at_notifications::AtNotificationStream, error::Error};
+use core::task::Poll;
+
+/// The quality of the signal of the serving cell
+///
+/// The modem reports indices that are converted the same way as in the nRF Connect SDK `modem_info` library:
+///
+/// | Value | Index range | Conversion            | Physical range      |
+/// |-------|-------------|-----------------------|---------------------|
+/// | RSRP  | 0..=97      | `index - 140` dBm     | -140..=-43 dBm      |
+/// | RSRQ  | 0..=34      | `index / 2 - 19.5` dB | -19.5..=-2.5 dB     |
+/// | SNR   | 0..=49      | `index - 24` dB       | -24..=25 dB         |
+///
+/// Every value is the lower bound of a 1 dB (0.5 dB for RSRQ) wide range.
+/// A value is `None` when the modem reports it as not known, e.g. when there is no serving cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SignalQuality {
+    /// Reference signal received power in dBm
+    pub rsrp: Option<i16>,
+    /// Reference signal received quality in dB
+    pub rsrq: Option<f32>,
+    /// Signal to noise ratio in dB
+    pub snr: Option<i16>,
+}
+
+impl SignalQuality {
+    /// Get the current signal quality by querying `AT+CESQ` and `AT%XSNRSQ?`
+    pub async fn query() -> Result<Self, Error> {
+        let mut buffer = [0; 64];
+
+        // +CESQ: <rxlev>,<ber>,<rscp>,<ecno>,<rsrq>,<rsrp>
+        let response = at::send_at_command("AT+CESQ", &mut buffer).await?;
+        let mut fields = parse_fields::<6>(response, "+CESQ:")?.into_iter().skip(4);
+        let rsrq = fields.next().flatten().and_then(convert_rsrq);
+        let rsrp = fields.next().flatten().and_then(convert_rsrp);
+
+        // %XSNRSQ: <snr>,<srxlev>,<ce_level>
+        let response = at::send_at_command("AT%XSNRSQ?", &mut buffer).await?;
+        let snr = parse_fields::<1>(response, "%XSNRSQ:")?[0].and_then(convert_snr);
+
+        Ok(Self { rsrp, rsrq, snr })
+    }
+
+    // Parse `%CESQ: <rsrp>,<rsrp_threshold_index>,<rsrq>,<rsrq_threshold_index>`
+    fn parse_notification(notification: &str) -> Result<Self, Error> {
+        let fields = parse_fields::<4>(notification, "%CESQ:")?;
+
+        Ok(Self {
+            rsrp: fields[0].and_then(convert_rsrp),
+            rsrq: fields[2].and_then(convert_rsrq),
+            snr: None,
+        })
+    }
+}
+
+pub(crate) fn convert_rsrp(index: u8) -> Option<i16> {
+    (index <= 97).then_some(index as i16 - 140)
+}
+
+pub(crate) fn convert_rsrq(index: u8) -> Option<f32> {
+    (index <= 34).then_some(index as f32 * 0.5 - 19.5)
+}
+
+pub(crate) fn convert_snr(index: u8) -> Option<i16> {
+    (index <= 49).then_some(index as i16 - 24)
+}
+
+// Parse the first `N` integer fields after the prefix. Fields that are missing or out of range are `None`.
+fn parse_fields<const N: usize>(response: &str, prefix: &str) -> Result<[Option<u8>; N], Error> {
+    let mut fields = response
+        .trim()
+        .strip_prefix(prefix)
+        .ok_or(Error::UnexpectedAtResponse)?
+        .split(',')
+        .map(|field| field.trim().parse().ok());
+
+    Ok([(); N].map(|_| fields.next().flatten()))
+}
+
+/// An async stream of the signal quality, using the unsolicited `%CESQ` notifications.
+///
+/// The modem only sends a notification when the RSRP or RSRQ crosses one of its thresholds,
+/// so this is a way to log changes in coverage without polling.
+/// The notifications don't contain the SNR, so that is always `None`.
+///
+/// Implements the [futures::Stream] trait for polling.
+/// `COUNT` is the amount of notifications that can be buffered while the stream is not polled.
+pub struct SignalQualityStream<const COUNT: usize> {
+    notifications: AtNotificationStream<64, COUNT>,
+}
+
+impl<const COUNT: usize> SignalQualityStream<COUNT> {
+    /// Creates a new stream and enables the unsolicited `%CESQ` notifications.
+    ///
+    /// To access all the nice iterator functions, use [futures::StreamExt].
+    pub async fn new() -> Result<Self, Error> {
+        let notifications = AtNotificationStream::with_prefix("%CESQ:").await;
+
+        at::send_at::<0>("AT%CESQ=1").await?;
+
+        Ok(Self { notifications })
+    }
+
+    /// Futures are lazy and can only register themselves once polled.
+    /// Call this function if you want to register this stream early so that it can already receive notifications.
+    pub async fn register(self: core::pin::Pin<&mut Self>) {
+        let notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+        notifications.register().await;
+    }
+}
+
+impl<const COUNT: usize> futures::Stream for SignalQualityStream<COUNT> {
+    type Item = SignalQuality;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut notifications = unsafe { self.map_unchecked_mut(|s| &mut s.notifications) };
+
+        loop {
+            match notifications.as_mut().poll_next(cx) {
+                Poll::Ready(Some(notification)) => {
+                    if let Ok(quality) = SignalQuality::parse_notification(&notification) {
+                        return Poll::Ready(Some(quality));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -1,6 +1,11 @@
-use crate::{error::Error, send_at, LteLink};
+use crate::{at::parse_final_result, error::Error, send_at, LteLink};
 use arrayvec::{ArrayString, ArrayVec};
-use core::{fmt::Write, write};
+use core::{
+    fmt::Write,
+    str::Chars,
+    sync::atomic::{AtomicU8, Ordering},
+    write,
+};
 
 // ASCII table for coverting ASCII to GSM 7 bit
 // Copied from https://github.com/nrfconnect/sdk-nrf/blob/main/lib/sms/string_conversion.c#L36
@@ -48,6 +53,130 @@ const STR_7BIT_ESCAPE_IND: u8 = 0x80;
 const STR_7BIT_CODE_MASK: u8 = 0x7F;
 const STR_7BIT_ESCAPE_CODE: u8 = 0x1B;
 
+/// The maximum amount of user data bytes in a single SMS
+const MAX_USER_DATA_LEN: usize = 140;
+/// The length of the concatenated SMS user data header, including its own length byte
+const CONCAT_UDH_LEN: usize = 6;
+
+// The user data header of a segment of a concatenated SMS.
+// IEI 0x00: concatenated short message with an 8 bit reference number.
+fn concat_header(reference: u8, segment_count: u8, sequence: u8) -> [u8; CONCAT_UDH_LEN] {
+    [0x05, 0x00, 0x03, reference, segment_count, sequence]
+}
+
+/// The reference number of the next concatenated SMS.
+/// All segments of one message share the same reference so the receiver can put them back together.
+static CONCAT_REFERENCE: AtomicU8 = AtomicU8::new(0);
+
+/// The alphabet an SMS is encoded with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SmsEncoding {
+    /// The GSM 7 bit default alphabet. Used when the message only contains ASCII characters.
+    ///
+    /// A single SMS holds 160 characters, a segment of a concatenated SMS holds 153.
+    /// Some characters like `[` or `{` take up two characters.
+    Gsm7Bit,
+    /// UCS-2 (UTF-16). Used when the message contains any non-ASCII character.
+    ///
+    /// A single SMS holds 70 characters, a segment of a concatenated SMS holds 67.
+    /// Characters outside of the basic multilingual plane take up two characters.
+    Ucs2,
+}
+
+impl SmsEncoding {
+    fn for_message(message: &str) -> Self {
+        if message.is_ascii() {
+            SmsEncoding::Gsm7Bit
+        } else {
+            SmsEncoding::Ucs2
+        }
+    }
+
+    /// The TP-DCS value
+    fn data_coding_scheme(self) -> u8 {
+        match self {
+            SmsEncoding::Gsm7Bit => 0x00,
+            SmsEncoding::Ucs2 => 0x08,
+        }
+    }
+
+    /// The amount of septets (GSM 7 bit) or bytes (UCS-2) that fit in one segment
+    fn segment_capacity(self, concatenated: bool) -> usize {
+        match (self, concatenated) {
+            (SmsEncoding::Gsm7Bit, false) => 160,
+            (SmsEncoding::Gsm7Bit, true) => 153,
+            (SmsEncoding::Ucs2, false) => MAX_USER_DATA_LEN,
+            (SmsEncoding::Ucs2, true) => MAX_USER_DATA_LEN - CONCAT_UDH_LEN,
+        }
+    }
+
+    // Encode a single character into septets (GSM 7 bit) or big endian bytes (UCS-2)
+    // The GSM 7 bit conversion is reimplemented from https://github.com/nrfconnect/sdk-nrf/blob/main/lib/sms/string_conversion.c#L162
+    fn encode_char(self, c: char) -> ArrayVec<u8, 4> {
+        let mut encoded = ArrayVec::new();
+
+        match self {
+            SmsEncoding::Gsm7Bit => {
+                let char_7bit = ASCII_TO_7BIT_TABLE[c as usize & 0xFF];
+                if char_7bit & STR_7BIT_ESCAPE_IND == 0 {
+                    encoded.push(char_7bit);
+                } else {
+                    encoded.push(STR_7BIT_ESCAPE_CODE);
+                    encoded.push(char_7bit & STR_7BIT_CODE_MASK);
+                }
+            }
+            SmsEncoding::Ucs2 => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    encoded.extend(unit.to_be_bytes());
+                }
+            }
+        }
+
+        encoded
+    }
+}
+
+/// Iterator that splits a message up into the encoded segments of an SMS
+struct Segments<'a> {
+    chars: Chars<'a>,
+    encoding: SmsEncoding,
+    capacity: usize,
+    first: bool,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = ArrayVec<u8, 160>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut segment = ArrayVec::new();
+
+        // Characters are never split over two segments
+        loop {
+            let mut rest = self.chars.clone();
+            let encoded = match rest.next() {
+                Some(c) => self.encoding.encode_char(c),
+                None => break,
+            };
+
+            if segment.len() + encoded.len() > self.capacity {
+                break;
+            }
+
+            segment.try_extend_from_slice(&encoded).unwrap();
+            self.chars = rest;
+        }
+
+        // An empty message is still sent as one empty segment
+        let first = core::mem::replace(&mut self.first, false);
+        if segment.is_empty() && !first {
+            None
+        } else {
+            Some(segment)
+        }
+    }
+}
+
 /// A struct holding both number and message with can be send as an SMS
 pub struct Sms<'a> {
     number: &'a str,
@@ -57,10 +186,39 @@ pub struct Sms<'a> {
 impl<'a> Sms<'a> {
     /// Creates a new Sms message
     /// `number` should be in national format, including the country code at start. The + character is not need at start and will be ignored.
-    /// Max `message` lenght 160 chars
+    ///
+    /// The `message` can be of any length. If it doesn't fit in a single SMS, it is sent as a concatenated SMS
+    /// of at most 255 segments. See [SmsEncoding] for how much text fits in a segment.
     pub fn new(number: &'a str, message: &'a str) -> Self {
         Self { number, message }
     }
+
+    /// The encoding the message will be sent with
+    pub fn encoding(&self) -> SmsEncoding {
+        SmsEncoding::for_message(self.message)
+    }
+
+    /// The amount of SMS segments the message will be sent as
+    pub fn segment_count(&self) -> usize {
+        let single = self.segments(false).count();
+        if single <= 1 {
+            single
+        } else {
+            self.segments(true).count()
+        }
+    }
+
+    fn segments(&self, concatenated: bool) -> Segments<'a> {
+        let encoding = self.encoding();
+
+        Segments {
+            chars: self.message.chars(),
+            encoding,
+            capacity: encoding.segment_capacity(concatenated),
+            first: true,
+        }
+    }
+
     // Encode number in the way modem expect it
     // Reimplement from https://github.com/nrfconnect/sdk-nrf/blob/main/lib/sms/sms_submit.c#L46
     fn encode_number(number: &str) -> Result<ArrayString<15>, Error> {
@@ -92,100 +250,114 @@ impl<'a> Sms<'a> {
             Err(Error::SmsNumberNotAscii)
         }
     }
-    // Convert a ASCII string to GSM 7bit
-    // Reimplement from https://github.com/nrfconnect/sdk-nrf/blob/main/lib/sms/string_conversion.c#L162
-    fn ascii_to_gsm7bit<const N: usize>(text: &str) -> Result<ArrayString<N>, Error> {
-        let mut encoded_message = ArrayString::new();
+    // Pack GSM 7 bit septets into octets, starting after `fill_bits` zero bits.
+    // The fill bits are needed to align the septets after a user data header.
+    // Reimplement from https://github.com/nrfconnect/sdk-nrf/blob/main/lib/sms/string_conversion.c#L294
+    fn pack_gsm7bit(septets: &[u8], fill_bits: usize) -> ArrayVec<u8, MAX_USER_DATA_LEN> {
+        let mut bytes = ArrayVec::new();
+        let len = (fill_bits + septets.len() * 7 + 7) / 8;
+        bytes.extend((0..len).map(|_| 0));
 
-        for c in text.chars() {
-            if c.is_ascii() {
-                let char_7bit = ASCII_TO_7BIT_TABLE[c as usize];
-                if char_7bit & STR_7BIT_ESCAPE_IND == 0 {
-                    encoded_message
-                        .try_push(char_7bit as char)
-                        .map_err(|_| Error::BufferTooSmall(None))?;
-                } else {
-                    encoded_message
-                        .try_push(STR_7BIT_ESCAPE_CODE as char)
-                        .map_err(|_| Error::BufferTooSmall(None))?;
-                    encoded_message
-                        .try_push((char_7bit & STR_7BIT_CODE_MASK) as char)
-                        .map_err(|_| Error::BufferTooSmall(None))?;
-                }
-            }
-        }
+        for (index, septet) in septets.iter().enumerate() {
+            let bit = fill_bits + index * 7;
+            let (byte, shift) = (bit / 8, bit % 8);
 
-        Ok(encoded_message)
-    }
-    // Pack a GSM 7 bit strings into 7 bites without 1 bit padding
-    // Reimplement from https://github.com/nrfconnect/sdk-nrf/blob/main/lib/sms/string_conversion.c#L294
-    fn pack_gsm7bit<const N: usize>(text: ArrayString<N>) -> ArrayVec<u8, N> {
-        let mut src: usize = 0;
-        let mut dst: usize = 0;
-        let mut shift: usize = 0;
-        let len = text.len();
-        let mut bytes: ArrayVec<u8, N> = ArrayVec::new();
-        bytes.try_extend_from_slice(text.as_bytes()).unwrap();
-
-        while src < len {
-            bytes[dst] = bytes[src] >> shift;
-            src += 1;
-            if src < len {
-                bytes[dst] |= bytes[src] << (7 - shift);
-                shift += 1;
-                if shift == 7 {
-                    shift = 0;
-                    src += 1;
-                }
+            bytes[byte] |= septet << shift;
+            if shift > 1 {
+                bytes[byte + 1] |= septet >> (8 - shift);
             }
-            dst += 1;
         }
-        bytes.truncate(dst);
+
         bytes
     }
-    /// Sends the craftes message
-    /// `N` is need to provide internal buffer size for message and number encoding. Needs to be at least 2 * message.len() + 34
-    /// Max ever need value for the buffer should be not more then 354 bytes
-    pub async fn send<const N: usize>(self) -> Result<(), Error> {
-        let encoded_number = Self::encode_number(self.number).unwrap();
 
-        #[cfg(feature = "defmt")]
-        defmt::trace!("encoded_number: {}", encoded_number.as_str());
+    // Create the AT+CMGS command that sends one segment as an SMS-SUBMIT PDU
+    fn encode_pdu<const N: usize>(
+        &self,
+        encoded_number: &str,
+        header: Option<[u8; CONCAT_UDH_LEN]>,
+        segment: &[u8],
+    ) -> Result<ArrayString<N>, Error> {
+        let encoding = self.encoding();
+        let header_len = header.map(|h| h.len()).unwrap_or(0);
 
-        let encoded_message = Self::pack_gsm7bit(Self::ascii_to_gsm7bit::<N>(self.message)?);
+        let (user_data, user_data_len) = match encoding {
+            SmsEncoding::Gsm7Bit => {
+                // The header is padded up to the next septet boundary
+                let fill_bits = (7 - (header_len * 8) % 7) % 7;
+                let header_septets = (header_len * 8 + fill_bits) / 7;
+                (
+                    Self::pack_gsm7bit(segment, fill_bits),
+                    header_septets + segment.len(),
+                )
+            }
+            SmsEncoding::Ucs2 => (
+                segment
+                    .iter()
+                    .copied()
+                    .collect::<ArrayVec<u8, MAX_USER_DATA_LEN>>(),
+                header_len + segment.len(),
+            ),
+        };
 
         let size = 2 + /* First header byte and TP-MR fields */
-		1 + /* Length of phone number */
-		1 + /* Phone number Type-of-Address byte */
-		encoded_number.len()/2 +
-		2 + /* TP-PID and TP-DCS fields */
-		1 + /* TP-UDL field */
-		encoded_message.len();
+            1 + /* Length of phone number */
+            1 + /* Phone number Type-of-Address byte */
+            encoded_number.len() / 2 +
+            2 + /* TP-PID and TP-DCS fields */
+            1 + /* TP-UDL field */
+            header_len +
+            user_data.len();
+
+        // SMS-SUBMIT, with the TP-UDHI bit set when there's a user data header
+        let first_octet = if header.is_some() { 0x41 } else { 0x01 };
 
         let mut at_cmgs: ArrayString<N> = ArrayString::new();
-        let mut encoded_number_len = encoded_number.len();
-        if self.number.trim_start_matches('+').len() % 2 != 0 {
-            encoded_number_len -= 1;
-        }
-        // Write the at command and begin with encoded number and it's lenght
+        // Write the at command, the header, the TP-MR and the encoded number with its length
         write!(
             &mut at_cmgs,
-            "AT+CMGS={}\r{:04X}{:04X}91{}",
-            size, 0x01, encoded_number_len, encoded_number
+            "AT+CMGS={}\r00{:02X}00{:02X}91{}",
+            size,
+            first_octet,
+            self.number.trim_start_matches('+').len(),
+            encoded_number
         )
         .map_err(|_| Error::BufferTooSmall(None))?;
-        // Write the message lenght
-        write!(&mut at_cmgs, "00{:04X}", self.message.len())
-            .map_err(|_| Error::BufferTooSmall(None))?;
-        // Write the GSM 7 bit packaged message as hex string
-        for c in &encoded_message {
+        // Write the TP-PID, TP-DCS and the message length
+        write!(
+            &mut at_cmgs,
+            "00{:02X}{:02X}",
+            encoding.data_coding_scheme(),
+            user_data_len
+        )
+        .map_err(|_| Error::BufferTooSmall(None))?;
+        // Write the user data header and the message as hex string
+        for c in header.iter().flatten().chain(&user_data) {
             write!(&mut at_cmgs, "{:02X}", c).map_err(|_| Error::BufferTooSmall(None))?;
         }
         // End character
         write!(&mut at_cmgs, "\x1A").map_err(|_| Error::BufferTooSmall(None))?;
 
+        Ok(at_cmgs)
+    }
+
+    /// Sends the craftes message
+    ///
+    /// `N` is need to provide internal buffer size for the encoding of a single segment.
+    /// Max ever need value for the buffer should be not more then 354 bytes
+    ///
+    /// When the network rejects a segment, [Error::CmsError] is returned with the error code the modem reported.
+    /// Segments of a concatenated SMS that were sent before the rejection can't be taken back.
+    pub async fn send<const N: usize>(self) -> Result<(), Error> {
+        let encoded_number = Self::encode_number(self.number)?;
+
         #[cfg(feature = "defmt")]
-        defmt::trace!("at_cmgs: {:?}", at_cmgs.as_str());
+        defmt::trace!("encoded_number: {}", encoded_number.as_str());
+
+        let segment_count = self.segment_count();
+        if segment_count > u8::MAX as usize {
+            return Err(Error::SmsTooLong);
+        }
 
         // Wait for LteLink to send the message
         let lte_link = LteLink::new().await?;
@@ -194,23 +366,663 @@ impl<'a> Sms<'a> {
         #[cfg(feature = "defmt")]
         defmt::trace!("link found");
 
+        let result = self
+            .send_segments::<N>(&encoded_number, segment_count as u8)
+            .await;
+
+        lte_link.deactivate().await?;
+        result
+    }
+
+    async fn send_segments<const N: usize>(
+        &self,
+        encoded_number: &str,
+        segment_count: u8,
+    ) -> Result<(), Error> {
         // Configure the SMS parameters in modem
         // This might need some rework when reciving SMS is add and reporting
         if send_at::<6>("AT+CNMI=3,2,0,1").await?.as_str() != "OK\r\n" {
             return Err(Error::UnexpectedAtResponse);
         }
 
-        // Send the SMS
-        let result = send_at::<18>(&at_cmgs).await?;
+        let concatenated = segment_count > 1;
+        let reference = CONCAT_REFERENCE.fetch_add(1, Ordering::Relaxed);
 
-        #[cfg(feature = "defmt")]
-        defmt::trace!("result: {}", result.as_str());
+        for (index, segment) in self.segments(concatenated).enumerate() {
+            let header =
+                concatenated.then(|| concat_header(reference, segment_count, index as u8 + 1));
 
-        lte_link.deactivate().await?;
-        if result.ends_with("OK\r\n") {
-            Ok(())
+            let at_cmgs = self.encode_pdu::<N>(encoded_number, header, &segment)?;
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!("at_cmgs: {:?}", at_cmgs.as_str());
+
+            // Send the SMS
+            let result = send_at::<32>(&at_cmgs).await?;
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!("result: {}", result.as_str());
+
+            parse_final_result(&result)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Send a text message to the given number.
+///
+/// This is a shorthand for [Sms::new] and [Sms::send] with a buffer that is big enough for any segment.
+pub async fn send_text(number: &str, message: &str) -> Result<(), Error> {
+    Sms::new(number, message).send::<354>().await
+}
+
+// GSM 7 bit default alphabet to unicode
+// Taken from 3GPP TS 23.038, section 6.2.1
+const GSM7BIT_TO_CHAR_TABLE: [char; 128] = [
+    '@', '£', '$', '¥', 'è', 'é', 'ù', 'ì', 'ò', 'Ç', '\n', 'Ø', 'ø', '\r', 'Å',
+    'å', /* 0x00 */
+    'Δ', '_', 'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ', 'Σ', 'Θ', 'Ξ', ' ', 'Æ', 'æ', 'ß', 'É', /* 0x10 */
+    ' ', '!', '"', '#', '¤', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.',
+    '/', /* 0x20 */
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?', /* 0x30 */
+    '¡', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', /* 0x40 */
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'Ä', 'Ö', 'Ñ', 'Ü', '§', /* 0x50 */
+    '¿', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', /* 0x60 */
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'ä', 'ö', 'ñ', 'ü', 'à', /* 0x70 */
+];
+
+// Convert a GSM 7 bit escaped character to unicode
+// Taken from 3GPP TS 23.038, section 6.2.1.1
+fn gsm7bit_extension_to_char(septet: u8) -> char {
+    match septet {
+        0x0A => '\x0C',
+        0x14 => '^',
+        0x28 => '{',
+        0x29 => '}',
+        0x2F => '\\',
+        0x3C => '[',
+        0x3D => '~',
+        0x3E => ']',
+        0x40 => '|',
+        0x65 => '€',
+        // Unknown extensions should be shown as the character of the default alphabet
+        _ => GSM7BIT_TO_CHAR_TABLE[septet as usize],
+    }
+}
+
+/// The maximum length of an SMS-DELIVER PDU in bytes
+const MAX_PDU_LEN: usize = 176;
+/// The size of a `+CMT` notification that holds the biggest PDU as hex string
+const CMT_NOTIFICATION_CAP: usize = 400;
+
+/// The time at which the SMS service center received an SMS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmsTimestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub seconds: u8,
+    /// The offset of the local time to UTC in minutes
+    pub utc_offset_minutes: i16,
+}
+
+/// An SMS that was received, with all of its segments put back together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedSms<const LEN: usize> {
+    /// The phone number of the sender in international format (with a leading +) if the network gave it like that.
+    /// Some senders use an alphanumeric name instead of a number.
+    pub sender: ArrayString<32>,
+    /// The time at which the service center received the (first segment of the) message
+    pub timestamp: SmsTimestamp,
+    pub text: ArrayString<LEN>,
+}
+
+#[cfg(feature = "defmt")]
+impl<const LEN: usize> defmt::Format for ReceivedSms<LEN> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "ReceivedSms {{ sender: {}, timestamp: {}, text: {} }}",
+            self.sender.as_str(),
+            self.timestamp,
+            self.text.as_str()
+        )
+    }
+}
+
+/// A single decoded SMS-DELIVER PDU
+struct SmsSegment<const LEN: usize> {
+    sms: ReceivedSms<LEN>,
+    /// The reference number, total and sequence number if the segment is part of a concatenated SMS
+    concatenation: Option<(u16, u8, u8)>,
+}
+
+// Reads the fields of a PDU front to back
+struct PduReader<'a> {
+    pdu: &'a [u8],
+}
+
+impl<'a> PduReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.pdu.len() < len {
+            return Err(Error::InvalidSmsPdu);
+        }
+
+        let (taken, rest) = self.pdu.split_at(len);
+        self.pdu = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+// A byte with two swapped BCD semi-octets
+fn swapped_bcd(byte: u8) -> u8 {
+    (byte & 0x0F) * 10 + (byte >> 4)
+}
+
+// Get the septet at the given index out of GSM 7 bit packed data
+fn unpack_gsm7bit(data: &[u8], index: usize) -> Result<u8, Error> {
+    let bit = index * 7;
+    let (byte, shift) = (bit / 8, bit % 8);
+
+    let low = *data.get(byte).ok_or(Error::InvalidSmsPdu)? as u16;
+    let high = if shift > 1 {
+        *data.get(byte + 1).ok_or(Error::InvalidSmsPdu)? as u16
+    } else {
+        0
+    };
+
+    Ok((((high << 8) | low) >> shift) as u8 & 0x7F)
+}
+
+// Decode the given range of septets out of GSM 7 bit packed data
+fn decode_gsm7bit<const LEN: usize>(
+    data: &[u8],
+    septets: core::ops::Range<usize>,
+) -> Result<ArrayString<LEN>, Error> {
+    let mut text = ArrayString::new();
+    let mut escaped = false;
+
+    for index in septets {
+        let septet = unpack_gsm7bit(data, index)?;
+
+        let c = if escaped {
+            escaped = false;
+            gsm7bit_extension_to_char(septet)
+        } else if septet == STR_7BIT_ESCAPE_CODE {
+            escaped = true;
+            continue;
         } else {
-            Err(Error::UnexpectedAtResponse)
+            GSM7BIT_TO_CHAR_TABLE[septet as usize]
+        };
+
+        text.try_push(c).map_err(|_| Error::BufferTooSmall(None))?;
+    }
+
+    Ok(text)
+}
+
+impl<const LEN: usize> SmsSegment<LEN> {
+    // Decode an SMS-DELIVER PDU
+    // See 3GPP TS 23.040, section 9.2.2.1
+    fn decode(pdu: &[u8]) -> Result<Self, Error> {
+        let mut reader = PduReader { pdu };
+
+        // Skip the service center address
+        let sca_len = reader.byte()? as usize;
+        reader.take(sca_len)?;
+
+        let first_octet = reader.byte()?;
+        if first_octet & 0x03 != 0x00 {
+            // Not an SMS-DELIVER
+            return Err(Error::InvalidSmsPdu);
+        }
+        let has_header = first_octet & 0x40 != 0;
+
+        // The originating address. The length is in semi-octets
+        let address_len = reader.byte()? as usize;
+        let address_type = reader.byte()?;
+        let address = reader.take((address_len + 1) / 2)?;
+
+        let mut sender = ArrayString::new();
+        if address_type & 0x70 == 0x50 {
+            // Alphanumeric, GSM 7 bit packed
+            sender = decode_gsm7bit(address, 0..address_len * 4 / 7)?;
+        } else {
+            if address_type & 0x70 == 0x10 {
+                sender.push('+');
+            }
+            for digit in address
+                .iter()
+                .flat_map(|b| [b & 0x0F, b >> 4])
+                .take(address_len)
+            {
+                sender
+                    .try_push(char::from_digit(digit as u32, 10).ok_or(Error::InvalidSmsPdu)?)
+                    .map_err(|_| Error::InvalidSmsPdu)?;
+            }
+        }
+
+        let _protocol_id = reader.byte()?;
+        let encoding = match reader.byte()? {
+            // General data coding, the alphabet is in bits 3 and 2
+            dcs if dcs & 0xC0 == 0x00 => match (dcs >> 2) & 0x03 {
+                0b00 => Some(SmsEncoding::Gsm7Bit),
+                0b10 => Some(SmsEncoding::Ucs2),
+                _ => None,
+            },
+            // Message waiting indication with UCS-2 text
+            dcs if dcs & 0xF0 == 0xE0 => Some(SmsEncoding::Ucs2),
+            // Data coding/message class, the alphabet is in bit 2
+            dcs if dcs & 0xF0 == 0xF0 && dcs & 0x04 != 0 => None,
+            _ => Some(SmsEncoding::Gsm7Bit),
+        };
+
+        let timestamp = reader.take(7)?;
+        let timezone = (timestamp[6] & 0x07) * 10 + (timestamp[6] >> 4);
+        let timestamp = SmsTimestamp {
+            year: 2000 + swapped_bcd(timestamp[0]) as u16,
+            month: swapped_bcd(timestamp[1]),
+            day: swapped_bcd(timestamp[2]),
+            hour: swapped_bcd(timestamp[3]),
+            minute: swapped_bcd(timestamp[4]),
+            seconds: swapped_bcd(timestamp[5]),
+            utc_offset_minutes: if timestamp[6] & 0x08 != 0 {
+                -(timezone as i16 * 15)
+            } else {
+                timezone as i16 * 15
+            },
+        };
+
+        // The user data length is in septets for GSM 7 bit and in bytes otherwise
+        let user_data_len = reader.byte()? as usize;
+        let user_data = reader.pdu;
+
+        let mut concatenation = None;
+        let header_len = if has_header {
+            let mut header = PduReader { pdu: user_data };
+            let header_len = header.byte()? as usize;
+            header.pdu = header.take(header_len)?;
+
+            while !header.pdu.is_empty() {
+                let id = header.byte()?;
+                let element_len = header.byte()? as usize;
+                let element = header.take(element_len)?;
+
+                concatenation = match (id, element) {
+                    // Concatenated short message with an 8 bit reference number
+                    (0x00, [reference, total, sequence]) => {
+                        Some((*reference as u16, *total, *sequence))
+                    }
+                    // Concatenated short message with a 16 bit reference number
+                    (0x08, [reference_high, reference_low, total, sequence]) => Some((
+                        u16::from_be_bytes([*reference_high, *reference_low]),
+                        *total,
+                        *sequence,
+                    )),
+                    _ => concatenation,
+                };
+            }
+
+            1 + header_len
+        } else {
+            0
+        };
+
+        let text = match encoding {
+            Some(SmsEncoding::Gsm7Bit) => {
+                // The header is padded up to the next septet boundary
+                let header_septets = (header_len * 8 + 6) / 7;
+                decode_gsm7bit(user_data, header_septets.min(user_data_len)..user_data_len)?
+            }
+            Some(SmsEncoding::Ucs2) => {
+                let data = user_data
+                    .get(header_len.min(user_data_len)..user_data_len)
+                    .ok_or(Error::InvalidSmsPdu)?;
+
+                let mut text = ArrayString::new();
+                for c in char::decode_utf16(
+                    data.chunks_exact(2)
+                        .map(|unit| u16::from_be_bytes([unit[0], unit[1]])),
+                ) {
+                    text.try_push(c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                        .map_err(|_| Error::BufferTooSmall(None))?;
+                }
+                text
+            }
+            // 8 bit data, interpret it as latin-1
+            None => {
+                let data = user_data
+                    .get(header_len.min(user_data_len)..user_data_len)
+                    .ok_or(Error::InvalidSmsPdu)?;
+
+                let mut text = ArrayString::new();
+                for byte in data {
+                    text.try_push(*byte as char)
+                        .map_err(|_| Error::BufferTooSmall(None))?;
+                }
+                text
+            }
+        };
+
+        Ok(Self {
+            sms: ReceivedSms {
+                sender,
+                timestamp,
+                text,
+            },
+            concatenation,
+        })
+    }
+}
+
+/// An async receiver of incoming SMS.
+///
+/// `LEN` is the maximum size of the text of a message in bytes.
+/// `SEGMENTS` is the amount of segments of concatenated messages that can be held while waiting on the other segments.
+///
+/// The segments of a concatenated SMS may arrive in any order.
+/// When a segment comes in and there is no place left to hold it, the oldest incomplete message gets dropped.
+///
+/// The modem can only receive SMS while it's connected to the network, so make sure to keep an [LteLink] alive.
+/// There should be only one receiver at a time since every SMS is acknowledged to the network by the receiver.
+pub struct SmsReceiver<const LEN: usize, const SEGMENTS: usize> {
+    notifications: crate::AtNotificationStream<CMT_NOTIFICATION_CAP, 2>,
+    segments: ArrayVec<SmsSegment<LEN>, SEGMENTS>,
+    subscribed: bool,
+}
+
+impl<const LEN: usize, const SEGMENTS: usize> SmsReceiver<LEN, SEGMENTS> {
+    /// Creates a new receiver.
+    ///
+    /// The modem is only told to forward incoming SMS once [Self::receive] is first called.
+    pub async fn new() -> Self {
+        Self {
+            notifications: crate::AtNotificationStream::with_prefix("+CMT:").await,
+            segments: ArrayVec::new(),
+            subscribed: false,
+        }
+    }
+
+    /// Wait for the next SMS.
+    ///
+    /// Messages that can't be decoded are skipped.
+    /// An error is returned if the text of a message doesn't fit in `LEN`.
+    pub async fn receive(self: core::pin::Pin<&mut Self>) -> Result<ReceivedSms<LEN>, Error> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let mut notifications = unsafe { core::pin::Pin::new_unchecked(&mut this.notifications) };
+
+        if !this.subscribed {
+            // Register the notification stream before the modem starts sending the +CMT notifications
+            notifications.as_mut().register().await;
+
+            if send_at::<6>("AT+CNMI=3,2,0,1").await?.as_str() != "OK\r\n" {
+                return Err(Error::UnexpectedAtResponse);
+            }
+            this.subscribed = true;
         }
+
+        loop {
+            let notification = futures::StreamExt::next(&mut notifications)
+                .await
+                .ok_or(Error::Disconnected)?;
+
+            // The notification has the form `+CMT: <alpha>,<length>\r\n<pdu>`
+            let pdu_hex = match notification
+                .strip_prefix("+CMT:")
+                .and_then(|cmt| cmt.split('\n').nth(1))
+            {
+                Some(pdu_hex) => pdu_hex.trim(),
+                None => continue,
+            };
+
+            // Acknowledge the SMS, otherwise the network will send it again
+            send_at::<0>("AT+CNMA=1").await?;
+
+            let mut pdu = ArrayVec::<u8, MAX_PDU_LEN>::new();
+            let segment = pdu_hex
+                .as_bytes()
+                .chunks(2)
+                .map(|hex| {
+                    core::str::from_utf8(hex)
+                        .ok()
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                        .ok_or(Error::InvalidSmsPdu)
+                })
+                .try_for_each(|byte| pdu.try_push(byte?).map_err(|_| Error::InvalidSmsPdu))
+                .and_then(|_| SmsSegment::<LEN>::decode(&pdu));
+
+            let segment = match segment {
+                Ok(segment) => segment,
+                Err(Error::BufferTooSmall(size)) => return Err(Error::BufferTooSmall(size)),
+                Err(_e) => {
+                    #[cfg(feature = "defmt")]
+                    defmt::warn!("Could not decode SMS: {}", _e);
+                    continue;
+                }
+            };
+
+            if let Some(sms) = Self::reassemble(&mut this.segments, segment)? {
+                return Ok(sms);
+            }
+        }
+    }
+
+    // Store the segment and return the full message if all of its segments are there
+    fn reassemble(
+        segments: &mut ArrayVec<SmsSegment<LEN>, SEGMENTS>,
+        segment: SmsSegment<LEN>,
+    ) -> Result<Option<ReceivedSms<LEN>>, Error> {
+        let (reference, total, _) = match segment.concatenation {
+            Some((_, total, _)) if total <= 1 => return Ok(Some(segment.sms)),
+            Some(concatenation) => concatenation,
+            None => return Ok(Some(segment.sms)),
+        };
+        let sender = segment.sms.sender;
+
+        let is_same_message = |s: &SmsSegment<LEN>| {
+            s.sms.sender == sender
+                && matches!(s.concatenation, Some((r, t, _)) if r == reference && t == total)
+        };
+
+        // A segment we already have is a repetition and can be ignored
+        if !segments
+            .iter()
+            .any(|s| is_same_message(s) && s.concatenation == segment.concatenation)
+        {
+            if SEGMENTS == 0 {
+                return Err(Error::BufferTooSmall(None));
+            }
+
+            if segments.is_full() {
+                // Drop the oldest incomplete message
+                let oldest = &segments[0];
+                let (oldest_sender, oldest_concatenation) =
+                    (oldest.sms.sender, oldest.concatenation);
+
+                #[cfg(feature = "defmt")]
+                defmt::warn!("Dropping incomplete SMS from {}", oldest_sender.as_str());
+
+                segments.retain(|s| {
+                    !(s.sms.sender == oldest_sender
+                        && matches!((s.concatenation, oldest_concatenation), (Some((r, t, _)), Some((or, ot, _))) if r == or && t == ot))
+                });
+            }
+
+            segments.push(segment);
+        }
+
+        if segments.iter().filter(|s| is_same_message(s)).count() < total as usize {
+            return Ok(None);
+        }
+
+        // All segments are there, put them together in order
+        let mut sms: Option<ReceivedSms<LEN>> = None;
+        let mut result = Ok(());
+        for sequence in 1..=total {
+            let part = segments.iter().find(|s| {
+                is_same_message(s) && matches!(s.concatenation, Some((_, _, q)) if q == sequence)
+            });
+
+            match (part, sms.as_mut()) {
+                (Some(part), None) => sms = Some(part.sms.clone()),
+                (Some(part), Some(sms)) => {
+                    if sms.text.try_push_str(&part.sms.text).is_err() {
+                        result = Err(Error::BufferTooSmall(None));
+                    }
+                }
+                (None, _) => result = Err(Error::InvalidSmsPdu),
+            }
+        }
+
+        segments.retain(|s| !is_same_message(s));
+        result.map(|_| sms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The bytes of the PDU in an AT+CMGS command, together with the length the command gives for it
+    fn pdu_bytes(at_cmgs: &str) -> (usize, ArrayVec<u8, MAX_PDU_LEN>) {
+        let (command, hex) = at_cmgs.split_once('\r').unwrap();
+        let size = command.strip_prefix("AT+CMGS=").unwrap().parse().unwrap();
+        let hex = hex.strip_suffix('\x1A').unwrap();
+
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        (size, bytes)
+    }
+
+    // The TP-DCS, TP-UDL and user data of an SMS-SUBMIT PDU to a 12 digit number
+    fn user_data(pdu: &[u8]) -> (u8, u8, &[u8]) {
+        // SMSC length, first octet, TP-MR, number length, type of address, number, TP-PID
+        let offset = 5 + 6 + 1;
+        (pdu[offset], pdu[offset + 1], &pdu[offset + 2..])
+    }
+
+    #[test]
+    fn ucs2_segments_keep_surrogate_pairs_together() {
+        // 66 characters fill 132 bytes, so the surrogate pair doesn't fit in the 134 bytes of the first segment
+        let mut message = ArrayString::<512>::new();
+        (0..66).for_each(|_| message.push('é'));
+        message.push('😀');
+        (0..10).for_each(|_| message.push('é'));
+
+        let sms = Sms::new("+31612345678", &message);
+        assert_eq!(sms.encoding(), SmsEncoding::Ucs2);
+        assert_eq!(sms.segment_count(), 2);
+
+        let segments: ArrayVec<_, 2> = sms.segments(true).collect();
+        assert_eq!(segments[0].len(), 132);
+        assert_eq!(&segments[0][130..], &[0x00, 0xE9]);
+        assert_eq!(segments[1].len(), 4 + 20);
+        assert_eq!(&segments[1][..4], &[0xD8, 0x3D, 0xDE, 0x00]);
+    }
+
+    #[test]
+    fn ucs2_segments_are_filled_up_to_134_bytes() {
+        let mut message = ArrayString::<512>::new();
+        (0..68).for_each(|_| message.push('é'));
+        message.push('😀');
+        message.push('😀');
+
+        let sms = Sms::new("+31612345678", &message);
+        assert_eq!(sms.segment_count(), 2);
+
+        let segments: ArrayVec<_, 2> = sms.segments(true).collect();
+        assert_eq!(segments[0].len(), 134);
+        assert_eq!(segments[1].len(), 2 + 8);
+    }
+
+    #[test]
+    fn concatenated_ucs2_pdu() {
+        let mut message = ArrayString::<512>::new();
+        (0..100).for_each(|_| message.push('é'));
+
+        let sms = Sms::new("+31612345678", &message);
+        let encoded_number = Sms::encode_number("+31612345678").unwrap();
+        let segments: ArrayVec<_, 2> = sms.segments(true).collect();
+        assert_eq!(segments.len(), 2);
+
+        for (index, segment) in segments.iter().enumerate() {
+            let header = concat_header(7, 2, index as u8 + 1);
+            let at_cmgs = sms
+                .encode_pdu::<354>(&encoded_number, Some(header), segment)
+                .unwrap();
+            let (size, pdu) = pdu_bytes(&at_cmgs);
+
+            // The length leaves out the SMSC length byte
+            assert_eq!(size, pdu.len() - 1);
+            // SMS-SUBMIT with TP-UDHI
+            assert_eq!(pdu[1], 0x41);
+
+            let (dcs, udl, user_data) = user_data(&pdu);
+            assert_eq!(dcs, 0x08);
+            assert_eq!(udl as usize, CONCAT_UDH_LEN + segment.len());
+            assert_eq!(&user_data[..6], &[0x05, 0x00, 0x03, 7, 2, index as u8 + 1]);
+            assert_eq!(&user_data[6..], &segment[..]);
+        }
+
+        let (_, pdu) = pdu_bytes(
+            &sms.encode_pdu::<354>(&encoded_number, Some(concat_header(7, 2, 1)), &segments[0])
+                .unwrap(),
+        );
+        assert_eq!(user_data(&pdu).1, 6 + 134);
+    }
+
+    #[test]
+    fn concatenated_gsm7bit_pdu_has_fill_bit_after_header() {
+        let mut message = ArrayString::<512>::new();
+        (0..200).for_each(|_| message.push('A'));
+
+        let sms = Sms::new("+31612345678", &message);
+        let encoded_number = Sms::encode_number("+31612345678").unwrap();
+        assert_eq!(sms.encoding(), SmsEncoding::Gsm7Bit);
+        assert_eq!(sms.segment_count(), 2);
+
+        let segments: ArrayVec<_, 2> = sms.segments(true).collect();
+        assert_eq!(segments[0].len(), 153);
+        assert_eq!(segments[1].len(), 47);
+
+        let at_cmgs = sms
+            .encode_pdu::<354>(&encoded_number, Some(concat_header(3, 2, 1)), &segments[0])
+            .unwrap();
+        let (size, pdu) = pdu_bytes(&at_cmgs);
+        assert_eq!(size, pdu.len() - 1);
+
+        let (dcs, udl, user_data) = user_data(&pdu);
+        assert_eq!(dcs, 0x00);
+        // The 6 header bytes and the fill bit take up 7 septets
+        assert_eq!(udl, 7 + 153);
+        assert_eq!(&user_data[..6], &[0x05, 0x00, 0x03, 3, 2, 1]);
+        assert_eq!(user_data.len(), MAX_USER_DATA_LEN);
+        // The first septet starts after a single fill bit, so the second one starts at the next byte
+        assert_eq!(user_data[6], 0x41 << 1);
+        assert_eq!(user_data[7], 0x41 | 0x41 << 7);
+    }
+
+    #[test]
+    fn gsm7bit_packing() {
+        let septets: ArrayVec<u8, 10> = "hellohello"
+            .chars()
+            .flat_map(|c| SmsEncoding::Gsm7Bit.encode_char(c))
+            .collect();
+
+        assert_eq!(
+            &Sms::pack_gsm7bit(&septets, 0)[..],
+            &[0xE8, 0x32, 0x9B, 0xFD, 0x46, 0x97, 0xD9, 0xEC, 0x37]
+        );
+        assert_eq!(&Sms::pack_gsm7bit(&[0x41], 1)[..], &[0x82]);
     }
 }
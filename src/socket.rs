@@ -1,10 +1,11 @@
 use crate::{
     error::Error, ffi::get_last_error, ip::NrfSockAddr, lte_link::LteLink, CancellationToken,
 };
+use arrayvec::ArrayVec;
 use core::{
     cell::RefCell,
     ops::{BitOr, BitOrAssign, Deref, Neg},
-    sync::atomic::{AtomicU8, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering},
     task::{Poll, Waker},
 };
 use critical_section::Mutex;
@@ -13,32 +14,316 @@ use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 // use 16 slots for wakers instead of 8, which is the max number of sockets allowed, so they
 // are not overwritten when split into their rx/tx counterparts and run in separate tasks.
+// The `socket-wakers-32` and `socket-wakers-64` features give more slots for when many operations
+// wait at the same time, e.g. with [poll_sockets].
+#[cfg(not(any(feature = "socket-wakers-32", feature = "socket-wakers-64")))]
 const WAKER_SLOTS: usize = (nrfxlib_sys::NRF_MODEM_MAX_SOCKET_COUNT * 2) as usize;
+#[cfg(all(feature = "socket-wakers-32", not(feature = "socket-wakers-64")))]
+const WAKER_SLOTS: usize = 32;
+#[cfg(feature = "socket-wakers-64")]
+const WAKER_SLOTS: usize = 64;
 const WAKER_INIT: Option<(Waker, i32, SocketDirection)> = None;
 #[allow(clippy::type_complexity)]
 static SOCKET_WAKERS: Mutex<RefCell<[Option<(Waker, i32, SocketDirection)>; WAKER_SLOTS]>> =
     Mutex::new(RefCell::new([WAKER_INIT; WAKER_SLOTS]));
 
+/// The maximum amount of sockets the modem can have open at the same time
+const MAX_SOCKETS: u8 = nrfxlib_sys::NRF_MODEM_MAX_SOCKET_COUNT as u8;
+/// The amount of sockets that are currently open
+static OPEN_SOCKETS: AtomicU8 = AtomicU8::new(0);
+
+const STATS_INIT: Option<(i32, SocketStats)> = None;
+/// The stats of the open sockets, by fd
+#[allow(clippy::type_complexity)]
+static SOCKET_STATS: Mutex<RefCell<[Option<(i32, SocketStats)>; MAX_SOCKETS as usize]>> =
+    Mutex::new(RefCell::new([STATS_INIT; MAX_SOCKETS as usize]));
+
+/// The amount of data that has been sent and received on a socket since it was created.
+///
+/// This counts the payload that is given to and taken from the modem, so the TCP, UDP, IP and TLS
+/// headers and any retransmissions are not included. The modem has no counters for those per socket.
+/// See [crate::data_usage()] for the data usage of the whole modem.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SocketStats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+/// A release assistance indication (RAI), which tells the network how much more data is expected on a socket.
+///
+/// With it the modem can leave the connected mode right after the last data, instead of waiting for the
+/// network to release the connection after seconds of inactivity. This saves a lot of energy for a device that
+/// sends one message and goes to sleep. The network has to support RAI for it to have any effect,
+/// which is mostly the case on LTE-M and NB-IoT networks with 3GPP release 14 features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReleaseAssistance {
+    /// No more data will be sent or received. This applies right away and requires a connected socket.
+    NoData,
+    /// The next send is the last one and no response is expected
+    Last,
+    /// The next send is the last one and exactly one response is expected
+    OneResponse,
+    /// The socket is in active use by a client, so the modem should stay in connected mode longer
+    Ongoing,
+    /// The socket is in active use by a server, so the modem should stay in connected mode longer
+    WaitMore,
+}
+
+impl ReleaseAssistance {
+    pub(crate) fn as_integer(self) -> i32 {
+        (match self {
+            ReleaseAssistance::NoData => nrfxlib_sys::NRF_RAI_NO_DATA,
+            ReleaseAssistance::Last => nrfxlib_sys::NRF_RAI_LAST,
+            ReleaseAssistance::OneResponse => nrfxlib_sys::NRF_RAI_ONE_RESP,
+            ReleaseAssistance::Ongoing => nrfxlib_sys::NRF_RAI_ONGOING,
+            ReleaseAssistance::WaitMore => nrfxlib_sys::NRF_RAI_WAIT_MORE,
+        }) as i32
+    }
+}
+
+/// Get how many more sockets can be opened.
+///
+/// The modem supports at most 8 sockets at the same time. This includes the sockets of every
+/// [crate::TcpStream], [crate::UdpSocket] and [crate::DtlsSocket], but not the ones opened through [crate::ffi] or nrfxlib directly.
+/// Opening more returns [Error::TooManySockets].
+pub fn available_sockets() -> usize {
+    (MAX_SOCKETS - OPEN_SOCKETS.load(Ordering::SeqCst)) as usize
+}
+
+/// What to wait for on a socket in [poll_sockets]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SocketInterest {
+    /// Wait for data to receive
+    Readable,
+    /// Wait for room to send data
+    Writable,
+    /// Wait for either of the two
+    ReadableOrWritable,
+}
+
+impl SocketInterest {
+    fn events(self) -> u32 {
+        match self {
+            SocketInterest::Readable => nrfxlib_sys::NRF_POLLIN,
+            SocketInterest::Writable => nrfxlib_sys::NRF_POLLOUT,
+            SocketInterest::ReadableOrWritable => {
+                nrfxlib_sys::NRF_POLLIN | nrfxlib_sys::NRF_POLLOUT
+            }
+        }
+    }
+
+    fn direction(self) -> SocketDirection {
+        match self {
+            SocketInterest::Readable => SocketDirection::In,
+            SocketInterest::Writable => SocketDirection::Out,
+            SocketInterest::ReadableOrWritable => SocketDirection::Either,
+        }
+    }
+}
+
+/// The readiness of a socket, as returned by [poll_sockets]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SocketReadiness {
+    /// There is data to receive
+    pub readable: bool,
+    /// There is room to send data
+    pub writable: bool,
+    /// The socket has an error or the connection was closed. The next receive or send call returns it.
+    pub error: bool,
+}
+
+impl SocketReadiness {
+    fn from_revents(revents: u32) -> Self {
+        Self {
+            readable: revents & nrfxlib_sys::NRF_POLLIN != 0,
+            writable: revents & nrfxlib_sys::NRF_POLLOUT != 0,
+            error: revents
+                & (nrfxlib_sys::NRF_POLLERR | nrfxlib_sys::NRF_POLLHUP | nrfxlib_sys::NRF_POLLNVAL)
+                != 0,
+        }
+    }
+
+    /// True if anything can be done with the socket
+    pub fn is_ready(&self) -> bool {
+        self.readable || self.writable || self.error
+    }
+}
+
+/// Wait until at least one of the given sockets is ready, using [nrfxlib_sys::nrf_poll].
+///
+/// The sockets are given by their raw file descriptor (see e.g. [crate::TcpStream::as_raw_fd]) and what to wait for.
+/// The readiness of every socket is returned in the same order, so one task can serve multiple sockets.
+///
+/// ```rust,ignore
+/// let [tcp, udp] = poll_sockets(
+///     [
+///         (stream.as_raw_fd(), SocketInterest::Readable),
+///         (socket.as_raw_fd(), SocketInterest::Readable),
+///     ],
+///     &token,
+/// )
+/// .await?;
+/// ```
+pub async fn poll_sockets<const N: usize>(
+    sockets: [(i32, SocketInterest); N],
+    token: &CancellationToken,
+) -> Result<[SocketReadiness; N], Error> {
+    token.bind_to_current_task().await;
+
+    let _waker_guards =
+        sockets.map(|(fd, interest)| SocketWakerGuard::new(fd, interest.direction()));
+
+    core::future::poll_fn(|cx| {
+        if token.is_cancelled() {
+            return Poll::Ready(Err(Error::OperationCancelled));
+        }
+
+        // Register first so an event that comes in right after the poll still wakes us
+        for (fd, interest) in sockets.iter() {
+            register_socket_waker(cx.waker().clone(), *fd, interest.direction())?;
+        }
+
+        let mut pollfds = sockets.map(|(fd, interest)| nrfxlib_sys::nrf_pollfd {
+            fd,
+            events: interest.events() as _,
+            revents: 0,
+        });
+
+        if unsafe { nrfxlib_sys::nrf_poll(pollfds.as_mut_ptr(), N as _, 0) } == -1 {
+            return Poll::Ready(Err(Error::NrfError(get_last_error())));
+        }
+
+        let readiness = pollfds.map(|pollfd| SocketReadiness::from_revents(pollfd.revents as u32));
+
+        match readiness.iter().any(SocketReadiness::is_ready) {
+            true => Poll::Ready(Ok(readiness)),
+            false => Poll::Pending,
+        }
+    })
+    .await
+}
+
+/// Whether the modem was registered to the network at the last `+CEREG` it reported
+static NETWORK_REGISTERED: AtomicBool = AtomicBool::new(false);
+/// The amount of times the modem lost its network registration
+static NETWORK_LOST_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Keep track of the network registration. When it's lost, all waiting socket operations are woken up
+/// so they can return [Error::NetworkDown] instead of waiting for a timeout.
+pub(crate) fn update_network_registration(registered: bool) {
+    let was_registered = NETWORK_REGISTERED.swap(registered, Ordering::SeqCst);
+
+    if was_registered && !registered {
+        #[cfg(feature = "defmt")]
+        defmt::debug!("Network registration lost, waking the sockets");
+
+        NETWORK_LOST_COUNT.fetch_add(1, Ordering::SeqCst);
+
+        take_socket_wakers(|_, _| true)
+            .into_iter()
+            .for_each(Waker::wake);
+    }
+}
+
+/// Goes up every time the modem library is restarted, which closes all sockets
+static SOCKET_GENERATION: AtomicU32 = AtomicU32::new(0);
+
+/// Mark all existing sockets as closed, after the modem library was restarted.
+/// Their waiting operations are woken up and return [Error::SocketInvalidated].
+pub(crate) fn invalidate_sockets() {
+    SOCKET_GENERATION.fetch_add(1, Ordering::SeqCst);
+
+    critical_section::with(|cs| SOCKET_STATS.borrow_ref_mut(cs).fill(STATS_INIT));
+    take_socket_wakers(|_, _| true)
+        .into_iter()
+        .for_each(Waker::wake);
+}
+
+/// Returns true if the network registration was lost since the [NETWORK_LOST_COUNT] had the given value
+fn network_lost_since(lost_count: u32) -> bool {
+    NETWORK_LOST_COUNT.load(Ordering::SeqCst) != lost_count
+}
+
+/// Turn the return value of a send or receive call into the result of the poll.
+///
+/// A return value of 0 for a buffer that isn't empty means that the peer has closed the connection.
+/// That's [Error::Disconnected], just like `ENOTCONN`, and a reset connection is [Error::ConnectionReset].
+fn map_io_result(result: isize, buffer_is_empty: bool) -> Poll<Result<usize, Error>> {
+    const NRF_EWOULDBLOCK: isize = -(nrfxlib_sys::NRF_EWOULDBLOCK as isize);
+    const NRF_ENOTCONN: isize = -(nrfxlib_sys::NRF_ENOTCONN as isize);
+    const NRF_ECONNRESET: isize = -(nrfxlib_sys::NRF_ECONNRESET as isize);
+    const NRF_ETIMEDOUT: isize = -(nrfxlib_sys::NRF_ETIMEDOUT as isize);
+
+    match result {
+        0 if !buffer_is_empty => Poll::Ready(Err(Error::Disconnected)),
+        NRF_ENOTCONN => Poll::Ready(Err(Error::Disconnected)),
+        NRF_ECONNRESET => Poll::Ready(Err(Error::ConnectionReset)),
+        NRF_ETIMEDOUT => Poll::Ready(Err(Error::Timeout)),
+        len @ 0.. => Poll::Ready(Ok(len as usize)),
+        NRF_EWOULDBLOCK => Poll::Pending,
+        error => Poll::Ready(Err(Error::NrfError(error))),
+    }
+}
+
+/// The default maximum amount of bytes that is handed to the modem in a single stream call
+pub(crate) const DEFAULT_MAX_SEGMENT_SIZE: usize = 1024;
+
+/// Wake the operations that wait for the socket in the given direction.
+///
+/// This is called from the IPC interrupt, so it doesn't allocate and only holds the critical section
+/// while taking the wakers out of their slots. The wakers themselves are called after that.
 fn wake_sockets(socket_fd: i32, socket_dir: SocketDirection) {
+    take_socket_wakers(|fd, dir| fd == socket_fd && dir.same_direction(socket_dir))
+        .into_iter()
+        .for_each(Waker::wake);
+}
+
+/// Frees the waker slot of an operation when it's done or dropped.
+/// Otherwise the slot would stay taken until the socket gets an event in that direction.
+struct SocketWakerGuard {
+    fd: i32,
+    direction: SocketDirection,
+}
+
+impl SocketWakerGuard {
+    fn new(fd: i32, direction: SocketDirection) -> Self {
+        Self { fd, direction }
+    }
+}
+
+impl Drop for SocketWakerGuard {
+    fn drop(&mut self) {
+        take_socket_wakers(|fd, direction| fd == self.fd && direction == self.direction);
+    }
+}
+
+/// Take the wakers out of the slots that match the filter
+fn take_socket_wakers(
+    mut filter: impl FnMut(i32, SocketDirection) -> bool,
+) -> ArrayVec<Waker, WAKER_SLOTS> {
     critical_section::with(|cs| {
         SOCKET_WAKERS
             .borrow_ref_mut(cs)
             .iter_mut()
-            .filter(|slot| {
-                if let Some((_, fd, dir)) = slot {
-                    *fd == socket_fd && dir.same_direction(socket_dir)
-                } else {
-                    false
-                }
-            })
-            .for_each(|slot| {
-                let (waker, _, _) = slot.take().unwrap();
-                waker.wake();
-            });
-    });
+            .filter(|slot| matches!(slot, Some((_, fd, dir)) if filter(*fd, *dir)))
+            .filter_map(|slot| slot.take())
+            .map(|(waker, _, _)| waker)
+            .collect()
+    })
 }
 
-fn register_socket_waker(waker: Waker, socket_fd: i32, socket_dir: SocketDirection) {
+/// Store the waker so the socket callback can wake it.
+///
+/// Returns [Error::TooManySocketWakers] when all slots are taken by other operations that are waiting.
+fn register_socket_waker(
+    waker: Waker,
+    socket_fd: i32,
+    socket_dir: SocketDirection,
+) -> Result<(), Error> {
     critical_section::with(|cs| {
         // Get the wakers
         let mut wakers = SOCKET_WAKERS.borrow_ref_mut(cs);
@@ -49,21 +334,20 @@ fn register_socket_waker(waker: Waker, socket_fd: i32, socket_dir: SocketDirecti
                 || waker.as_ref().map(|(_, fd, dir)| (*fd, *dir)) == Some((socket_fd, socket_dir))
         });
 
-        if let Some(empty_waker) = empty_waker {
-            // In principle we should always have an empty spot and run this code
-            *empty_waker = Some((waker, socket_fd, socket_dir));
-        } else {
-            // It shouldn't ever happen, but if there's no empty spot, we just evict the first socket
-            // That socket will just reregister itself
-            wakers
-                .first_mut()
-                .unwrap()
-                .replace((waker, socket_fd, socket_dir))
-                .unwrap()
-                .0
-                .wake();
+        match empty_waker {
+            Some(empty_waker) => {
+                *empty_waker = Some((waker, socket_fd, socket_dir));
+                Ok(())
+            }
+            None => {
+                // Evicting another waker would make the two operations keep evicting each other
+                #[cfg(feature = "defmt")]
+                defmt::warn!("No free socket waker slot for fd {}", socket_fd);
+
+                Err(Error::TooManySocketWakers)
+            }
         }
-    });
+    })
 }
 
 unsafe extern "C" fn socket_poll_callback(pollfd: *mut nrfxlib_sys::nrf_pollfd) {
@@ -162,6 +446,10 @@ pub struct Socket {
     link: Option<LteLink>,
     /// Gets set to true when the socket has been split. This is relevant for the drop functions
     split: bool,
+    /// The maximum amount of bytes the stream wrappers give to the modem in one call
+    max_segment_size: usize,
+    /// The [SOCKET_GENERATION] the socket was created in
+    generation: u32,
 }
 
 impl Socket {
@@ -186,6 +474,13 @@ impl Socket {
         // Let's activate the modem
         let link = LteLink::new().await?;
 
+        // Claim a socket slot. It's released again when the socket is closed in the drop function.
+        OPEN_SOCKETS
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                (open < MAX_SOCKETS).then_some(open + 1)
+            })
+            .map_err(|_| Error::TooManySockets)?;
+
         // Create the socket in the nrf-modem lib
         let fd = unsafe {
             nrfxlib_sys::nrf_socket(
@@ -197,9 +492,40 @@ impl Socket {
 
         // If the fd is -1, then there is an error in `errno`
         if fd == -1 {
-            return Err(Error::NrfError(get_last_error()));
+            OPEN_SOCKETS.fetch_sub(1, Ordering::SeqCst);
+
+            let errno = get_last_error();
+            return match errno.unsigned_abs() as u32 {
+                nrfxlib_sys::NRF_EMFILE | nrfxlib_sys::NRF_ENFILE => Err(Error::TooManySockets),
+                _ => Err(Error::NrfError(errno)),
+            };
         }
 
+        Self::from_fd(fd, family, link)
+    }
+
+    /// Wrap a file descriptor of the modem lib, which already holds one of the [OPEN_SOCKETS] slots
+    fn from_fd(fd: i32, family: SocketFamily, link: LteLink) -> Result<Self, Error> {
+        critical_section::with(|cs| {
+            if let Some(slot) = SOCKET_STATS
+                .borrow_ref_mut(cs)
+                .iter_mut()
+                .find(|slot| slot.is_none())
+            {
+                *slot = Some((fd, SocketStats::default()));
+            }
+        });
+
+        // From here on the drop function closes the socket when something fails
+        let socket = Socket {
+            fd,
+            family,
+            link: Some(link),
+            split: false,
+            max_segment_size: DEFAULT_MAX_SEGMENT_SIZE,
+            generation: SOCKET_GENERATION.load(Ordering::SeqCst),
+        };
+
         // Set the socket to non-blocking
         unsafe {
             let result = nrfxlib_sys::nrf_fcntl(
@@ -234,12 +560,7 @@ impl Socket {
             }
         }
 
-        Ok(Socket {
-            fd,
-            family,
-            link: Some(link),
-            split: false,
-        })
+        Ok(socket)
     }
 
     /// Get the nrf-modem file descriptor so the user can opt out of using this high level wrapper for things
@@ -247,8 +568,76 @@ impl Socket {
         self.fd
     }
 
+    /// Returns [Error::SocketInvalidated] if the modem library was restarted since the socket was created
+    fn check_valid(&self) -> Result<(), Error> {
+        match self.generation == SOCKET_GENERATION.load(Ordering::SeqCst) {
+            true => Ok(()),
+            false => Err(Error::SocketInvalidated),
+        }
+    }
+
+    /// Give the release assistance indication to the modem.
+    ///
+    /// [ReleaseAssistance::NoData] on a socket that isn't connected gives [SocketOptionError::NotConnected].
+    pub fn set_release_assistance(&self, rai: ReleaseAssistance) -> Result<(), Error> {
+        self.set_option(SocketOption::ReleaseAssistance(rai.as_integer()))?;
+        Ok(())
+    }
+
+    /// Get the amount of data that has been sent and received on this socket
+    pub fn stats(&self) -> SocketStats {
+        critical_section::with(|cs| {
+            SOCKET_STATS
+                .borrow_ref(cs)
+                .iter()
+                .flatten()
+                .find(|(fd, _)| *fd == self.fd)
+                .map(|(_, stats)| *stats)
+                .unwrap_or_default()
+        })
+    }
+
+    fn update_stats(&self, f: impl FnOnce(&mut Option<(i32, SocketStats)>)) {
+        critical_section::with(|cs| {
+            if let Some(slot) = SOCKET_STATS
+                .borrow_ref_mut(cs)
+                .iter_mut()
+                .find(|slot| matches!(slot, Some((fd, _)) if *fd == self.fd))
+            {
+                f(slot);
+            }
+        });
+    }
+
+    fn count_sent(&self, len: usize) {
+        self.update_stats(|slot| {
+            if let Some((_, stats)) = slot {
+                stats.bytes_sent += len as u64;
+            }
+        });
+    }
+
+    fn count_received(&self, len: usize) {
+        self.update_stats(|slot| {
+            if let Some((_, stats)) = slot {
+                stats.bytes_received += len as u64;
+            }
+        });
+    }
+
+    /// The maximum amount of bytes the stream wrappers give to the modem in one call
+    pub fn max_segment_size(&self) -> usize {
+        self.max_segment_size
+    }
+
+    /// Set the maximum amount of bytes the stream wrappers give to the modem in one call.
+    /// A size of 0 is treated as 1.
+    pub fn set_max_segment_size(&mut self, size: usize) {
+        self.max_segment_size = size.max(1);
+    }
+
     pub async fn split(mut self) -> Result<(SplitSocketHandle, SplitSocketHandle), Error> {
-        let index = SplitSocketHandle::get_new_spot();
+        let index = SplitSocketHandle::get_new_spot(2);
         self.split = true;
 
         Ok((
@@ -258,6 +647,8 @@ impl Socket {
                     family: self.family,
                     link: Some(LteLink::new().await?),
                     split: true,
+                    max_segment_size: self.max_segment_size,
+                    generation: self.generation,
                 }),
                 index,
             },
@@ -268,6 +659,18 @@ impl Socket {
         ))
     }
 
+    /// Turn the socket into a handle that can be duplicated with [SplitSocketHandle::duplicate].
+    /// The socket is closed when the last handle is gone.
+    pub(crate) fn share(mut self) -> SplitSocketHandle {
+        let index = SplitSocketHandle::get_new_spot(1);
+        self.split = true;
+
+        SplitSocketHandle {
+            inner: Some(self),
+            index,
+        }
+    }
+
     /// Connect to the given socket address.
     ///
     /// This calls the `nrf_connect` function and can be used for tcp streams, udp connections and dtls connections.
@@ -288,6 +691,7 @@ impl Socket {
         );
 
         token.bind_to_current_task().await;
+        let lost_count = NETWORK_LOST_COUNT.load(Ordering::SeqCst);
 
         // Before we can connect, we need to make sure we have a working link.
         // It is possible this will never resolve, but it is up to the user to manage the timeouts.
@@ -297,6 +701,7 @@ impl Socket {
             .wait_for_link_with_cancellation(token)
             .await?;
 
+        let _waker_guard = SocketWakerGuard::new(self.fd, SocketDirection::Either);
         core::future::poll_fn(|cx| {
             #[cfg(feature = "defmt")]
             defmt::trace!("Connecting socket {}", self.fd);
@@ -305,10 +710,16 @@ impl Socket {
                 return Poll::Ready(Err(Error::OperationCancelled));
             }
 
+            if network_lost_since(lost_count) {
+                return Poll::Ready(Err(Error::NetworkDown));
+            }
+
+            self.check_valid()?;
+
             // Cast the address to something the nrf-modem understands
             let address = NrfSockAddr::from(address);
 
-            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::Either);
+            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::Either)?;
 
             // Do the connect call, this is non-blocking due to the socket setup
             let mut connect_result = unsafe {
@@ -318,6 +729,7 @@ impl Socket {
             const NRF_EINPROGRESS: isize = nrfxlib_sys::NRF_EINPROGRESS as isize;
             const NRF_EALREADY: isize = nrfxlib_sys::NRF_EALREADY as isize;
             const NRF_EISCONN: isize = nrfxlib_sys::NRF_EISCONN as isize;
+            const NRF_ETIMEDOUT: isize = nrfxlib_sys::NRF_ETIMEDOUT as isize;
 
             if connect_result == -1 {
                 connect_result = get_last_error();
@@ -333,6 +745,8 @@ impl Socket {
                 NRF_EISCONN => Poll::Ready(Ok(())),
                 // The socket is not yet connected
                 NRF_EINPROGRESS | NRF_EALREADY => Poll::Pending,
+                // The modem gave up
+                NRF_ETIMEDOUT => Poll::Ready(Err(Error::Timeout)),
                 // Something else, this is likely an error
                 error => Poll::Ready(Err(Error::NrfError(error))),
             }
@@ -371,6 +785,7 @@ impl Socket {
             .wait_for_link_with_cancellation(token)
             .await?;
 
+        let _waker_guard = SocketWakerGuard::new(self.fd, SocketDirection::Either);
         core::future::poll_fn(|cx| {
             #[cfg(feature = "defmt")]
             defmt::trace!("Binding socket {}", self.fd);
@@ -382,7 +797,7 @@ impl Socket {
             // Cast the address to something the nrf-modem understands
             let address = NrfSockAddr::from(address);
 
-            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::Either);
+            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::Either)?;
 
             // Do the bind call, this is non-blocking due to the socket setup
             let mut bind_result =
@@ -392,6 +807,7 @@ impl Socket {
             const NRF_EINPROGRESS: isize = nrfxlib_sys::NRF_EINPROGRESS as isize;
             const NRF_EALREADY: isize = nrfxlib_sys::NRF_EALREADY as isize;
             const NRF_EISCONN: isize = nrfxlib_sys::NRF_EISCONN as isize;
+            const NRF_ETIMEDOUT: isize = nrfxlib_sys::NRF_ETIMEDOUT as isize;
 
             if bind_result == -1 {
                 bind_result = get_last_error();
@@ -407,6 +823,8 @@ impl Socket {
                 NRF_EISCONN => Poll::Ready(Ok(())),
                 // The socket is not yet connected
                 NRF_EINPROGRESS | NRF_EALREADY => Poll::Pending,
+                // The modem gave up
+                NRF_ETIMEDOUT => Poll::Ready(Err(Error::Timeout)),
                 // Something else, this is likely an error
                 error => Poll::Ready(Err(Error::NrfError(error))),
             }
@@ -416,53 +834,272 @@ impl Socket {
         Ok(())
     }
 
-    /// Call the [nrfxlib_sys::nrf_send] in an async fashion
-    pub async fn write(&self, buffer: &[u8], token: &CancellationToken) -> Result<usize, Error> {
+    /// Mark the socket as accepting incoming connections, with `nrf_listen`.
+    ///
+    /// The `backlog` is the amount of connections that can wait to be accepted.
+    pub fn listen(&self, backlog: i32) -> Result<(), Error> {
+        #[cfg(feature = "defmt")]
+        defmt::debug!("Listening on socket {}", self.fd);
+
+        if unsafe { nrfxlib_sys::nrf_listen(self.fd, backlog) } == -1 {
+            return Err(Error::NrfError(get_last_error()));
+        }
+
+        Ok(())
+    }
+
+    /// Wait for an incoming connection and accept it, using `nrf_accept` in an async fashion.
+    ///
+    /// Returns the socket of the new connection and the address of the peer.
+    pub async fn accept(&self, token: &CancellationToken) -> Result<(Socket, SocketAddr), Error> {
         token.bind_to_current_task().await;
+        let lost_count = NETWORK_LOST_COUNT.load(Ordering::SeqCst);
 
-        core::future::poll_fn(|cx| {
+        // The new socket holds its own link, like every socket
+        let link = LteLink::new().await?;
+
+        let _waker_guard = SocketWakerGuard::new(self.fd, SocketDirection::In);
+        let result = core::future::poll_fn(|cx| {
             #[cfg(feature = "defmt")]
-            defmt::trace!("Sending with socket {}", self.fd);
+            defmt::trace!("Accepting on socket {}", self.fd);
 
             if token.is_cancelled() {
                 return Poll::Ready(Err(Error::OperationCancelled));
             }
 
-            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::Out);
+            if network_lost_since(lost_count) {
+                return Poll::Ready(Err(Error::NetworkDown));
+            }
 
-            let mut send_result = unsafe {
-                nrfxlib_sys::nrf_send(self.fd, buffer.as_ptr() as *const _, buffer.len(), 0)
+            self.check_valid()?;
+
+            // Big enough to store both ipv4 and ipv6, and properly aligned for both
+            let mut socket_addr_store: nrfxlib_sys::nrf_sockaddr_in6 =
+                unsafe { core::mem::zeroed() };
+            let socket_addr_ptr =
+                &mut socket_addr_store as *mut _ as *mut nrfxlib_sys::nrf_sockaddr;
+            let mut socket_addr_len = core::mem::size_of::<nrfxlib_sys::nrf_sockaddr_in6>() as u32;
+
+            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::In)?;
+
+            // Claim a socket slot for the new connection
+            if OPEN_SOCKETS
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                    (open < MAX_SOCKETS).then_some(open + 1)
+                })
+                .is_err()
+            {
+                return Poll::Ready(Err(Error::TooManySockets));
+            }
+
+            let fd = unsafe {
+                nrfxlib_sys::nrf_accept(self.fd, socket_addr_ptr, &mut socket_addr_len as *mut u32)
             };
 
-            if send_result == -1 {
-                send_result = get_last_error().abs().neg();
+            if fd != -1 {
+                unsafe { (*socket_addr_ptr).sa_family = self.family as u16 }
+                return Poll::Ready(Ok((
+                    fd,
+                    NrfSockAddr::from(socket_addr_ptr as *const _).into(),
+                )));
             }
 
+            OPEN_SOCKETS.fetch_sub(1, Ordering::SeqCst);
+
+            let errno = get_last_error();
+
             #[cfg(feature = "defmt")]
-            defmt::trace!("Send result {}", send_result);
+            defmt::trace!("Accept result {}", errno);
 
-            const NRF_EWOULDBLOCK: isize = -(nrfxlib_sys::NRF_EWOULDBLOCK as isize);
-            const NRF_ENOTCONN: isize = -(nrfxlib_sys::NRF_ENOTCONN as isize);
+            match errno.unsigned_abs() as u32 {
+                nrfxlib_sys::NRF_EWOULDBLOCK => Poll::Pending,
+                nrfxlib_sys::NRF_EMFILE | nrfxlib_sys::NRF_ENFILE => {
+                    Poll::Ready(Err(Error::TooManySockets))
+                }
+                _ => Poll::Ready(Err(Error::NrfError(errno))),
+            }
+        })
+        .await;
 
-            match send_result {
-                0 if !buffer.is_empty() => Poll::Ready(Err(Error::Disconnected)),
-                NRF_ENOTCONN => Poll::Ready(Err(Error::Disconnected)),
-                bytes_sent @ 0.. => Poll::Ready(Ok(bytes_sent as usize)),
-                NRF_EWOULDBLOCK => Poll::Pending,
-                error => Poll::Ready(Err(Error::NrfError(error))),
+        match result {
+            Ok((fd, address)) => Ok((Self::from_fd(fd, self.family, link)?, address)),
+            Err(e) => {
+                link.deactivate().await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Wait until the socket has data to receive, using [nrfxlib_sys::nrf_poll].
+    ///
+    /// An error or a closed connection also counts as readable, so the next receive call returns it.
+    pub async fn readable(&self, token: &CancellationToken) -> Result<(), Error> {
+        self.wait_for_events(nrfxlib_sys::NRF_POLLIN, SocketDirection::In, token)
+            .await
+    }
+
+    /// Wait until the socket can accept data to send, using [nrfxlib_sys::nrf_poll].
+    ///
+    /// An error or a closed connection also counts as writable, so the next send call returns it.
+    pub async fn writable(&self, token: &CancellationToken) -> Result<(), Error> {
+        self.wait_for_events(nrfxlib_sys::NRF_POLLOUT, SocketDirection::Out, token)
+            .await
+    }
+
+    /// Check which of the given poll events are ready on the socket, without waiting
+    pub(crate) fn poll_events(&self, events: u32) -> Result<u32, Error> {
+        let mut pollfd = nrfxlib_sys::nrf_pollfd {
+            fd: self.fd,
+            events: events as _,
+            revents: 0,
+        };
+
+        if unsafe { nrfxlib_sys::nrf_poll(&mut pollfd, 1, 0) } == -1 {
+            return Err(Error::NrfError(get_last_error()));
+        }
+
+        Ok(pollfd.revents as u32)
+    }
+
+    async fn wait_for_events(
+        &self,
+        events: u32,
+        direction: SocketDirection,
+        token: &CancellationToken,
+    ) -> Result<(), Error> {
+        token.bind_to_current_task().await;
+        let lost_count = NETWORK_LOST_COUNT.load(Ordering::SeqCst);
+
+        let _waker_guard = SocketWakerGuard::new(self.fd, direction);
+        core::future::poll_fn(|cx| {
+            if token.is_cancelled() {
+                return Poll::Ready(Err(Error::OperationCancelled));
+            }
+
+            if network_lost_since(lost_count) {
+                return Poll::Ready(Err(Error::NetworkDown));
+            }
+
+            self.check_valid()?;
+
+            // Register first so an event that comes in right after the poll still wakes us
+            register_socket_waker(cx.waker().clone(), self.fd, direction)?;
+
+            let revents = self.poll_events(events)?;
+            let ready_events = events
+                | nrfxlib_sys::NRF_POLLERR
+                | nrfxlib_sys::NRF_POLLHUP
+                | nrfxlib_sys::NRF_POLLNVAL;
+
+            match revents & ready_events {
+                0 => Poll::Pending,
+                _ => Poll::Ready(Ok(())),
+            }
+        })
+        .await
+    }
+
+    /// Call the [nrfxlib_sys::nrf_send] in an async fashion
+    pub async fn write(&self, buffer: &[u8], token: &CancellationToken) -> Result<usize, Error> {
+        token.bind_to_current_task().await;
+        let lost_count = NETWORK_LOST_COUNT.load(Ordering::SeqCst);
+
+        let _waker_guard = SocketWakerGuard::new(self.fd, SocketDirection::Out);
+        core::future::poll_fn(|cx| {
+            #[cfg(feature = "defmt")]
+            defmt::trace!("Sending with socket {}", self.fd);
+
+            if token.is_cancelled() {
+                return Poll::Ready(Err(Error::OperationCancelled));
             }
+
+            if network_lost_since(lost_count) {
+                return Poll::Ready(Err(Error::NetworkDown));
+            }
+
+            self.check_valid()?;
+
+            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::Out)?;
+
+            self.send(buffer)
         })
         .await
     }
 
+    /// Call the [nrfxlib_sys::nrf_send] once without waiting.
+    /// Returns 0 if the modem can't take any data right now.
+    pub fn try_write(&self, buffer: &[u8]) -> Result<usize, Error> {
+        self.check_valid()?;
+
+        match self.send(buffer) {
+            Poll::Ready(result) => result,
+            Poll::Pending => Ok(0),
+        }
+    }
+
+    fn send(&self, buffer: &[u8]) -> Poll<Result<usize, Error>> {
+        let mut send_result =
+            unsafe { nrfxlib_sys::nrf_send(self.fd, buffer.as_ptr() as *const _, buffer.len(), 0) };
+
+        if send_result == -1 {
+            send_result = get_last_error().abs().neg();
+        }
+
+        #[cfg(feature = "defmt")]
+        defmt::trace!("Send result {}", send_result);
+
+        map_io_result(send_result, buffer.is_empty()).map_ok(|bytes_sent| {
+            self.count_sent(bytes_sent);
+            bytes_sent
+        })
+    }
+
+    /// Send the request and wait for a response, sending the request again when no response comes in time.
+    ///
+    /// The timeout doubles after every retransmission. Returns the length of the response.
+    #[cfg(feature = "embassy-time")]
+    pub async fn request_reply(
+        &self,
+        request: &[u8],
+        response: &mut [u8],
+        timeout: embassy_time::Duration,
+        retries: u8,
+        token: &CancellationToken,
+    ) -> Result<usize, Error> {
+        let mut timeout = timeout;
+
+        for _ in 0..=retries {
+            self.write(request, token).await?;
+
+            match embassy_time::with_timeout(timeout, self.receive(response, token)).await {
+                Ok(result) => return result,
+                Err(_) => timeout *= 2,
+            }
+        }
+
+        Err(Error::Timeout)
+    }
+
     /// Call the [nrfxlib_sys::nrf_recv] in an async fashion
     pub async fn receive(
         &self,
         buffer: &mut [u8],
         token: &CancellationToken,
+    ) -> Result<usize, Error> {
+        self.receive_with_flags(buffer, 0, token).await
+    }
+
+    /// Call the [nrfxlib_sys::nrf_recv] in an async fashion with the given `NRF_MSG_*` flags
+    pub async fn receive_with_flags(
+        &self,
+        buffer: &mut [u8],
+        flags: i32,
+        token: &CancellationToken,
     ) -> Result<usize, Error> {
         token.bind_to_current_task().await;
+        let lost_count = NETWORK_LOST_COUNT.load(Ordering::SeqCst);
 
+        let _waker_guard = SocketWakerGuard::new(self.fd, SocketDirection::In);
         core::future::poll_fn(|cx| {
             #[cfg(feature = "defmt")]
             defmt::trace!("Receiving with socket {}", self.fd);
@@ -471,10 +1108,16 @@ impl Socket {
                 return Poll::Ready(Err(Error::OperationCancelled));
             }
 
-            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::In);
+            if network_lost_since(lost_count) {
+                return Poll::Ready(Err(Error::NetworkDown));
+            }
+
+            self.check_valid()?;
+
+            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::In)?;
 
             let mut receive_result = unsafe {
-                nrfxlib_sys::nrf_recv(self.fd, buffer.as_mut_ptr() as *mut _, buffer.len(), 0)
+                nrfxlib_sys::nrf_recv(self.fd, buffer.as_mut_ptr() as *mut _, buffer.len(), flags)
             };
 
             if receive_result == -1 {
@@ -484,16 +1127,13 @@ impl Socket {
             #[cfg(feature = "defmt")]
             defmt::trace!("Receive result {}", receive_result);
 
-            const NRF_EWOULDBLOCK: isize = -(nrfxlib_sys::NRF_EWOULDBLOCK as isize);
-            const NRF_ENOTCONN: isize = -(nrfxlib_sys::NRF_ENOTCONN as isize);
-
-            match receive_result {
-                0 if !buffer.is_empty() => Poll::Ready(Err(Error::Disconnected)),
-                NRF_ENOTCONN => Poll::Ready(Err(Error::Disconnected)),
-                bytes_received @ 0.. => Poll::Ready(Ok(bytes_received as usize)),
-                NRF_EWOULDBLOCK => Poll::Pending,
-                error => Poll::Ready(Err(Error::NrfError(error))),
-            }
+            map_io_result(receive_result, buffer.is_empty()).map_ok(|bytes_received| {
+                // Peeked data is counted when it's received for real
+                if flags & nrfxlib_sys::NRF_MSG_PEEK as i32 == 0 {
+                    self.count_received(bytes_received);
+                }
+                bytes_received
+            })
         })
         .await
     }
@@ -505,7 +1145,9 @@ impl Socket {
         token: &CancellationToken,
     ) -> Result<(usize, SocketAddr), Error> {
         token.bind_to_current_task().await;
+        let lost_count = NETWORK_LOST_COUNT.load(Ordering::SeqCst);
 
+        let _waker_guard = SocketWakerGuard::new(self.fd, SocketDirection::In);
         core::future::poll_fn(|cx| {
             #[cfg(feature = "defmt")]
             defmt::trace!("Receiving with socket {}", self.fd);
@@ -514,13 +1156,21 @@ impl Socket {
                 return Poll::Ready(Err(Error::OperationCancelled));
             }
 
-            // Big enough to store both ipv4 and ipv6
-            let mut socket_addr_store =
-                [0u8; core::mem::size_of::<nrfxlib_sys::nrf_sockaddr_in6>()];
-            let socket_addr_ptr = socket_addr_store.as_mut_ptr() as *mut nrfxlib_sys::nrf_sockaddr;
-            let mut socket_addr_len = 0u32;
+            if network_lost_since(lost_count) {
+                return Poll::Ready(Err(Error::NetworkDown));
+            }
+
+            self.check_valid()?;
 
-            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::In);
+            // Big enough to store both ipv4 and ipv6, and properly aligned for both
+            let mut socket_addr_store: nrfxlib_sys::nrf_sockaddr_in6 =
+                unsafe { core::mem::zeroed() };
+            let socket_addr_ptr =
+                &mut socket_addr_store as *mut _ as *mut nrfxlib_sys::nrf_sockaddr;
+            // The modem only writes the address if it's told how much space there is
+            let mut socket_addr_len = core::mem::size_of::<nrfxlib_sys::nrf_sockaddr_in6>() as u32;
+
+            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::In)?;
 
             let mut receive_result = unsafe {
                 nrfxlib_sys::nrf_recvfrom(
@@ -540,19 +1190,14 @@ impl Socket {
             #[cfg(feature = "defmt")]
             defmt::trace!("Receive result {}", receive_result);
 
-            const NRF_EWOULDBLOCK: isize = -(nrfxlib_sys::NRF_EWOULDBLOCK as isize);
-            const NRF_ENOTCONN: isize = -(nrfxlib_sys::NRF_ENOTCONN as isize);
-
-            match receive_result {
-                0 if !buffer.is_empty() => Poll::Ready(Err(Error::Disconnected)),
-                NRF_ENOTCONN => Poll::Ready(Err(Error::Disconnected)),
-                bytes_received @ 0.. => Poll::Ready(Ok((bytes_received as usize, {
-                    unsafe { (*socket_addr_ptr).sa_family = self.family as u16 }
-                    NrfSockAddr::from(socket_addr_ptr as *const _).into()
-                }))),
-                NRF_EWOULDBLOCK => Poll::Pending,
-                error => Poll::Ready(Err(Error::NrfError(error))),
-            }
+            map_io_result(receive_result, buffer.is_empty()).map_ok(|bytes_received| {
+                self.count_received(bytes_received);
+                unsafe { (*socket_addr_ptr).sa_family = self.family as u16 }
+                (
+                    bytes_received,
+                    NrfSockAddr::from(socket_addr_ptr as *const _).into(),
+                )
+            })
         })
         .await
     }
@@ -565,7 +1210,9 @@ impl Socket {
         token: &CancellationToken,
     ) -> Result<usize, Error> {
         token.bind_to_current_task().await;
+        let lost_count = NETWORK_LOST_COUNT.load(Ordering::SeqCst);
 
+        let _waker_guard = SocketWakerGuard::new(self.fd, SocketDirection::Out);
         core::future::poll_fn(|cx| {
             #[cfg(feature = "defmt")]
             defmt::trace!("Sending with socket {}", self.fd);
@@ -574,9 +1221,15 @@ impl Socket {
                 return Poll::Ready(Err(Error::OperationCancelled));
             }
 
+            if network_lost_since(lost_count) {
+                return Poll::Ready(Err(Error::NetworkDown));
+            }
+
+            self.check_valid()?;
+
             let addr = NrfSockAddr::from(address);
 
-            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::Out);
+            register_socket_waker(cx.waker().clone(), self.fd, SocketDirection::Out)?;
 
             let mut send_result = unsafe {
                 nrfxlib_sys::nrf_sendto(
@@ -596,16 +1249,10 @@ impl Socket {
             #[cfg(feature = "defmt")]
             defmt::trace!("Sending result {}", send_result);
 
-            const NRF_EWOULDBLOCK: isize = -(nrfxlib_sys::NRF_EWOULDBLOCK as isize);
-            const NRF_ENOTCONN: isize = -(nrfxlib_sys::NRF_ENOTCONN as isize);
-
-            match send_result {
-                0 if !buffer.is_empty() => Poll::Ready(Err(Error::Disconnected)),
-                NRF_ENOTCONN => Poll::Ready(Err(Error::Disconnected)),
-                bytes_received @ 0.. => Poll::Ready(Ok(bytes_received as usize)),
-                NRF_EWOULDBLOCK => Poll::Pending,
-                error => Poll::Ready(Err(Error::NrfError(error))),
-            }
+            map_io_result(send_result, buffer.is_empty()).map_ok(|bytes_sent| {
+                self.count_sent(bytes_sent);
+                bytes_sent
+            })
         })
         .await
     }
@@ -616,7 +1263,7 @@ impl Socket {
         let result = unsafe {
             nrfxlib_sys::nrf_setsockopt(
                 self.fd,
-                nrfxlib_sys::NRF_SOL_SECURE.try_into().unwrap(),
+                option.get_level(),
                 option.get_name(),
                 option.get_value(),
                 length,
@@ -624,12 +1271,34 @@ impl Socket {
         };
 
         if result < 0 {
-            Err(result.into())
+            Err((get_last_error() as i32).into())
         } else {
             Ok(())
         }
     }
 
+    /// Read an integer socket option with the given level and name
+    pub fn get_int_option(&self, level: u32, name: u32) -> Result<i32, SocketOptionError> {
+        let mut value = 0i32;
+        let mut length = core::mem::size_of::<i32>() as u32;
+
+        let result = unsafe {
+            nrfxlib_sys::nrf_getsockopt(
+                self.fd,
+                level as i32,
+                name as i32,
+                &mut value as *mut i32 as *mut core::ffi::c_void,
+                &mut length as *mut u32,
+            )
+        };
+
+        if result < 0 {
+            Err((get_last_error() as i32).into())
+        } else {
+            Ok(value)
+        }
+    }
+
     /// Deactivates the socket and the LTE link.
     /// A normal drop will do the same thing, but blocking.
     pub async fn deactivate(mut self) -> Result<(), Error> {
@@ -640,8 +1309,27 @@ impl Socket {
 
 impl Drop for Socket {
     fn drop(&mut self) {
+        if self.link.is_some() {
+            #[cfg(feature = "defmt")]
+            defmt::warn!(
+                "Socket {} dropped without being deactivated. Use async function `deactivate` to avoid blocking.",
+                self.fd
+            );
+        }
+
+        if !self.split && self.check_valid().is_err() {
+            // The modem library was restarted, so the fd is gone already and may even belong to a new socket
+            OPEN_SOCKETS.fetch_sub(1, Ordering::SeqCst);
+            return;
+        }
+
         if !self.split {
+            // Free the waker slots that are still taken by this fd, so a new socket with the same fd starts clean
+            take_socket_wakers(|fd, _| fd == self.fd);
+
             let e = unsafe { nrfxlib_sys::nrf_close(self.fd) };
+            OPEN_SOCKETS.fetch_sub(1, Ordering::SeqCst);
+            self.update_stats(|stats| *stats = None);
 
             if e == -1 {
                 panic!("{:?}", Error::NrfError(get_last_error()));
@@ -697,14 +1385,46 @@ pub enum SocketOption<'a> {
     TlsPeerVerify(nrfxlib_sys::nrf_sec_peer_verify_t),
     TlsSessionCache(nrfxlib_sys::nrf_sec_session_cache_t),
     TlsTagList(&'a [nrfxlib_sys::nrf_sec_tag_t]),
+    /// Delete the TLS session cache of the socket so the next handshake is a full one
+    TlsSessionCachePurge,
+    /// Set the use of the DTLS connection ID. See the `NRF_SO_SEC_DTLS_CID_*` values.
+    DtlsConnectionId(u32),
+    /// The cipher suites the modem may offer in the handshake. See the `NRF_TLS_*` values.
+    TlsCipherSuiteList(&'a [u32]),
+    /// Route the traffic of the socket over the PDN with the given ID
+    BindToPdn(i32),
+    /// Give a release assistance indication to the network. See the `NRF_RAI_*` values.
+    ReleaseAssistance(i32),
 }
 impl<'a> SocketOption<'a> {
+    pub(crate) fn get_level(&self) -> i32 {
+        match self {
+            SocketOption::TlsHostName(_)
+            | SocketOption::TlsPeerVerify(_)
+            | SocketOption::TlsSessionCache(_)
+            | SocketOption::TlsTagList(_)
+            | SocketOption::TlsSessionCachePurge
+            | SocketOption::DtlsConnectionId(_)
+            | SocketOption::TlsCipherSuiteList(_) => nrfxlib_sys::NRF_SOL_SECURE as i32,
+            SocketOption::BindToPdn(_) | SocketOption::ReleaseAssistance(_) => {
+                nrfxlib_sys::NRF_SOL_SOCKET as i32
+            }
+        }
+    }
+
     pub(crate) fn get_name(&self) -> i32 {
         match self {
             SocketOption::TlsHostName(_) => nrfxlib_sys::NRF_SO_SEC_HOSTNAME as i32,
             SocketOption::TlsPeerVerify(_) => nrfxlib_sys::NRF_SO_SEC_PEER_VERIFY as i32,
             SocketOption::TlsSessionCache(_) => nrfxlib_sys::NRF_SO_SEC_SESSION_CACHE as i32,
             SocketOption::TlsTagList(_) => nrfxlib_sys::NRF_SO_SEC_TAG_LIST as i32,
+            SocketOption::TlsSessionCachePurge => {
+                nrfxlib_sys::NRF_SO_SEC_SESSION_CACHE_PURGE as i32
+            }
+            SocketOption::DtlsConnectionId(_) => nrfxlib_sys::NRF_SO_SEC_DTLS_CID as i32,
+            SocketOption::TlsCipherSuiteList(_) => nrfxlib_sys::NRF_SO_SEC_CIPHERSUITE_LIST as i32,
+            SocketOption::BindToPdn(_) => nrfxlib_sys::NRF_SO_BINDTOPDN as i32,
+            SocketOption::ReleaseAssistance(_) => nrfxlib_sys::NRF_SO_RAI as i32,
         }
     }
 
@@ -714,6 +1434,12 @@ impl<'a> SocketOption<'a> {
             SocketOption::TlsPeerVerify(x) => x as *const _ as *const core::ffi::c_void,
             SocketOption::TlsSessionCache(x) => x as *const _ as *const core::ffi::c_void,
             SocketOption::TlsTagList(x) => x.as_ptr() as *const core::ffi::c_void,
+            // The value is ignored by the modem, but it must be a valid int
+            SocketOption::TlsSessionCachePurge => &0i32 as *const _ as *const core::ffi::c_void,
+            SocketOption::DtlsConnectionId(x) => x as *const _ as *const core::ffi::c_void,
+            SocketOption::TlsCipherSuiteList(x) => x.as_ptr() as *const core::ffi::c_void,
+            SocketOption::BindToPdn(x) => x as *const _ as *const core::ffi::c_void,
+            SocketOption::ReleaseAssistance(x) => x as *const _ as *const core::ffi::c_void,
         }
     }
 
@@ -723,6 +1449,11 @@ impl<'a> SocketOption<'a> {
             SocketOption::TlsPeerVerify(x) => core::mem::size_of_val(x) as u32,
             SocketOption::TlsSessionCache(x) => core::mem::size_of_val(x) as u32,
             SocketOption::TlsTagList(x) => core::mem::size_of_val(*x) as u32,
+            SocketOption::TlsSessionCachePurge => core::mem::size_of::<i32>() as u32,
+            SocketOption::DtlsConnectionId(x) => core::mem::size_of_val(x) as u32,
+            SocketOption::TlsCipherSuiteList(x) => core::mem::size_of_val(*x) as u32,
+            SocketOption::BindToPdn(x) => core::mem::size_of_val(x) as u32,
+            SocketOption::ReleaseAssistance(x) => core::mem::size_of_val(x) as u32,
         }
     }
 }
@@ -746,19 +1477,43 @@ pub enum SocketOptionError {
     OutOfMemory,
     // Insufficient resources are available in the system to complete the call.
     OutOfResources,
+    /// The option needs a connected socket, e.g. [ReleaseAssistance::NoData]
+    NotConnected,
+    /// Another nrf errno, with the positive value
+    Other(i32),
+}
+
+impl SocketOptionError {
+    /// The nrf errno value this error was created from
+    pub fn errno(&self) -> i32 {
+        (match self {
+            SocketOptionError::InvalidFileDescriptor => nrfxlib_sys::NRF_EBADF,
+            SocketOptionError::TimeoutTooBig => nrfxlib_sys::NRF_EDOM,
+            SocketOptionError::InvalidOption => nrfxlib_sys::NRF_EINVAL,
+            SocketOptionError::AlreadyConnected => nrfxlib_sys::NRF_EISCONN,
+            SocketOptionError::UnsupportedOption => nrfxlib_sys::NRF_ENOPROTOOPT,
+            SocketOptionError::NotASocket => nrfxlib_sys::NRF_ENOTSOCK,
+            SocketOptionError::OutOfMemory => nrfxlib_sys::NRF_ENOMEM,
+            SocketOptionError::OutOfResources => nrfxlib_sys::NRF_ENOBUFS,
+            SocketOptionError::NotConnected => nrfxlib_sys::NRF_EDESTADDRREQ,
+            SocketOptionError::Other(errno) => *errno as u32,
+        }) as i32
+    }
 }
 
 impl From<i32> for SocketOptionError {
     fn from(errno: i32) -> Self {
         match errno.unsigned_abs() {
             nrfxlib_sys::NRF_EBADF => SocketOptionError::InvalidFileDescriptor,
+            nrfxlib_sys::NRF_EDOM => SocketOptionError::TimeoutTooBig,
             nrfxlib_sys::NRF_EINVAL => SocketOptionError::InvalidOption,
             nrfxlib_sys::NRF_EISCONN => SocketOptionError::AlreadyConnected,
             nrfxlib_sys::NRF_ENOPROTOOPT => SocketOptionError::UnsupportedOption,
             nrfxlib_sys::NRF_ENOTSOCK => SocketOptionError::NotASocket,
             nrfxlib_sys::NRF_ENOMEM => SocketOptionError::OutOfMemory,
             nrfxlib_sys::NRF_ENOBUFS => SocketOptionError::OutOfResources,
-            _ => panic!("Unknown error code: {}", errno),
+            nrfxlib_sys::NRF_EDESTADDRREQ => SocketOptionError::NotConnected,
+            errno => SocketOptionError::Other(errno as i32),
         }
     }
 }
@@ -768,11 +1523,55 @@ const ATOMIC_U8_INIT: AtomicU8 = AtomicU8::new(0);
 static ACTIVE_SPLIT_SOCKETS: [AtomicU8; nrfxlib_sys::NRF_MODEM_MAX_SOCKET_COUNT as usize] =
     [ATOMIC_U8_INIT; nrfxlib_sys::NRF_MODEM_MAX_SOCKET_COUNT as usize];
 
+/// The halves that were given to be reunited don't come from the same socket. They are given back unchanged.
+///
+/// It can be turned into [Error::SocketHalvesMismatch] with `?`, which drops the halves.
+pub struct ReuniteError<R, W>(pub R, pub W);
+
+impl<R, W> core::fmt::Debug for ReuniteError<R, W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ReuniteError(..)")
+    }
+}
+
+impl<R, W> core::fmt::Display for ReuniteError<R, W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("socket halves mismatch")
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<R, W> defmt::Format for ReuniteError<R, W> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "ReuniteError(..)")
+    }
+}
+
+impl<R, W> From<ReuniteError<R, W>> for Error {
+    fn from(_: ReuniteError<R, W>) -> Self {
+        Error::SocketHalvesMismatch
+    }
+}
+
 pub struct SplitSocketHandle {
     inner: Option<Socket>,
     index: usize,
 }
 
+// The owned halves are made to be moved into other tasks, so they must stay `Send` and `'static`.
+// The socket only holds the file descriptor, and the modem lib can use it from any context.
+const _: () = {
+    const fn assert_send_static<T: Send + 'static>() {}
+
+    assert_send_static::<crate::OwnedTcpReadStream>();
+    assert_send_static::<crate::OwnedTcpWriteStream>();
+    assert_send_static::<crate::OwnedUdpReceiveSocket>();
+    assert_send_static::<crate::OwnedUdpSendSocket>();
+    assert_send_static::<crate::OwnedDtlsReceiveSocket>();
+    assert_send_static::<crate::OwnedDtlsSendSocket>();
+    assert_send_static::<crate::SharedSocket>();
+};
+
 impl SplitSocketHandle {
     pub async fn deactivate(mut self) -> Result<(), Error> {
         let mut inner = self.inner.take().unwrap();
@@ -787,10 +1586,59 @@ impl SplitSocketHandle {
         Ok(())
     }
 
-    fn get_new_spot() -> usize {
+    /// Put the two halves of a split socket back together into one socket.
+    ///
+    /// Returns both halves in the error if they don't come from the same socket.
+    pub async fn reunite(
+        mut self,
+        mut other: SplitSocketHandle,
+    ) -> Result<Socket, ReuniteError<SplitSocketHandle, SplitSocketHandle>> {
+        if self.index != other.index || self.fd != other.fd {
+            return Err(ReuniteError(self, other));
+        }
+
+        let mut inner = self.inner.take().unwrap();
+        let other_inner = other.inner.take().unwrap();
+
+        // Both handles are gone now, so the spot can be reused
+        ACTIVE_SPLIT_SOCKETS[self.index].store(0, Ordering::SeqCst);
+        inner.split = false;
+
+        // The other socket is still marked as split, so this only releases its LTE link and leaves the fd open.
+        // The reunited socket keeps its own link, so this is never the last link and the modem isn't touched,
+        // which means that this can't fail.
+        let _ = other_inner.deactivate().await;
+
+        Ok(inner)
+    }
+
+    /// Create another handle to the same socket, with its own LTE link
+    pub(crate) async fn duplicate(&self) -> Result<SplitSocketHandle, Error> {
+        let link = LteLink::new().await?;
+        ACTIVE_SPLIT_SOCKETS[self.index].fetch_add(1, Ordering::SeqCst);
+
+        Ok(SplitSocketHandle {
+            inner: Some(Socket {
+                fd: self.fd,
+                family: self.family,
+                link: Some(link),
+                split: true,
+                max_segment_size: self.max_segment_size,
+                generation: self.generation,
+            }),
+            index: self.index,
+        })
+    }
+
+    /// The spot of the handle in the [ACTIVE_SPLIT_SOCKETS]
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+
+    fn get_new_spot(handles: u8) -> usize {
         for (index, count) in ACTIVE_SPLIT_SOCKETS.iter().enumerate() {
             if count
-                .compare_exchange(0, 2, Ordering::SeqCst, Ordering::SeqCst)
+                .compare_exchange(0, handles, Ordering::SeqCst, Ordering::SeqCst)
                 .is_ok()
             {
                 return index;
@@ -819,3 +1667,51 @@ impl Drop for SplitSocketHandle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn errno(errno: u32) -> isize {
+        -(errno as isize)
+    }
+
+    #[test]
+    fn end_of_stream_is_disconnected() {
+        assert!(matches!(
+            map_io_result(0, false),
+            Poll::Ready(Err(Error::Disconnected))
+        ));
+        assert!(matches!(
+            map_io_result(errno(nrfxlib_sys::NRF_ENOTCONN), false),
+            Poll::Ready(Err(Error::Disconnected))
+        ));
+    }
+
+    #[test]
+    fn reset_is_not_disconnected() {
+        assert!(matches!(
+            map_io_result(errno(nrfxlib_sys::NRF_ECONNRESET), false),
+            Poll::Ready(Err(Error::ConnectionReset))
+        ));
+    }
+
+    #[test]
+    fn other_results() {
+        // Nothing was asked for, so nothing is not the end of the stream
+        assert!(matches!(map_io_result(0, true), Poll::Ready(Ok(0))));
+        assert!(matches!(map_io_result(12, false), Poll::Ready(Ok(12))));
+        assert!(matches!(
+            map_io_result(errno(nrfxlib_sys::NRF_EWOULDBLOCK), false),
+            Poll::Pending
+        ));
+        assert!(matches!(
+            map_io_result(errno(nrfxlib_sys::NRF_ETIMEDOUT), false),
+            Poll::Ready(Err(Error::Timeout))
+        ));
+        assert!(matches!(
+            map_io_result(errno(nrfxlib_sys::NRF_EHOSTUNREACH), false),
+            Poll::Ready(Err(Error::NrfError(e))) if e == errno(nrfxlib_sys::NRF_EHOSTUNREACH)
+        ));
+    }
+}
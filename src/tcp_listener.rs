@@ -0,0 +1,105 @@
+use crate::{
+    error::Error,
+    socket::{Socket, SocketFamily, SocketProtocol, SocketType},
+    CancellationToken, LteLink, TcpStream,
+};
+use no_std_net::{SocketAddr, ToSocketAddrs};
+
+/// The amount of incoming connections that can wait to be accepted
+const BACKLOG: i32 = 1;
+
+/// A TCP socket that accepts incoming connections.
+///
+/// Whether the device can be reached from outside depends on the network.
+/// Most operators don't forward incoming connections, unless e.g. a static IP or a private APN is used.
+pub struct TcpListener {
+    inner: Socket,
+    local_addr: SocketAddr,
+}
+
+impl TcpListener {
+    /// Bind a new listening socket to the given address, e.g. `0.0.0.0:8080` to listen on all interfaces
+    pub async fn bind(addr: impl ToSocketAddrs) -> Result<Self, Error> {
+        Self::bind_with_cancellation(addr, &Default::default()).await
+    }
+
+    /// Bind a new listening socket to the given address, e.g. `0.0.0.0:8080` to listen on all interfaces
+    pub async fn bind_with_cancellation(
+        addr: impl ToSocketAddrs,
+        token: &CancellationToken,
+    ) -> Result<Self, Error> {
+        let mut last_error = None;
+        let addrs = addr.to_socket_addrs().map_err(|_| Error::DnsFailure)?;
+        let lte_link = LteLink::new().await?;
+
+        for addr in addrs {
+            token.as_result()?;
+
+            let family = match addr {
+                SocketAddr::V4(_) => SocketFamily::Ipv4,
+                SocketAddr::V6(_) => SocketFamily::Ipv6,
+            };
+
+            let socket = Socket::create(family, SocketType::Stream, SocketProtocol::Tcp).await?;
+
+            match unsafe { socket.bind(addr, token).await }.and_then(|_| socket.listen(BACKLOG)) {
+                Ok(_) => {
+                    lte_link.deactivate().await?;
+                    return Ok(TcpListener {
+                        inner: socket,
+                        local_addr: addr,
+                    });
+                }
+                Err(e) => {
+                    // Only the last error is returned, so log every failed attempt
+                    #[cfg(feature = "defmt")]
+                    defmt::warn!(
+                        "Listening on {:?} failed: {}",
+                        defmt::Debug2Format(&addr),
+                        e
+                    );
+
+                    last_error = Some(e);
+                    socket.deactivate().await?;
+                }
+            }
+        }
+
+        lte_link.deactivate().await?;
+        Err(last_error.unwrap_or(Error::NoAddresses))
+    }
+
+    /// Wait for an incoming connection and accept it
+    pub async fn accept(&self) -> Result<TcpStream, Error> {
+        self.accept_with_cancellation(&Default::default()).await
+    }
+
+    /// Wait for an incoming connection and accept it
+    pub async fn accept_with_cancellation(
+        &self,
+        token: &CancellationToken,
+    ) -> Result<TcpStream, Error> {
+        let (socket, peer_addr) = self.inner.accept(token).await?;
+
+        Ok(TcpStream::from_connected(socket, peer_addr))
+    }
+
+    /// Get the raw underlying file descriptor
+    pub fn as_raw_fd(&self) -> i32 {
+        self.inner.as_raw_fd()
+    }
+
+    /// The address that was given to [Self::bind]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Deactivates the socket and the LTE link.
+    /// A normal drop will do the same thing, but blocking.
+    ///
+    /// The streams that were accepted stay open.
+    pub async fn deactivate(self) -> Result<(), Error> {
+        self.inner.deactivate().await?;
+        Ok(())
+    }
+}
@@ -1,19 +1,373 @@
 use crate::{
     error::Error,
-    socket::{Socket, SocketFamily, SocketProtocol, SocketType, SplitSocketHandle},
-    CancellationToken, LteLink,
+    socket::{
+        ReleaseAssistance, ReuniteError, Socket, SocketFamily, SocketOption, SocketProtocol,
+        SocketStats, SocketType, SplitSocketHandle,
+    },
+    AddressFamily, CancellationToken, LteLink, SharedSocket,
 };
-use no_std_net::ToSocketAddrs;
+use arrayvec::ArrayVec;
+#[cfg(feature = "embassy-time")]
+use core::future::Future;
+use no_std_net::{SocketAddr, ToSocketAddrs};
+
+/// The maximum amount of resolved addresses that a connect tries
+const MAX_ADDRS: usize = 8;
+
+/// A builder for TCP connections with more options than [TcpStream::connect]
+///
+/// ```rust,ignore
+/// let stream = TcpConnectConfig::new()
+///     .retries(3, Duration::from_secs(1))
+///     .pdn(pdn.pdn_id())
+///     .connect(addr)
+///     .await?;
+/// ```
+#[derive(Default)]
+pub struct TcpConnectConfig<'a> {
+    pdn_id: Option<u8>,
+    family: AddressFamily,
+    family_only: bool,
+    #[cfg(feature = "embassy-time")]
+    per_address_timeout: Option<embassy_time::Duration>,
+    #[cfg(feature = "embassy-time")]
+    retries: u8,
+    #[cfg(feature = "embassy-time")]
+    backoff: embassy_time::Duration,
+    #[cfg(feature = "embassy-time")]
+    happy_eyeballs: Option<embassy_time::Duration>,
+    token: Option<&'a CancellationToken>,
+}
+
+impl<'a> TcpConnectConfig<'a> {
+    /// Create a new config that connects like [TcpStream::connect]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give up on each resolved address after the timeout. By default an address is tried for as long as the modem does.
+    ///
+    /// When an address times out, the next one is tried. This way a dead IPv6 address doesn't block the
+    /// connection over a working IPv4 address, e.g. together with [Self::prefer_family].
+    /// If the last address times out, [Error::Timeout] is returned.
+    #[cfg(feature = "embassy-time")]
+    pub fn timeout(mut self, per_address_timeout: embassy_time::Duration) -> Self {
+        self.per_address_timeout = Some(per_address_timeout);
+        self
+    }
+
+    /// Try the connect again when it fails with a transient error, at most `retries` times.
+    /// By default there are no retries.
+    ///
+    /// Every attempt resolves the address again and goes over all the resulting addresses.
+    /// Before the first retry the `backoff` is waited, and the wait doubles for every retry after that.
+    /// Only errors for which [Error::is_transient] returns true are retried, any other error is returned right away.
+    #[cfg(feature = "embassy-time")]
+    pub fn retries(mut self, retries: u8, backoff: embassy_time::Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Only connect to the resolved addresses of the given family. By default both families are used.
+    ///
+    /// This is useful on networks where one of the two families is broken.
+    /// If there are no addresses of the family, [Error::NoAddresses] is returned.
+    pub fn family(mut self, family: AddressFamily) -> Self {
+        self.family = family;
+        self.family_only = true;
+        self
+    }
+
+    /// Try the resolved addresses of the given family first, and the addresses of the other family after that.
+    /// Within a family the order of the DNS answer is kept.
+    ///
+    /// Unlike [Self::family], this still connects when the network or the server only has the other family.
+    pub fn prefer_family(mut self, family: AddressFamily) -> Self {
+        self.family = family;
+        self.family_only = false;
+        self
+    }
+
+    /// Try IPv6 and IPv4 at the same time (RFC 8305, happy eyeballs). By default the addresses are tried one after the other.
+    ///
+    /// The family of the first address is tried first, which can be picked with [Self::prefer_family].
+    /// If it hasn't connected after the `head_start`, the other family is tried next to it.
+    /// The first connection that is made is kept and the other attempt is stopped and its socket closed.
+    /// Within a family the addresses are tried one after the other with the [Self::timeout],
+    /// so there are never more than two sockets in use. RFC 8305 recommends a head start of 250 ms.
+    ///
+    /// When only one family is resolved, this makes no difference.
+    #[cfg(feature = "embassy-time")]
+    pub fn happy_eyeballs(mut self, head_start: embassy_time::Duration) -> Self {
+        self.happy_eyeballs = Some(head_start);
+        self
+    }
+
+    /// Route the traffic of the stream over the PDN with the given ID. By default the default PDN is used.
+    ///
+    /// See [crate::PdnContext] for creating an extra PDN. The default PDN has ID 0.
+    pub fn pdn(mut self, pdn_id: u8) -> Self {
+        self.pdn_id = Some(pdn_id);
+        self
+    }
+
+    /// Set the token that can cancel the connect, including the wait between the retries
+    pub fn cancellation(mut self, token: &'a CancellationToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Connect a TCP stream with this config to the given address.
+    ///
+    /// The resolved addresses are tried one after the other, at most 8 of them.
+    pub async fn connect(&self, addr: impl ToSocketAddrs) -> Result<TcpStream, Error> {
+        let default_token = CancellationToken::new();
+        let token = self.token.unwrap_or(&default_token);
+
+        let addrs = self.resolve(&addr)?;
+        let lte_link = LteLink::new().await?;
+        let result = self.connect_addrs(&addrs, token).await;
+        #[cfg(feature = "embassy-time")]
+        let result = self.retry(result, &addr, token).await;
+        lte_link.deactivate().await?;
+
+        result
+    }
+
+    /// Connect again for as long as the connect fails with a transient error and there are retries left
+    #[cfg(feature = "embassy-time")]
+    async fn retry(
+        &self,
+        mut result: Result<TcpStream, Error>,
+        addr: &impl ToSocketAddrs,
+        token: &CancellationToken,
+    ) -> Result<TcpStream, Error> {
+        for attempt in 0..self.retries {
+            match &result {
+                Err(e) if e.is_transient() => {}
+                _ => break,
+            }
+
+            // The delay doubles after every attempt, up to 1024 times the backoff
+            token
+                .sleep(self.backoff * 2u32.pow(attempt.min(10) as u32))
+                .await?;
+
+            result = match self.resolve(addr) {
+                Ok(addrs) => self.connect_addrs(&addrs, token).await,
+                Err(e) => Err(e),
+            };
+        }
+
+        result
+    }
+
+    /// Resolve the address into the addresses that are tried, in the order they are tried
+    fn resolve(&self, addr: &impl ToSocketAddrs) -> Result<ArrayVec<SocketAddr, MAX_ADDRS>, Error> {
+        let resolved = addr.to_socket_addrs().map_err(|_| Error::DnsFailure)?;
+
+        let mut addrs = ArrayVec::<SocketAddr, MAX_ADDRS>::new();
+        let mut others = ArrayVec::<SocketAddr, MAX_ADDRS>::new();
+        for addr in resolved {
+            match self.family.matches(&addr.ip()) {
+                true if !addrs.is_full() => addrs.push(addr),
+                false if !self.family_only && !others.is_full() => others.push(addr),
+                _ => {}
+            }
+        }
+
+        // The preferred family goes first, and the addresses that don't fit anymore aren't tried
+        let room = addrs.remaining_capacity();
+        addrs.extend(others.into_iter().take(room));
+
+        Ok(addrs)
+    }
+
+    /// Make one attempt at connecting to the resolved addresses
+    async fn connect_addrs(
+        &self,
+        addrs: &[SocketAddr],
+        token: &CancellationToken,
+    ) -> Result<TcpStream, Error> {
+        #[cfg(feature = "embassy-time")]
+        if let Some(head_start) = self.happy_eyeballs {
+            return self.race_families(addrs, head_start, token).await;
+        }
+
+        self.connect_each(addrs.iter().copied(), token).await
+    }
+
+    /// Connect to the addresses of both families at the same time, with a head start for the family of the first address
+    #[cfg(feature = "embassy-time")]
+    async fn race_families(
+        &self,
+        addrs: &[SocketAddr],
+        head_start: embassy_time::Duration,
+        token: &CancellationToken,
+    ) -> Result<TcpStream, Error> {
+        use futures::future::Either;
+
+        let preferred_ipv6 = match addrs.first() {
+            Some(addr) => addr.is_ipv6(),
+            None => return Err(Error::NoAddresses),
+        };
+
+        let of_family = |ipv6: bool| {
+            addrs
+                .iter()
+                .copied()
+                .filter(move |addr| addr.is_ipv6() == ipv6)
+        };
+
+        if of_family(!preferred_ipv6).next().is_none() {
+            return self.connect_each(of_family(preferred_ipv6), token).await;
+        }
+
+        // Every family gets its own token, so the attempt that loses can be stopped
+        // and deactivate its socket instead of closing it with a blocking drop
+        let preferred_token = CancellationToken::new();
+        let fallback_token = CancellationToken::new();
+
+        let race = async {
+            let preferred = self.connect_each(of_family(preferred_ipv6), &preferred_token);
+            futures::pin_mut!(preferred);
+
+            match embassy_time::with_timeout(head_start, preferred.as_mut()).await {
+                Ok(Ok(stream)) => return Ok(stream),
+                // The preferred family already failed, so there's nothing to race against
+                Ok(Err(_)) => {
+                    return self
+                        .connect_each(of_family(!preferred_ipv6), &fallback_token)
+                        .await
+                }
+                Err(_) => {}
+            }
+
+            let fallback = self.connect_each(of_family(!preferred_ipv6), &fallback_token);
+            futures::pin_mut!(fallback);
+
+            match futures::future::select(preferred, fallback).await {
+                Either::Left((Ok(stream), fallback)) => {
+                    Self::stop_attempt(&fallback_token, fallback).await?;
+                    Ok(stream)
+                }
+                Either::Right((Ok(stream), preferred)) => {
+                    Self::stop_attempt(&preferred_token, preferred).await?;
+                    Ok(stream)
+                }
+                Either::Left((Err(_), fallback)) => fallback.await,
+                Either::Right((Err(_), preferred)) => preferred.await,
+            }
+        };
+        futures::pin_mut!(race);
+
+        // The token of the caller stops both attempts
+        let cancelled = token.cancelled();
+        futures::pin_mut!(cancelled);
+
+        match futures::future::select(race, cancelled).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), race)) => {
+                preferred_token.cancel();
+                fallback_token.cancel();
+                race.await
+            }
+        }
+    }
+
+    /// Stop an attempt that lost the race and wait for it to deactivate its socket.
+    /// If it has connected in the meantime, that stream is deactivated as well.
+    #[cfg(feature = "embassy-time")]
+    async fn stop_attempt(
+        token: &CancellationToken,
+        attempt: impl Future<Output = Result<TcpStream, Error>>,
+    ) -> Result<(), Error> {
+        token.cancel();
+
+        match attempt.await {
+            Ok(stream) => stream.deactivate().await,
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Try to connect to the addresses one after the other
+    async fn connect_each(
+        &self,
+        addrs: impl Iterator<Item = SocketAddr>,
+        token: &CancellationToken,
+    ) -> Result<TcpStream, Error> {
+        let mut last_error = None;
+
+        for addr in addrs {
+            token.as_result()?;
+
+            let family = match addr {
+                SocketAddr::V4(_) => SocketFamily::Ipv4,
+                SocketAddr::V6(_) => SocketFamily::Ipv6,
+            };
+
+            let socket = Socket::create(family, SocketType::Stream, SocketProtocol::Tcp).await?;
+            if let Some(pdn_id) = self.pdn_id {
+                socket.set_option(SocketOption::BindToPdn(pdn_id as i32))?;
+            }
+
+            // A connect that is stopped halfway leaves the socket in an unknown state,
+            // but the socket is deactivated then anyways
+            let connect = unsafe { socket.connect(addr, token) };
+            #[cfg(feature = "embassy-time")]
+            let result = match self.per_address_timeout {
+                Some(timeout) => embassy_time::with_timeout(timeout, connect)
+                    .await
+                    .unwrap_or(Err(Error::Timeout)),
+                None => connect.await,
+            };
+            #[cfg(not(feature = "embassy-time"))]
+            let result = connect.await;
+
+            match result {
+                Ok(_) => {
+                    return Ok(TcpStream {
+                        inner: socket,
+                        peer_addr: addr,
+                    });
+                }
+                Err(e) => {
+                    // Only the last error is returned, so log every failed attempt
+                    #[cfg(feature = "defmt")]
+                    defmt::warn!(
+                        "Connecting to {:?} failed: {}",
+                        defmt::Debug2Format(&addr),
+                        e
+                    );
+
+                    last_error = Some(e);
+                    socket.deactivate().await?;
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(Error::NoAddresses))
+    }
+}
 
 /// A TCP stream that is connected to another endpoint
+///
+/// The modem has no TCP keepalive option, so a connection that is silently dropped by the network
+/// is only noticed at the next write. Long-lived connections need a heartbeat at the application level.
 pub struct TcpStream {
     inner: Socket,
+    peer_addr: SocketAddr,
 }
 
 macro_rules! impl_receive {
     () => {
         /// Try fill the given buffer with the data that has been received. The written part of the
         /// buffer is returned.
+        ///
+        /// When the peer has closed its side of the connection and all data has been received,
+        /// [Error::Disconnected] is returned. A connection that was reset gives [Error::ConnectionReset] instead.
+        /// If the modem loses its network registration while waiting, [Error::NetworkDown] is returned.
         pub async fn receive<'buf>(&self, buf: &'buf mut [u8]) -> Result<&'buf mut [u8], Error> {
             self.receive_with_cancellation(buf, &Default::default())
                 .await
@@ -21,12 +375,16 @@ macro_rules! impl_receive {
 
         /// Try fill the given buffer with the data that has been received. The written part of the
         /// buffer is returned.
+        ///
+        /// When the peer has closed its side of the connection and all data has been received,
+        /// [Error::Disconnected] is returned. A connection that was reset gives [Error::ConnectionReset] instead.
+        /// If the modem loses its network registration while waiting, [Error::NetworkDown] is returned.
         pub async fn receive_with_cancellation<'buf>(
             &self,
             buf: &'buf mut [u8],
             token: &CancellationToken,
         ) -> Result<&'buf mut [u8], Error> {
-            let max_receive_len = 1024.min(buf.len());
+            let max_receive_len = self.socket().max_segment_size().min(buf.len());
             let received_bytes = self
                 .socket()
                 .receive(&mut buf[..max_receive_len], token)
@@ -34,11 +392,43 @@ macro_rules! impl_receive {
             Ok(&mut buf[..received_bytes])
         }
 
+        /// Try fill the given buffer with the data that has been received, without removing it from the socket.
+        /// The next receive will return the same data again. The written part of the buffer is returned.
+        ///
+        /// The modem can't report how much data is waiting (there is no `FIONREAD`),
+        /// but peeking with a large buffer shows it, up to the size of the buffer.
+        pub async fn peek<'buf>(&self, buf: &'buf mut [u8]) -> Result<&'buf mut [u8], Error> {
+            self.peek_with_cancellation(buf, &Default::default()).await
+        }
+
+        /// Try fill the given buffer with the data that has been received, without removing it from the socket.
+        /// The next receive will return the same data again. The written part of the buffer is returned.
+        pub async fn peek_with_cancellation<'buf>(
+            &self,
+            buf: &'buf mut [u8],
+            token: &CancellationToken,
+        ) -> Result<&'buf mut [u8], Error> {
+            if buf.is_empty() {
+                return Ok(buf);
+            }
+
+            let max_receive_len = self.socket().max_segment_size().min(buf.len());
+            let received_bytes = self
+                .socket()
+                .receive_with_flags(
+                    &mut buf[..max_receive_len],
+                    nrfxlib_sys::NRF_MSG_PEEK as i32,
+                    token,
+                )
+                .await?;
+            Ok(&mut buf[..received_bytes])
+        }
+
         /// Fill the entire buffer with data that has been received. This will wait as long as necessary to fill up the
         /// buffer.
         ///
         /// If there's an error while receiving, then the error is returned as well as the part of the buffer that was
-        /// partially filled with received data.
+        /// partially filled with received data. When the peer closes the connection first, that error is [Error::Disconnected].
         pub async fn receive_exact<'buf>(
             &self,
             buf: &'buf mut [u8],
@@ -51,7 +441,7 @@ macro_rules! impl_receive {
         /// buffer.
         ///
         /// If there's an error while receiving, then the error is returned as well as the part of the buffer that was
-        /// partially filled with received data.
+        /// partially filled with received data. When the peer closes the connection first, that error is [Error::Disconnected].
         pub async fn receive_exact_with_cancellation<'buf>(
             &self,
             buf: &'buf mut [u8],
@@ -71,12 +461,72 @@ macro_rules! impl_receive {
 
             Ok(())
         }
+
+        /// Try fill the given buffer with the data that has been received. The written part of the
+        /// buffer is returned.
+        ///
+        /// If nothing is received within the timeout, [Error::Timeout] is returned.
+        #[cfg(feature = "embassy-time")]
+        pub async fn receive_with_timeout<'buf>(
+            &self,
+            buf: &'buf mut [u8],
+            timeout: embassy_time::Duration,
+        ) -> Result<&'buf mut [u8], Error> {
+            let token = CancellationToken::new();
+            token
+                .with_timeout(timeout, self.receive_with_cancellation(buf, &token))
+                .await
+        }
+
+        /// Fill the entire buffer with data that has been received, waiting at most the timeout for all of it.
+        ///
+        /// If there's an error while receiving or the timeout passes ([Error::Timeout]), then the error is returned
+        /// as well as the part of the buffer that was partially filled with received data.
+        #[cfg(feature = "embassy-time")]
+        pub async fn receive_exact_with_timeout<'buf>(
+            &self,
+            buf: &'buf mut [u8],
+            timeout: embassy_time::Duration,
+        ) -> Result<(), (Error, &'buf mut [u8])> {
+            let token = CancellationToken::new();
+            match token
+                .run_with_timeout(timeout, self.receive_exact_with_cancellation(buf, &token))
+                .await
+            {
+                (Err((Error::OperationCancelled, received)), true) => {
+                    Err((Error::Timeout, received))
+                }
+                (result, _) => result,
+            }
+        }
+
+        /// Wait until there is data to receive, without receiving it.
+        ///
+        /// An error or a closed connection also counts, so the next receive call returns it.
+        pub async fn readable(&self) -> Result<(), Error> {
+            self.readable_with_cancellation(&Default::default()).await
+        }
+
+        /// Wait until there is data to receive, without receiving it.
+        ///
+        /// An error or a closed connection also counts, so the next receive call returns it.
+        pub async fn readable_with_cancellation(
+            &self,
+            token: &CancellationToken,
+        ) -> Result<(), Error> {
+            self.socket().readable(token).await
+        }
     };
 }
 
 macro_rules! impl_write {
     () => {
         /// Write the entire buffer to the stream
+        ///
+        /// This returns once the modem has taken the data into its own buffers, not when it has been sent.
+        /// The modem can wait for the acknowledgement of the peer with the `NRF_MSG_WAITACK` send flag,
+        /// but that makes the send block the whole executor until then, so this crate doesn't use it and has no flush.
+        /// To be sure the data has arrived before e.g. deactivating the stream, wait for a response of the peer.
         pub async fn write(&self, buf: &[u8]) -> Result<(), Error> {
             self.write_with_cancellation(buf, &Default::default()).await
         }
@@ -91,7 +541,10 @@ macro_rules! impl_write {
 
             while written_bytes < buf.len() {
                 // We can't write very huge chunks because then the socket can't process it all at once
-                let max_write_len = 1024.min(buf.len() - written_bytes);
+                let max_write_len = self
+                    .socket()
+                    .max_segment_size()
+                    .min(buf.len() - written_bytes);
                 written_bytes += self
                     .socket()
                     .write(&buf[written_bytes..][..max_write_len], token)
@@ -100,6 +553,166 @@ macro_rules! impl_write {
 
             Ok(())
         }
+
+        /// Write all the buffers to the stream, one after the other, e.g. a header and a body.
+        ///
+        /// The small buffers are gathered in an internal buffer of `N` bytes, so they are given to the modem
+        /// in as few calls as possible. When everything fits in `N` bytes and the max segment size,
+        /// that is one call. Buffers that are too big to gather are written directly without copying.
+        ///
+        /// ```rust,ignore
+        /// stream.write_vectored::<256>(&[header, body]).await?;
+        /// ```
+        pub async fn write_vectored<const N: usize>(&self, bufs: &[&[u8]]) -> Result<(), Error> {
+            self.write_vectored_with_cancellation::<N>(bufs, &Default::default())
+                .await
+        }
+
+        /// Write all the buffers to the stream, one after the other, gathering the small buffers
+        /// in an internal buffer of `N` bytes
+        pub async fn write_vectored_with_cancellation<const N: usize>(
+            &self,
+            bufs: &[&[u8]],
+            token: &CancellationToken,
+        ) -> Result<(), Error> {
+            let limit = self.socket().max_segment_size().min(N);
+            let mut gathered = arrayvec::ArrayVec::<u8, N>::new();
+
+            for buf in bufs {
+                let mut buf = *buf;
+
+                while !buf.is_empty() {
+                    if gathered.is_empty() && buf.len() >= limit {
+                        self.write_with_cancellation(buf, token).await?;
+                        break;
+                    }
+
+                    let len = (limit - gathered.len()).min(buf.len());
+                    // This can't fail, because the limit is at most N
+                    let _ = gathered.try_extend_from_slice(&buf[..len]);
+                    buf = &buf[len..];
+
+                    if gathered.len() == limit {
+                        self.write_with_cancellation(&gathered, token).await?;
+                        gathered.clear();
+                    }
+                }
+            }
+
+            self.write_with_cancellation(&gathered, token).await
+        }
+
+        /// Write as much of the buffer as the modem takes right now, without waiting.
+        ///
+        /// Returns how many bytes were written, which is 0 if the modem can't take any data at the moment.
+        /// Use [Self::writable] to wait until the write can be tried again.
+        pub fn try_write(&self, buf: &[u8]) -> Result<usize, Error> {
+            let max_write_len = self.socket().max_segment_size().min(buf.len());
+            self.socket().try_write(&buf[..max_write_len])
+        }
+
+        /// Write the entire buffer to the stream, or return [Error::Timeout] if that takes longer than the timeout.
+        ///
+        /// On a timeout, an unknown part of the buffer may have been written already.
+        #[cfg(feature = "embassy-time")]
+        pub async fn write_with_timeout(
+            &self,
+            buf: &[u8],
+            timeout: embassy_time::Duration,
+        ) -> Result<(), Error> {
+            let token = CancellationToken::new();
+            token
+                .with_timeout(timeout, self.write_with_cancellation(buf, &token))
+                .await
+        }
+
+        /// Format the arguments into a buffer of `N` bytes and write that to the stream.
+        ///
+        /// If the formatted text doesn't fit in the buffer, nothing is written and [Error::BufferTooSmall] is returned.
+        ///
+        /// ```rust,ignore
+        /// stream.write_formatted::<128>(format_args!("GET {} HTTP/1.1\r\n", path)).await?;
+        /// ```
+        pub async fn write_formatted<const N: usize>(
+            &self,
+            args: core::fmt::Arguments<'_>,
+        ) -> Result<(), Error> {
+            self.write_formatted_with_cancellation::<N>(args, &Default::default())
+                .await
+        }
+
+        /// Format the arguments into a buffer of `N` bytes and write that to the stream.
+        ///
+        /// If the formatted text doesn't fit in the buffer, nothing is written and [Error::BufferTooSmall] is returned.
+        pub async fn write_formatted_with_cancellation<const N: usize>(
+            &self,
+            args: core::fmt::Arguments<'_>,
+            token: &CancellationToken,
+        ) -> Result<(), Error> {
+            let mut buffer = arrayvec::ArrayString::<N>::new();
+            core::fmt::Write::write_fmt(&mut buffer, args)
+                .map_err(|_| Error::BufferTooSmall(None))?;
+
+            self.write_with_cancellation(buffer.as_bytes(), token).await
+        }
+
+        /// Wait until the socket can accept data to send, without sending anything.
+        ///
+        /// An error or a closed connection also counts, so the next send call returns it.
+        pub async fn writable(&self) -> Result<(), Error> {
+            self.writable_with_cancellation(&Default::default()).await
+        }
+
+        /// Wait until the socket can accept data to send, without sending anything.
+        ///
+        /// An error or a closed connection also counts, so the next send call returns it.
+        pub async fn writable_with_cancellation(
+            &self,
+            token: &CancellationToken,
+        ) -> Result<(), Error> {
+            self.socket().writable(token).await
+        }
+    };
+}
+
+#[cfg(feature = "embedded-io")]
+macro_rules! impl_embedded_io_read {
+    ($ty:ty) => {
+        impl embedded_io_async::ErrorType for $ty {
+            type Error = Error;
+        }
+
+        impl embedded_io_async::Read for $ty {
+            async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+                match self.receive(buf).await {
+                    Ok(received) => Ok(received.len()),
+                    // A closed connection is the end of the stream
+                    Err(Error::Disconnected) => Ok(0),
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "embedded-io")]
+macro_rules! impl_embedded_io_write {
+    ($ty:ty) => {
+        impl embedded_io_async::Write for $ty {
+            async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+                // Same chunk limit as the inherent write, but only a single chunk is written
+                let max_write_len = self.socket().max_segment_size().min(buf.len());
+                self.socket()
+                    .write(&buf[..max_write_len], &Default::default())
+                    .await
+            }
+
+            async fn flush(&mut self) -> Result<(), Self::Error> {
+                // The modem has no user facing send buffer to flush. Waiting for the acknowledgement
+                // would need the blocking `NRF_MSG_WAITACK` send flag, so there is nothing to wait for here
+                Ok(())
+            }
+        }
     };
 }
 
@@ -110,38 +723,30 @@ impl TcpStream {
     }
 
     /// Connect a TCP stream to the given address
+    ///
+    /// The stream holds its own [LteLink], so the link temporarily created here is released without
+    /// turning LTE off. When the last stream is dropped the link can go down though. To keep it up across
+    /// multiple connections in a row, keep your own [LteLink] alive for as long as it's needed:
+    ///
+    /// ```rust,ignore
+    /// let link = LteLink::new().await?;
+    /// link.wait_for_link().await?;
+    /// // All connects in between reuse the registration of the link
+    /// let stream = TcpStream::connect(addr).await?;
+    /// ```
     pub async fn connect_with_cancellation(
         addr: impl ToSocketAddrs,
         token: &CancellationToken,
     ) -> Result<Self, Error> {
-        let mut last_error = None;
-        let lte_link = LteLink::new().await?;
-        let addrs = addr.to_socket_addrs().unwrap();
-
-        for addr in addrs {
-            token.as_result()?;
-
-            let family = match addr {
-                no_std_net::SocketAddr::V4(_) => SocketFamily::Ipv4,
-                no_std_net::SocketAddr::V6(_) => SocketFamily::Ipv6,
-            };
-
-            let socket = Socket::create(family, SocketType::Stream, SocketProtocol::Tcp).await?;
-
-            match unsafe { socket.connect(addr, token).await } {
-                Ok(_) => {
-                    lte_link.deactivate().await?;
-                    return Ok(TcpStream { inner: socket });
-                }
-                Err(e) => {
-                    last_error = Some(e);
-                    socket.deactivate().await?;
-                }
-            }
-        }
+        TcpConnectConfig::new()
+            .cancellation(token)
+            .connect(addr)
+            .await
+    }
 
-        lte_link.deactivate().await?;
-        Err(last_error.take().unwrap())
+    /// Wrap a socket that is already connected, e.g. one that was accepted by a [crate::TcpListener]
+    pub(crate) fn from_connected(inner: Socket, peer_addr: SocketAddr) -> Self {
+        TcpStream { inner, peer_addr }
     }
 
     /// Get the raw underlying file descriptor for when you need to interact with the nrf libraries directly
@@ -149,22 +754,96 @@ impl TcpStream {
         self.inner.as_raw_fd()
     }
 
+    /// Get the amount of data that has been sent and received on this stream since it was connected.
+    ///
+    /// The halves of a split stream add to the same counters.
+    pub fn stats(&self) -> SocketStats {
+        self.inner.stats()
+    }
+
+    /// Tell the network how much more data is expected on this stream, so the modem can go to sleep sooner.
+    ///
+    /// E.g. set [ReleaseAssistance::Last] right before the last write.
+    /// [ReleaseAssistance::NoData] needs a connected socket, otherwise [crate::SocketOptionError::NotConnected] is returned.
+    pub fn set_release_assistance(&self, rai: ReleaseAssistance) -> Result<(), Error> {
+        self.inner.set_release_assistance(rai)
+    }
+
     fn socket(&self) -> &Socket {
         &self.inner
     }
 
-    /// Split the stream into an owned read and write half
+    /// The address of the endpoint this stream is connected to
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Set the maximum amount of bytes that is given to the modem in a single receive or write call.
+    /// The default is 1024. Larger writes are sent in multiple chunks.
+    /// Raise it to receive larger messages in one call.
+    ///
+    /// The split halves inherit this value.
+    pub fn with_max_segment_size(mut self, size: usize) -> Self {
+        self.inner.set_max_segment_size(size);
+        self
+    }
+
+    /// The maximum amount of bytes that is given to the modem in a single receive or write call
+    pub fn max_segment_size(&self) -> usize {
+        self.inner.max_segment_size()
+    }
+
+    /// Turn the stream into a [SharedSocket] that can be used from multiple tasks
+    pub fn into_shared(self) -> SharedSocket {
+        SharedSocket::new(self.inner, true)
+    }
+
+    /// Split the stream into an owned read and write half.
+    ///
+    /// The halves are `Send` and `'static`, so they can be moved into separate tasks, e.g. spawned embassy tasks.
+    /// To use both halves in the same task, [Self::split] doesn't need the extra [LteLink] of the second half.
     pub async fn split_owned(self) -> Result<(OwnedTcpReadStream, OwnedTcpWriteStream), Error> {
         let (read_split, write_split) = self.inner.split().await?;
 
         Ok((
-            OwnedTcpReadStream { stream: read_split },
+            OwnedTcpReadStream {
+                stream: read_split,
+                peer_addr: self.peer_addr,
+            },
             OwnedTcpWriteStream {
                 stream: write_split,
+                peer_addr: self.peer_addr,
             },
         ))
     }
 
+    /// Put the owned halves of a split stream back together.
+    ///
+    /// If the halves don't come from the same stream, they are given back in the [ReuniteError].
+    pub async fn reunite(
+        read: OwnedTcpReadStream,
+        write: OwnedTcpWriteStream,
+    ) -> Result<Self, ReuniteError<OwnedTcpReadStream, OwnedTcpWriteStream>> {
+        let (read_peer_addr, write_peer_addr) = (read.peer_addr, write.peer_addr);
+
+        match read.stream.reunite(write.stream).await {
+            Ok(inner) => Ok(TcpStream {
+                inner,
+                peer_addr: read_peer_addr,
+            }),
+            Err(ReuniteError(read, write)) => Err(ReuniteError(
+                OwnedTcpReadStream {
+                    stream: read,
+                    peer_addr: read_peer_addr,
+                },
+                OwnedTcpWriteStream {
+                    stream: write,
+                    peer_addr: write_peer_addr,
+                },
+            )),
+        }
+    }
+
     /// Split the stream into a borrowed read and write half
     pub fn split(&self) -> (TcpReadStream<'_>, TcpWriteStream<'_>) {
         (
@@ -178,6 +857,9 @@ impl TcpStream {
 
     /// Deactivates the socket and the LTE link.
     /// A normal drop will do the same thing, but blocking.
+    ///
+    /// With the `defmt` feature, a warning is logged when a stream is dropped without being deactivated,
+    /// so the blocking drops can be found during development.
     pub async fn deactivate(self) -> Result<(), Error> {
         self.inner.deactivate().await?;
         Ok(())
@@ -194,6 +876,11 @@ impl<'a> TcpReadStream<'a> {
         &self.stream.inner
     }
 
+    /// The address of the endpoint this stream is connected to
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.stream.peer_addr
+    }
+
     impl_receive!();
 }
 
@@ -207,12 +894,18 @@ impl<'a> TcpWriteStream<'a> {
         &self.stream.inner
     }
 
+    /// The address of the endpoint this stream is connected to
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.stream.peer_addr
+    }
+
     impl_write!();
 }
 
 /// An owned read half of a TCP stream
 pub struct OwnedTcpReadStream {
     stream: SplitSocketHandle,
+    peer_addr: SocketAddr,
 }
 
 impl OwnedTcpReadStream {
@@ -220,6 +913,11 @@ impl OwnedTcpReadStream {
         &self.stream
     }
 
+    /// The address of the endpoint this stream is connected to
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
     impl_receive!();
 
     /// Deactivates the socket and the LTE link.
@@ -233,6 +931,7 @@ impl OwnedTcpReadStream {
 /// An owned write half of a TCP stream
 pub struct OwnedTcpWriteStream {
     stream: SplitSocketHandle,
+    peer_addr: SocketAddr,
 }
 
 impl OwnedTcpWriteStream {
@@ -240,6 +939,11 @@ impl OwnedTcpWriteStream {
         &self.stream
     }
 
+    /// The address of the endpoint this stream is connected to
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
     impl_write!();
 
     /// Deactivates the socket and the LTE link.
@@ -249,3 +953,157 @@ impl OwnedTcpWriteStream {
         Ok(())
     }
 }
+
+#[cfg(feature = "embedded-io")]
+mod embedded_io_impls {
+    use super::*;
+
+    impl_embedded_io_read!(TcpStream);
+    impl_embedded_io_write!(TcpStream);
+
+    impl_embedded_io_read!(TcpReadStream<'_>);
+    impl_embedded_io_read!(OwnedTcpReadStream);
+
+    impl embedded_io_async::ErrorType for TcpWriteStream<'_> {
+        type Error = Error;
+    }
+    impl_embedded_io_write!(TcpWriteStream<'_>);
+
+    impl embedded_io_async::ErrorType for OwnedTcpWriteStream {
+        type Error = Error;
+    }
+    impl_embedded_io_write!(OwnedTcpWriteStream);
+}
+
+/// A reader that buffers the data of an owned TCP read half, so it can be read in lines or other delimited parts.
+///
+/// The internal buffer is `N` bytes. The modem is asked for as much data as fits in the buffer at once.
+/// A line that is longer than the buffer can't be read and results in [Error::BufferTooSmall].
+pub struct BufferedTcpReader<const N: usize> {
+    stream: OwnedTcpReadStream,
+    buffer: [u8; N],
+    start: usize,
+    end: usize,
+}
+
+impl<const N: usize> BufferedTcpReader<N> {
+    /// Create a new buffered reader around the read half
+    pub fn new(stream: OwnedTcpReadStream) -> Self {
+        Self {
+            stream,
+            buffer: [0; N],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Get the read half back. Any data that is still in the buffer is lost.
+    pub fn into_inner(self) -> OwnedTcpReadStream {
+        self.stream
+    }
+
+    /// The data that has been received, but not yet read
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer[self.start..self.end]
+    }
+
+    /// Return the buffered data, receiving new data first if the buffer is empty.
+    /// Call [Self::consume] to mark the data as read.
+    pub async fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        self.fill_buf_with_cancellation(&Default::default()).await
+    }
+
+    /// Return the buffered data, receiving new data first if the buffer is empty.
+    /// Call [Self::consume] to mark the data as read.
+    pub async fn fill_buf_with_cancellation(
+        &mut self,
+        token: &CancellationToken,
+    ) -> Result<&[u8], Error> {
+        if self.start == self.end {
+            self.start = 0;
+            self.end = self
+                .stream
+                .receive_with_cancellation(&mut self.buffer, token)
+                .await?
+                .len();
+        }
+
+        Ok(self.buffer())
+    }
+
+    /// Mark the given amount of buffered bytes as read
+    pub fn consume(&mut self, amount: usize) {
+        self.start = (self.start + amount).min(self.end);
+    }
+
+    /// Read up to and including the next delimiter. The returned slice points into the internal buffer.
+    ///
+    /// When the connection is closed before the delimiter is found, [Error::Disconnected] is returned
+    /// and the data that was received is left in the [buffer](Self::buffer).
+    /// When the buffer is full without a delimiter, [Error::BufferTooSmall] is returned.
+    pub async fn read_until(&mut self, delimiter: u8) -> Result<&[u8], Error> {
+        self.read_until_with_cancellation(delimiter, &Default::default())
+            .await
+    }
+
+    /// Read up to and including the next delimiter. The returned slice points into the internal buffer.
+    ///
+    /// When the connection is closed before the delimiter is found, [Error::Disconnected] is returned
+    /// and the data that was received is left in the [buffer](Self::buffer).
+    /// When the buffer is full without a delimiter, [Error::BufferTooSmall] is returned.
+    pub async fn read_until_with_cancellation(
+        &mut self,
+        delimiter: u8,
+        token: &CancellationToken,
+    ) -> Result<&[u8], Error> {
+        // Everything before this index is known to not be the delimiter
+        let mut searched = self.start;
+
+        loop {
+            if let Some(position) = self.buffer[searched..self.end]
+                .iter()
+                .position(|byte| *byte == delimiter)
+            {
+                let part_start = self.start;
+                self.start = searched + position + 1;
+                return Ok(&self.buffer[part_start..self.start]);
+            }
+
+            // Move the unread data to the front to make room for more
+            if self.start > 0 {
+                self.buffer.copy_within(self.start..self.end, 0);
+                self.end -= self.start;
+                self.start = 0;
+            }
+            searched = self.end;
+
+            if self.end == N {
+                return Err(Error::BufferTooSmall(None));
+            }
+
+            self.end += self
+                .stream
+                .receive_with_cancellation(&mut self.buffer[self.end..], token)
+                .await?
+                .len();
+        }
+    }
+
+    /// Read the next line, including the line ending.
+    ///
+    /// See [Self::read_until] for the error cases. If the line is not valid UTF-8, [Error::Utf8Error] is returned.
+    pub async fn read_line(&mut self) -> Result<&str, Error> {
+        self.read_line_with_cancellation(&Default::default()).await
+    }
+
+    /// Read the next line, including the line ending.
+    ///
+    /// See [Self::read_until] for the error cases. If the line is not valid UTF-8, [Error::Utf8Error] is returned.
+    pub async fn read_line_with_cancellation(
+        &mut self,
+        token: &CancellationToken,
+    ) -> Result<&str, Error> {
+        let line = self.read_until_with_cancellation(b'\n', token).await?;
+        Ok(core::str::from_utf8(line)?)
+    }
+}
@@ -1,13 +1,19 @@
 use crate::{
     error::Error,
-    socket::{Socket, SocketFamily, SocketProtocol, SocketType, SplitSocketHandle},
-    CancellationToken, LteLink,
+    socket::{
+        ReleaseAssistance, Socket, SocketFamily, SocketOption, SocketProtocol, SocketStats,
+        SocketType, SplitSocketHandle,
+    },
+    CancellationToken, LteLink, SharedSocket,
 };
 use no_std_net::{SocketAddr, ToSocketAddrs};
 
 /// A socket that sends and receives UDP messages
+///
+/// The modem library has no IP multicast socket options, so multicast groups can't be joined.
 pub struct UdpSocket {
     inner: Socket,
+    local_addr: SocketAddr,
 }
 
 macro_rules! impl_receive_from {
@@ -32,6 +38,23 @@ macro_rules! impl_receive_from {
             let (received_len, addr) = self.socket().receive_from(buf, token).await?;
             Ok((&mut buf[..received_len], addr))
         }
+
+        /// Wait until there is data to receive, without receiving it.
+        ///
+        /// An error or a closed connection also counts, so the next receive call returns it.
+        pub async fn readable(&self) -> Result<(), Error> {
+            self.readable_with_cancellation(&Default::default()).await
+        }
+
+        /// Wait until there is data to receive, without receiving it.
+        ///
+        /// An error or a closed connection also counts, so the next receive call returns it.
+        pub async fn readable_with_cancellation(
+            &self,
+            token: &CancellationToken,
+        ) -> Result<(), Error> {
+            self.socket().readable(token).await
+        }
     };
 }
 
@@ -52,6 +75,71 @@ macro_rules! impl_send_to {
         ) -> Result<(), Error> {
             self.socket().send_to(buf, addr, token).await.map(|_| ())
         }
+
+        /// Wait until the socket can accept data to send, without sending anything.
+        ///
+        /// An error or a closed connection also counts, so the next send call returns it.
+        pub async fn writable(&self) -> Result<(), Error> {
+            self.writable_with_cancellation(&Default::default()).await
+        }
+
+        /// Wait until the socket can accept data to send, without sending anything.
+        ///
+        /// An error or a closed connection also counts, so the next send call returns it.
+        pub async fn writable_with_cancellation(
+            &self,
+            token: &CancellationToken,
+        ) -> Result<(), Error> {
+            self.socket().writable(token).await
+        }
+    };
+}
+
+macro_rules! impl_receive {
+    () => {
+        /// Try to fill the given buffer with a message from the connected address.
+        /// The part of the buffer that was filled is returned.
+        ///
+        /// Only works after [UdpSocket::connect]. Messages from other addresses are filtered out by the modem.
+        pub async fn receive<'buf>(&self, buf: &'buf mut [u8]) -> Result<&'buf mut [u8], Error> {
+            self.receive_with_cancellation(buf, &Default::default())
+                .await
+        }
+
+        /// Try to fill the given buffer with a message from the connected address.
+        /// The part of the buffer that was filled is returned.
+        ///
+        /// Only works after [UdpSocket::connect]. Messages from other addresses are filtered out by the modem.
+        pub async fn receive_with_cancellation<'buf>(
+            &self,
+            buf: &'buf mut [u8],
+            token: &CancellationToken,
+        ) -> Result<&'buf mut [u8], Error> {
+            let received_len = self.socket().receive(buf, token).await?;
+            Ok(&mut buf[..received_len])
+        }
+    };
+}
+
+macro_rules! impl_send {
+    () => {
+        /// Send the given buffer to the connected address
+        ///
+        /// Only works after [UdpSocket::connect].
+        pub async fn send(&self, buf: &[u8]) -> Result<(), Error> {
+            self.send_with_cancellation(buf, &Default::default()).await
+        }
+
+        /// Send the given buffer to the connected address
+        ///
+        /// Only works after [UdpSocket::connect].
+        pub async fn send_with_cancellation(
+            &self,
+            buf: &[u8],
+            token: &CancellationToken,
+        ) -> Result<(), Error> {
+            self.socket().write(buf, token).await.map(|_| ())
+        }
     };
 }
 
@@ -65,10 +153,36 @@ impl UdpSocket {
     pub async fn bind_with_cancellation(
         addr: impl ToSocketAddrs,
         token: &CancellationToken,
+    ) -> Result<Self, Error> {
+        Self::bind_inner(addr, None, token).await
+    }
+
+    /// Bind a new socket to the given address, with its traffic routed over the PDN with the given ID.
+    ///
+    /// See [crate::PdnContext] for creating an extra PDN. The default PDN has ID 0.
+    pub async fn bind_on_pdn(addr: impl ToSocketAddrs, pdn_id: u8) -> Result<Self, Error> {
+        Self::bind_on_pdn_with_cancellation(addr, pdn_id, &Default::default()).await
+    }
+
+    /// Bind a new socket to the given address, with its traffic routed over the PDN with the given ID.
+    ///
+    /// See [crate::PdnContext] for creating an extra PDN. The default PDN has ID 0.
+    pub async fn bind_on_pdn_with_cancellation(
+        addr: impl ToSocketAddrs,
+        pdn_id: u8,
+        token: &CancellationToken,
+    ) -> Result<Self, Error> {
+        Self::bind_inner(addr, Some(pdn_id), token).await
+    }
+
+    async fn bind_inner(
+        addr: impl ToSocketAddrs,
+        pdn_id: Option<u8>,
+        token: &CancellationToken,
     ) -> Result<Self, Error> {
         let mut last_error = None;
+        let addrs = addr.to_socket_addrs().map_err(|_| Error::DnsFailure)?;
         let lte_link = LteLink::new().await?;
-        let addrs = addr.to_socket_addrs().unwrap();
 
         for addr in addrs {
             token.as_result()?;
@@ -79,13 +193,23 @@ impl UdpSocket {
             };
 
             let socket = Socket::create(family, SocketType::Datagram, SocketProtocol::Udp).await?;
+            if let Some(pdn_id) = pdn_id {
+                socket.set_option(SocketOption::BindToPdn(pdn_id as i32))?;
+            }
 
             match unsafe { socket.bind(addr, token).await } {
                 Ok(_) => {
                     lte_link.deactivate().await?;
-                    return Ok(UdpSocket { inner: socket });
+                    return Ok(UdpSocket {
+                        inner: socket,
+                        local_addr: addr,
+                    });
                 }
                 Err(e) => {
+                    // Only the last error is returned, so log every failed attempt
+                    #[cfg(feature = "defmt")]
+                    defmt::warn!("Binding to {:?} failed: {}", defmt::Debug2Format(&addr), e);
+
                     last_error = Some(e);
                     socket.deactivate().await?;
                 }
@@ -93,7 +217,7 @@ impl UdpSocket {
         }
 
         lte_link.deactivate().await?;
-        Err(last_error.take().unwrap())
+        Err(last_error.unwrap_or(Error::NoAddresses))
     }
 
     /// Get the raw underlying file descriptor
@@ -101,11 +225,55 @@ impl UdpSocket {
         self.inner.as_raw_fd()
     }
 
+    /// Get the amount of data that has been sent and received on this socket since it was created.
+    ///
+    /// The halves of a split socket add to the same counters.
+    pub fn stats(&self) -> SocketStats {
+        self.inner.stats()
+    }
+
+    /// Tell the network how much more data is expected on this socket, so the modem can go to sleep sooner.
+    ///
+    /// E.g. set [ReleaseAssistance::Last] right before the last send.
+    /// [ReleaseAssistance::NoData] needs a connected socket, otherwise [crate::SocketOptionError::NotConnected] is returned.
+    pub fn set_release_assistance(&self, rai: ReleaseAssistance) -> Result<(), Error> {
+        self.inner.set_release_assistance(rai)
+    }
+
     fn socket(&self) -> &Socket {
         &self.inner
     }
 
-    /// Split the socket into an owned read and write half
+    /// The local address the socket was bound to.
+    ///
+    /// The modem has no way to query the address of a socket, so this is the address that was given to [Self::bind].
+    /// When that had port 0, the port the modem picked can't be known and 0 is returned.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Set the default address of the socket.
+    ///
+    /// After this, [Self::send] sends to this address and [Self::receive] only receives messages coming from it.
+    /// [Self::send_to] and [Self::receive_from] can still be used.
+    ///
+    /// UDP is connectionless, so this returns right away without contacting the address.
+    pub async fn connect(&self, addr: SocketAddr) -> Result<(), Error> {
+        // Safety: A UDP connect finishes immediately, so it can't be cancelled halfway
+        unsafe { self.inner.connect(addr, &Default::default()).await }
+    }
+
+    /// Turn the socket into a [SharedSocket] that can be used from multiple tasks.
+    ///
+    /// The shared socket can only write to the address set with [Self::connect] and doesn't report where a datagram came from.
+    pub fn into_shared(self) -> SharedSocket {
+        SharedSocket::new(self.inner, false)
+    }
+
+    /// Split the socket into an owned read and write half.
+    ///
+    /// The halves are `Send` and `'static`, so they can be moved into separate tasks, e.g. spawned embassy tasks.
+    /// To use both halves in the same task, [Self::split] doesn't need the extra [crate::LteLink] of the second half.
     pub async fn split_owned(self) -> Result<(OwnedUdpReceiveSocket, OwnedUdpSendSocket), Error> {
         let (read_split, write_split) = self.inner.split().await?;
 
@@ -126,7 +294,57 @@ impl UdpSocket {
     }
 
     impl_receive_from!();
+    impl_receive!();
     impl_send_to!();
+    impl_send!();
+
+    /// Send the request and wait for exactly one response, like a CoAP confirmable message.
+    ///
+    /// When no response comes within the timeout, the request is sent again, up to `retries` times.
+    /// The timeout doubles after every retransmission, like in CoAP. If the last attempt times out too,
+    /// [Error::Timeout] is returned. The part of the response buffer that was filled is returned.
+    ///
+    /// The socket must be connected with [Self::connect] first, so the request goes to the peer and only its response is received.
+    #[cfg(feature = "embassy-time")]
+    pub async fn request_reply<'buf>(
+        &self,
+        request: &[u8],
+        response: &'buf mut [u8],
+        timeout: embassy_time::Duration,
+        retries: u8,
+    ) -> Result<&'buf mut [u8], Error> {
+        self.request_reply_with_cancellation(
+            request,
+            response,
+            timeout,
+            retries,
+            &Default::default(),
+        )
+        .await
+    }
+
+    /// Send the request and wait for exactly one response, like a CoAP confirmable message.
+    ///
+    /// When no response comes within the timeout, the request is sent again, up to `retries` times.
+    /// The timeout doubles after every retransmission, like in CoAP. If the last attempt times out too,
+    /// [Error::Timeout] is returned. The part of the response buffer that was filled is returned.
+    ///
+    /// The socket must be connected with [Self::connect] first, so the request goes to the peer and only its response is received.
+    #[cfg(feature = "embassy-time")]
+    pub async fn request_reply_with_cancellation<'buf>(
+        &self,
+        request: &[u8],
+        response: &'buf mut [u8],
+        timeout: embassy_time::Duration,
+        retries: u8,
+        token: &CancellationToken,
+    ) -> Result<&'buf mut [u8], Error> {
+        let response_len = self
+            .inner
+            .request_reply(request, response, timeout, retries, token)
+            .await?;
+        Ok(&mut response[..response_len])
+    }
 
     /// Deactivates the socket and the LTE link.
     /// A normal drop will do the same thing, but blocking.
@@ -147,6 +365,7 @@ impl<'a> UdpReceiveSocket<'a> {
     }
 
     impl_receive_from!();
+    impl_receive!();
 }
 
 /// A borrowed send half of a udp socket
@@ -160,6 +379,7 @@ impl<'a> UdpSendSocket<'a> {
     }
 
     impl_send_to!();
+    impl_send!();
 }
 
 /// An owned receive half of a udp socket
@@ -173,6 +393,7 @@ impl OwnedUdpReceiveSocket {
     }
 
     impl_receive_from!();
+    impl_receive!();
 
     /// Deactivates the socket and the LTE link.
     /// A normal drop will do the same thing, but blocking.
@@ -193,6 +414,7 @@ impl OwnedUdpSendSocket {
     }
 
     impl_send_to!();
+    impl_send!();
 
     /// Deactivates the socket and the LTE link.
     /// A normal drop will do the same thing, but blocking.